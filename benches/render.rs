@@ -0,0 +1,60 @@
+//! Rendering benchmarks on a fixed scene.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use eray::{
+    engine::Engine,
+    graph,
+    prelude::*,
+    shader::graph::{SocketValue, Validated},
+    Building,
+};
+
+use std::path::Path;
+
+const SIZE: u32 = 64;
+
+fn flat_color() -> eray::shader::graph::Graph<Validated> {
+    graph! {
+        inputs,
+        nodes,
+        outputs:
+            "color": (None, SocketValue::IColor(Some(Image::new(1, 1, Color::new(1., 0.5, 0.2))))),
+    }
+    .validate()
+    .unwrap()
+}
+
+fn setup_engine() -> Engine<Building> {
+    let mut cube = Object::load_obj(Path::new("./objects/cube.obj")).unwrap();
+    cube.material = Material::builder(flat_color())
+        .with_output(StandardMaterialOutput::Color, "color")
+        .build()
+        .unwrap();
+
+    let mut engine = Engine::new((SIZE, SIZE), 0, 0).unwrap();
+    engine
+        .scene()
+        .set_camera(Camera {
+            center: Vector::new(0., 0., 5.),
+            width: SIZE,
+            ..Default::default()
+        })
+        .add_light(Light::point(Vector::new(1., 1., 2.)))
+        .add_object(cube.build().unwrap());
+
+    engine
+}
+
+fn render(c: &mut Criterion) {
+    let mut engine = setup_engine();
+
+    c.bench_function("render cube 64x64", |b| {
+        b.iter(|| {
+            engine.render_to_image();
+        })
+    });
+}
+
+criterion_group!(benches, render);
+criterion_main!(benches);
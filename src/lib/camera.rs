@@ -1,6 +1,9 @@
 //! Definition of the camera and its auxilliary data structures.
 
-use crate::{raycasting::Ray, vector::Vector};
+use crate::{
+    raycasting::{Ray, RayDifferential},
+    vector::Vector,
+};
 
 #[derive(Clone, Debug)]
 /// Field of view as angles in radians.
@@ -26,6 +29,11 @@ pub struct Camera {
     pub fov: Fov,
     /// Number of pixels making width-wise.
     pub width: u32,
+    /// Number of pixels making height-wise, derived from the [Fov] when unset.
+    ///
+    /// Setting it to anything else makes for non-square (anamorphic) pixels, see
+    /// [pixel_aspect](Self::pixel_aspect).
+    pub height: Option<u32>,
 
     /// Clipping plane.
     pub z_dist: f32,
@@ -33,8 +41,31 @@ pub struct Camera {
 
 impl Camera {
     /// Get viewport size in pixels.
+    ///
+    /// The height is at least 1 pixel, and falls back to the width on a degenerate [Fov].
     pub fn size(&self) -> (u32, u32) {
-        (self.width, (self.width as f32 / self.fov.ratio()) as u32)
+        let ratio = self.fov.ratio();
+        let height = if let Some(height) = self.height {
+            height
+        } else if ratio.is_normal() && ratio > 0. {
+            (self.width as f32 / ratio) as u32
+        } else {
+            self.width
+        };
+
+        (self.width, height.max(1))
+    }
+
+    /// Width of a pixel relative to its height, 1 for square pixels.
+    pub fn pixel_aspect(&self) -> f32 {
+        let (width, height) = self.size();
+        let ratio = self.fov.ratio();
+
+        if ratio.is_normal() && ratio > 0. {
+            ratio * height as f32 / width as f32
+        } else {
+            1.
+        }
     }
 }
 
@@ -46,6 +77,7 @@ impl Default for Camera {
             up: Vector::new(0., 1., 0.),
             fov: Fov(60., 60.),
             width: 1024,
+            height: None,
             z_dist: 1.,
         }
     }
@@ -74,4 +106,55 @@ impl Camera {
             botleft + horizontal * x + vertical * y - self.center,
         )
     }
+
+    /// Same as [pixel_to_ray](Self::pixel_to_ray), along with the rays going through the points
+    /// one pixel to the right and one pixel up, as given by the camera's [size](Self::size).
+    pub fn pixel_to_ray_differential(&self, x: f32, y: f32) -> RayDifferential {
+        let (width, height) = self.size();
+
+        RayDifferential {
+            center: self.pixel_to_ray(x, y),
+            dx: self.pixel_to_ray(x + 1. / width as f32, y),
+            dy: self.pixel_to_ray(x, y + 1. / height as f32),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn pixel_to_ray_differential() {
+        let camera = Camera {
+            fov: Fov(60., 30.),
+            width: 16,
+            ..Default::default()
+        };
+        let (width, height) = camera.size();
+        assert_eq!((16, 8), (width, height));
+
+        // Point where a ray crosses the viewport plane, in viewport units
+        let on_viewport = |ray: &Ray| *ray.dir() * (camera.z_dist / -ray.dir()[2]);
+
+        let rays = camera.pixel_to_ray_differential(0.25, 0.5);
+        let center = on_viewport(&rays.center);
+
+        // The viewport spans 4 by 2 units
+        let dx = on_viewport(&rays.dx) - center;
+        assert!(
+            dx.approx_eq(&Vector::new(4. / 16., 0., 0.), 1e-5),
+            "Got {dx:?}"
+        );
+        let dy = on_viewport(&rays.dy) - center;
+        assert!(
+            dy.approx_eq(&Vector::new(0., 2. / 8., 0.), 1e-5),
+            "Got {dy:?}"
+        );
+
+        let ray = Ray::from(rays.clone());
+        let (ray_dx, ray_dy) = ray.differentials().unwrap();
+        assert!(ray_dx.dir().approx_eq(rays.dx.dir(), 1e-6));
+        assert!(ray_dy.dir().approx_eq(rays.dy.dir(), 1e-6));
+    }
 }
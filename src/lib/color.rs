@@ -2,7 +2,7 @@
 
 use std::{
     iter::Sum,
-    ops::{Div, Mul},
+    ops::{Div, Mul, Neg},
 };
 
 use ::derive_more::{Add, AddAssign};
@@ -45,6 +45,29 @@ impl Color {
         }
     }
 
+    /// Rec. 709 luminance, assuming linear values.
+    pub fn luminance(&self) -> f32 {
+        0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b
+    }
+
+    /// Encodes a unit normal as a [Color], mapping each coordinate from [-1, 1] to [0, 1].
+    pub fn from_normal(n: Vector<3, f32>) -> Self {
+        Self::new(n[0] * 0.5 + 0.5, n[1] * 0.5 + 0.5, n[2] * 0.5 + 0.5)
+    }
+
+    /// Decodes a normal encoded by [from_normal](Self::from_normal), e.g. a normal map texel,
+    /// back to a unit vector.
+    pub fn decode_normal(&self) -> Vector<3, f32> {
+        Vector::new(self.r * 2. - 1., self.g * 2. - 1., self.b * 2. - 1.).normalize()
+    }
+
+    /// Checks that every channel is within `eps` of the matching one in `other`.
+    pub fn approx_eq(&self, other: &Color, eps: f32) -> bool {
+        (self.r - other.r).abs() <= eps
+            && (self.g - other.g).abs() <= eps
+            && (self.b - other.b).abs() <= eps
+    }
+
     /// Equivalent to subtractive synthesis between two colors.
     pub fn min(&self, other: &Color) -> Self {
         Self {
@@ -53,6 +76,36 @@ impl Color {
             b: self.b.min(other.b),
         }
     }
+
+    /// Converts to hue (in degrees, in the 0..360 range), saturation and value.
+    pub fn to_hsv(&self) -> [f32; 3] {
+        let max = self.r.max(self.g).max(self.b);
+        let chroma = max - self.r.min(self.g).min(self.b);
+
+        let hue = if chroma == 0. {
+            0.
+        } else if max == self.r {
+            60. * ((self.g - self.b) / chroma)
+        } else if max == self.g {
+            60. * ((self.b - self.r) / chroma + 2.)
+        } else {
+            60. * ((self.r - self.g) / chroma + 4.)
+        };
+        let saturation = if max == 0. { 0. } else { chroma / max };
+
+        [hue.rem_euclid(360.), saturation, max]
+    }
+
+    /// Creates a [Color] from hue (in degrees, wrapped to the 0..360 range), saturation and
+    /// value, see [to_hsv](Self::to_hsv).
+    pub fn from_hsv([hue, saturation, value]: [f32; 3]) -> Self {
+        let channel = |n: f32| {
+            let k = (n + hue.rem_euclid(360.) / 60.) % 6.;
+            value - value * saturation * k.min(4. - k).clamp(0., 1.)
+        };
+
+        Self::new(channel(5.), channel(3.), channel(1.))
+    }
 }
 
 impl Mul<f32> for Color {
@@ -67,6 +120,26 @@ impl Mul<f32> for Color {
     }
 }
 
+impl Mul<Color> for f32 {
+    type Output = Color;
+
+    fn mul(self, rhs: Color) -> Self::Output {
+        rhs * self
+    }
+}
+
+impl Neg for Color {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self {
+            r: -self.r,
+            g: -self.g,
+            b: -self.b,
+        }
+    }
+}
+
 impl Mul for Color {
     type Output = Self;
 
@@ -114,3 +187,61 @@ impl From<Color> for f32 {
         (val.r + val.g + val.b) / 3.
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn normal_encoding() {
+        let up = Vector::new(0., 0., 1.);
+        assert_eq!(Color::new(0.5, 0.5, 1.), Color::from_normal(up));
+        assert_eq!(up, Color::from_normal(up).decode_normal());
+
+        let normal = Vector::new(1., -2., 0.5).normalize();
+        assert!(Color::from_normal(normal)
+            .decode_normal()
+            .approx_eq(&normal, 1e-6));
+    }
+
+    #[test]
+    fn approx_eq() {
+        let color = Color::new(0.1, 0.5, 1.);
+
+        assert!(color.approx_eq(&Color::new(0.1 + 1e-6, 0.5, 1. - 1e-6), 1e-5));
+        assert!(color.approx_eq(&color, 0.));
+        assert!(!color.approx_eq(&Color::new(0.1, 0.6, 1.), 1e-5));
+        assert!(!color.approx_eq(&Color::new(0.1, 0.5, f32::NAN), 1e-5));
+    }
+
+    #[test]
+    fn hsv() {
+        assert_eq!(Color::new(1., 0., 0.).to_hsv(), [0., 1., 1.]);
+        assert_eq!(Color::new(0., 0.5, 0.).to_hsv(), [120., 1., 0.5]);
+        assert_eq!(Color::new(0.5, 0.5, 0.5).to_hsv(), [0., 0., 0.5]);
+
+        for color in [
+            Color::new(0.1, 0.5, 1.),
+            Color::new(0.9, 0.2, 0.4),
+            Color::new(0.3, 0.3, 0.),
+            Color::default(),
+        ] {
+            let back = Color::from_hsv(color.to_hsv());
+            assert!(
+                back.approx_eq(&color, 1e-6),
+                "Expected {color:?}, got {back:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn scalar_lhs_mul() {
+        let color = Color::new(0.1, 0.5, 1.);
+        assert_eq!(2. * color, color * 2.);
+    }
+
+    #[test]
+    fn neg() {
+        assert_eq!(-Color::new(0.1, -0.5, 0.), Color::new(-0.1, 0.5, -0.));
+    }
+}
@@ -1,30 +1,204 @@
 //! Actual renderer leveraging the constructs defined in the eray library.
 
-use crate::Building;
+use crate::{shader::graph, Building};
 
 use super::prelude::*;
 
-use std::path::Path;
+use std::{
+    ops::Range,
+    path::Path,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
 
 use rand::prelude::*;
 
+#[derive(Debug, thiserror::Error)]
+/// Possible errors returned by the [Engine].
+pub enum Error {
+    #[error("Invalid render dimensions {width}x{height}, both must be non-zero")]
+    /// Render buffer would be empty.
+    InvalidDimensions {
+        #[allow(missing_docs)]
+        width: u32,
+        #[allow(missing_docs)]
+        height: u32,
+    },
+
+    #[error(transparent)]
+    /// Error while updating a [Material]'s shader graph.
+    Graph(#[from] graph::Error),
+}
+
 /// Render engine.
 pub struct Engine<State> {
     image: Image<Color>,
+    depth: Image<f32>,
+    samples: Image<usize>,
     scene: Scene<State>,
-    bounces: usize,
+    reflection_depth: usize,
+    gi_depth: usize,
     anti_aliasing: usize,
+    adaptive_threshold: Option<f32>,
+    firefly_clamp: Option<f32>,
+    dither: bool,
+    auto_exposure: bool,
+    exposure: f32,
+    time: f32,
+    clay: bool,
+    counters: Counters,
+    stats: RenderStats,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+/// Cost of the last rendered frame, see [Engine::stats].
+pub struct RenderStats {
+    /// Rays cast from the camera, anti-aliasing samples included.
+    pub primary_rays: usize,
+    /// Rays cast toward [Light]s to check whether they are occluded.
+    pub shadow_rays: usize,
+    /// Rays cast along mirror reflections.
+    pub reflection_rays: usize,
+    /// Ray-triangle intersection tests, rays missing an object's bounding box testing none.
+    pub triangles_tested: usize,
+    /// Wall-clock time spent rendering.
+    pub duration: Duration,
+}
+
+#[derive(Debug, Default)]
+/// [RenderStats] counters, updated from the shared references used while casting rays.
+struct Counters {
+    primary_rays: AtomicUsize,
+    shadow_rays: AtomicUsize,
+    reflection_rays: AtomicUsize,
+    triangles_tested: AtomicUsize,
+}
+
+impl Counters {
+    fn increment(counter: &AtomicUsize) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Collect the counts into [RenderStats], resetting them for the next frame.
+    fn take(&self, duration: Duration) -> RenderStats {
+        RenderStats {
+            primary_rays: self.primary_rays.swap(0, Ordering::Relaxed),
+            shadow_rays: self.shadow_rays.swap(0, Ordering::Relaxed),
+            reflection_rays: self.reflection_rays.swap(0, Ordering::Relaxed),
+            triangles_tested: self.triangles_tested.swap(0, Ordering::Relaxed),
+            duration,
+        }
+    }
+}
+
+/// Number of shadow rays cast toward [Light]s with a non-zero `shadow_softness`.
+const SHADOW_SAMPLES: usize = 32;
+
+/// Transmittance under which composited hits are considered opaque, ignoring anything behind.
+const MIN_TRANSMITTANCE: f32 = 1e-3;
+
+#[derive(Clone, Copy, Debug, Default)]
+/// Running average of [Color] samples, updated incrementally instead of dividing a sum so that it
+/// does not drift however many samples are accumulated.
+struct RunningMean {
+    count: usize,
+    mean: Color,
+}
+
+impl RunningMean {
+    fn push(&mut self, sample: Color) {
+        self.count += 1;
+        self.mean += (sample + -self.mean) / self.count as f32;
+    }
+
+    fn mean(&self) -> Color {
+        self.mean
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+/// Number of bounces of each kind a ray went through since leaving the camera.
+struct Bounces {
+    reflection: usize,
+    diffuse: usize,
+}
+
+/// Luminance auto-exposure brings the median pixel to, i.e. middle grey.
+const AUTO_EXPOSURE_KEY: f32 = 0.18;
+
+/// Number of [luminance histogram](Image::luminance_histogram) buckets used for auto-exposure.
+const AUTO_EXPOSURE_BINS: usize = 64;
+
+/// Exposure multiplier bringing the median luminance of the lit pixels of `image` to
+/// [AUTO_EXPOSURE_KEY].
+fn auto_exposure(image: &Image<Color>) -> f32 {
+    let mut histogram = image.luminance_histogram(AUTO_EXPOSURE_BINS);
+
+    // Black pixels, e.g. rays missing every object, all land in the first bucket and would drag
+    // the median down whenever they cover most of the frame
+    let black = image
+        .pixels
+        .iter()
+        .filter(|pixel| pixel.luminance() <= 0.)
+        .count();
+    if let Some(first) = histogram.first_mut() {
+        *first -= black as u32;
+    }
+
+    let total = histogram.iter().sum::<u32>();
+    if total == 0 {
+        return 1.;
+    }
+
+    let mut count = 0;
+    let Some(median) = histogram.iter().position(|&bin| {
+        count += bin;
+        count * 2 >= total
+    }) else {
+        return 1.;
+    };
+
+    let (min, max) = LUMINANCE_HISTOGRAM_RANGE;
+    let log_luminance = min + (median as f32 + 0.5) / AUTO_EXPOSURE_BINS as f32 * (max - min);
+
+    AUTO_EXPOSURE_KEY / log_luminance.exp2()
+}
+
+/// Neutral grey diffuse material used in place of every object's material in clay mode.
+fn clay_material() -> MaterialOutputBundle {
+    MaterialOutputBundle {
+        color: Some(Color::new(0.8, 0.8, 0.8)),
+        diffuse: Some(0.8),
+        specular: Some(0.),
+        specular_power: Some(1.),
+        reflection: Some(0.),
+        alpha: Some(1.),
+        unlit: false,
+    }
 }
 
 impl Engine<Building> {
-    /// Create an Engine with a [default](Default) [Scene] from the given parameters.
-    pub fn new((width, height): (u32, u32), bounces: usize, anti_aliasing: usize) -> Self {
-        Self {
+    /// Create an Engine with a [default](Default) [Scene] from the given parameters, following
+    /// up to `reflection_depth` mirror reflections.
+    ///
+    /// Fails if either dimension is zero.
+    pub fn new(
+        (width, height): (u32, u32),
+        reflection_depth: usize,
+        anti_aliasing: usize,
+    ) -> Result<Self, Error> {
+        if width == 0 || height == 0 {
+            return Err(Error::InvalidDimensions { width, height });
+        }
+
+        Ok(Self {
             image: Image {
                 width,
                 height,
                 pixels: vec![Color::new(0., 0., 0.); (width * height) as usize],
             },
+            depth: Image::new(width, height, f32::INFINITY),
+            samples: Image::new(width, height, 0),
             // scene: Scene::new(Camera {
             //     center: Vector::new(0., 0., 1.),
             //     fov: Fov(60., 60. * (width as f32 / height as f32)),
@@ -32,9 +206,99 @@ impl Engine<Building> {
             //     ..Default::default()
             // }),
             scene: Default::default(),
-            bounces,
+            reflection_depth,
+            gi_depth: 0,
             anti_aliasing,
+            adaptive_threshold: None,
+            firefly_clamp: None,
+            dither: false,
+            auto_exposure: false,
+            exposure: 1.,
+            time: 0.,
+            clay: false,
+            counters: Counters::default(),
+            stats: RenderStats::default(),
+        })
+    }
+
+    /// Get the current scene time.
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    /// Set the scene time for the next frames.
+    ///
+    /// Every object's [Material] is only updated accordingly when rendering, so objects added
+    /// afterwards are also affected, see [update_materials](Self::update_materials).
+    pub fn set_time(&mut self, time: f32) -> &mut Self {
+        self.time = time;
+        self
+    }
+
+    /// [Update](Material::update) every object's [Material] to the current [time](Self::time).
+    ///
+    /// Render methods call it first and panic if it fails, call it beforehand to handle errors.
+    pub fn update_materials(&mut self) -> Result<&mut Self, Error> {
+        for object in self.scene.objects.iter_mut() {
+            object.material.update(self.time)?;
         }
+
+        Ok(self)
+    }
+
+    /// Toggle clay mode, overriding every object's [Material] with a neutral grey diffuse one at
+    /// render time to check lighting independently from texturing.
+    pub fn set_clay(&mut self, clay: bool) -> &mut Self {
+        self.clay = clay;
+        self
+    }
+
+    /// Set the global illumination depth, continuing rays from diffuse hits in a cosine-weighted
+    /// random direction to gather indirect lighting, up to `gi_depth` times. 0 disables it.
+    ///
+    /// Each camera ray only follows a single path, the noise being averaged out over the
+    /// anti-aliasing samples. Mirror reflections are counted separately, see [new](Self::new).
+    /// [Ambient](LightVariant::Ambient) lights are ignored while it is enabled, as they stand in
+    /// for the indirect lighting it gathers.
+    pub fn set_gi_depth(&mut self, gi_depth: usize) -> &mut Self {
+        self.gi_depth = gi_depth;
+        self
+    }
+
+    /// Toggle adaptive anti-aliasing, only supersampling pixels differing from one of their direct
+    /// neighbors by more than `threshold` on any channel after a first 1 sample per pixel pass.
+    pub fn set_adaptive_anti_aliasing(&mut self, threshold: Option<f32>) -> &mut Self {
+        self.adaptive_threshold = threshold;
+        self
+    }
+
+    /// Cap the brightest channel of each camera sample to `max_radiance` before averaging them,
+    /// trading a bit of energy for the removal of "firefly" pixels caused by rare, very bright
+    /// samples.
+    pub fn set_firefly_clamp(&mut self, max_radiance: Option<f32>) -> &mut Self {
+        self.firefly_clamp = max_radiance;
+        self
+    }
+
+    /// Toggle auto-exposure, scaling every rendered frame so that its median luminance ends up
+    /// at middle grey before it gets clamped to the displayable range, see
+    /// [exposure](Self::exposure).
+    pub fn set_auto_exposure(&mut self, auto_exposure: bool) -> &mut Self {
+        self.auto_exposure = auto_exposure;
+        self
+    }
+
+    /// Exposure multiplier applied to the last rendered frame, 1 without
+    /// [auto-exposure](Self::set_auto_exposure).
+    pub fn exposure(&self) -> f32 {
+        self.exposure
+    }
+
+    /// Toggle ordered dithering when quantizing frames saved by
+    /// [render_to_path](Self::render_to_path) to 8 bits, breaking up banding in smooth gradients.
+    pub fn set_dither(&mut self, dither: bool) -> &mut Self {
+        self.dither = dither;
+        self
     }
 
     /// Get the [Scene] to add entities to it.
@@ -42,42 +306,229 @@ impl Engine<Building> {
         &mut self.scene
     }
 
-    /// Render a frame to the inner 1-frame buffer.
+    /// Get the inner 1-frame buffer holding the last rendered frame.
+    ///
+    /// Values are left unclamped (HDR), see [as_vectors](Self::as_vectors) for a plain float
+    /// representation and [Color::clamp] to bring them back to the displayable range.
+    pub fn raw_image(&self) -> &Image<Color> {
+        &self.image
+    }
+
+    /// Get the depth buffer holding the distance from the camera to the nearest primary hit of each
+    /// pixel of the last [rendered](Self::render) frame, or [f32::INFINITY] if nothing was hit.
+    pub fn depth(&self) -> &Image<f32> {
+        &self.depth
+    }
+
+    /// Get the number of camera rays cast for each pixel of the last [rendered](Self::render) frame.
+    pub fn samples(&self) -> &Image<usize> {
+        &self.samples
+    }
+
+    /// Copy the inner 1-frame buffer as an unclamped [Vector] image.
+    pub fn as_vectors(&self) -> Image<Vector<3, f32>> {
+        self.image.clone().convert_image(Vector::from)
+    }
+
+    /// Render a frame to the inner 1-frame buffer, see [raw_image](Self::raw_image).
+    ///
+    /// Progress is logged to the standard output, see [render_to_image](Self::render_to_image)
+    /// for a silent version.
     pub fn render(&mut self) -> &Image<Color> {
+        self.render_frame(true)
+    }
+
+    /// Same as [render](Self::render) without any output, e.g. for benchmarking.
+    pub fn render_to_image(&mut self) -> &Image<Color> {
+        self.render_frame(false)
+    }
+
+    /// Render only the `width` by `height` rectangle starting at pixel (`x`, `y`) to the inner
+    /// 1-frame buffer, leaving the rest of it untouched, e.g. to refresh an edited area.
+    ///
+    /// The rectangle is clipped to the image. Rendered pixels are scaled by the
+    /// [exposure](Self::exposure) of the last full frame instead of computing a new one.
+    pub fn render_region(&mut self, x: u32, y: u32, width: u32, height: u32) -> &Image<Color> {
+        let start = Instant::now();
+        self.update_materials().expect("Failed to update materials");
+        let (image_width, image_height) = self.scene.camera.size();
+        let xs = x.min(image_width)..x.saturating_add(width).min(image_width);
+        let ys = y.min(image_height)..y.saturating_add(height).min(image_height);
+
+        self.render_pixels(xs.clone(), ys.clone(), false);
+
+        if self.exposure != 1. {
+            for y in ys {
+                for x in xs.clone() {
+                    let color = self.image.mod_get(x, y) * self.exposure;
+                    self.image.set(x, y, color);
+                }
+            }
+        }
+
+        self.stats = self.counters.take(start.elapsed());
+
+        &self.image
+    }
+
+    fn render_frame(&mut self, log_progress: bool) -> &Image<Color> {
+        let start = Instant::now();
+        self.update_materials().expect("Failed to update materials");
         let (width, height) = self.scene.camera.size();
 
+        self.render_pixels(0..width, 0..height, log_progress);
+
+        self.exposure = if self.auto_exposure {
+            auto_exposure(&self.image)
+        } else {
+            1.
+        };
+        if self.exposure != 1. {
+            let exposure = self.exposure;
+            self.image
+                .pixels
+                .iter_mut()
+                .for_each(|pixel| *pixel = *pixel * exposure);
+        }
+
+        self.stats = self.counters.take(start.elapsed());
+
+        &self.image
+    }
+
+    /// Render the pixels within the given columns and rows to the inner buffers, before exposure.
+    fn render_pixels(&mut self, xs: Range<u32>, ys: Range<u32>, log_progress: bool) {
         let mut rng = rand::thread_rng();
+        let adaptive_threshold = self.adaptive_threshold.filter(|_| self.anti_aliasing > 0);
 
         let mut step = 0;
-        for y in 0..height {
-            let new_step = ((y as f32 / height as f32) * 100.) as u32 / 10;
-            if new_step > step {
+        for y in ys.clone() {
+            let new_step = (((y - ys.start) as f32 / ys.len() as f32) * 100.) as u32 / 10;
+            if log_progress && new_step > step {
                 step = new_step;
                 println!("{}%", step * 10);
             }
 
-            for x in 0..width {
-                let mut average: Color = self.cast_ray_from_camera(x as f32, y as f32).sum();
-
-                for _ in 0..self.anti_aliasing {
-                    average += self
-                        .cast_ray_from_camera(
-                            x as f32 + rng.gen_range((-1.)..1.),
-                            y as f32 + rng.gen_range((-1.)..1.),
-                        )
-                        .sum();
-                }
-
-                if self.anti_aliasing > 0 {
-                    self.image
-                        .set(x, y, (average / self.anti_aliasing as f32).clamp());
+            for x in xs.clone() {
+                let (color, depth, samples) = if adaptive_threshold.is_some() {
+                    let (depth, lighting) = self.cast_ray_from_camera(x as f32, y as f32, &mut rng);
+                    (self.clamp_sample(lighting.sum()), depth, 1)
                 } else {
-                    self.image.set(x, y, average);
+                    let (color, depth) = self.sample_pixel(x as f32, y as f32, &mut rng);
+                    (color, depth, 1 + self.anti_aliasing)
+                };
+
+                self.image.set(x, y, color);
+                self.depth.set(x, y, depth);
+                self.samples.set(x, y, samples);
+            }
+        }
+
+        if let Some(threshold) = adaptive_threshold {
+            let flat = self.image.clone();
+
+            for y in ys {
+                for x in xs.clone() {
+                    if is_edge(&flat, x, y, threshold) {
+                        let color =
+                            self.anti_alias(flat.mod_get(x, y), x as f32, y as f32, &mut rng);
+                        self.image.set(x, y, color);
+                        self.samples.set(x, y, 1 + self.anti_aliasing);
+                    }
                 }
             }
         }
+    }
 
-        &self.image
+    /// Render a frame at `scale` times the camera's resolution (clamped to the 0..=1 range), then
+    /// nearest-upscale it to the inner 1-frame buffer for fast previews.
+    pub fn render_preview(&mut self, scale: f32) -> Image<Color> {
+        let start = Instant::now();
+        self.update_materials().expect("Failed to update materials");
+        let scale = if scale > 0. { scale.min(1.) } else { 1. };
+        let (width, height) = self.scene.camera.size();
+        let low_size = |size: u32| ((size as f32 * scale).ceil() as u32).max(1);
+        let (low_width, low_height) = (low_size(width), low_size(height));
+
+        let mut rng = rand::thread_rng();
+
+        let mut preview = Image::new(low_width, low_height, Color::default());
+        for y in 0..low_height {
+            for x in 0..low_width {
+                let (color, _) = self.sample_pixel(x as f32 / scale, y as f32 / scale, &mut rng);
+                preview.set(x, y, color);
+            }
+        }
+
+        for y in 0..height {
+            for x in 0..width {
+                let (px, py) = ((x as f32 * scale) as u32, (y as f32 * scale) as u32);
+                self.image.set(
+                    x,
+                    y,
+                    preview.mod_get(px.min(low_width - 1), py.min(low_height - 1)),
+                );
+            }
+        }
+
+        self.stats = self.counters.take(start.elapsed());
+
+        self.image.clone()
+    }
+
+    /// Number of camera rays cast during the last rendered frame.
+    pub fn primary_rays(&self) -> usize {
+        self.stats.primary_rays
+    }
+
+    /// Rays cast, intersection tests and time spent during the last rendered frame.
+    pub fn stats(&self) -> RenderStats {
+        self.stats
+    }
+
+    /// Average the camera rays cast around the given pixel coordinates, along with the depth of
+    /// the nearest hit of the central ray.
+    fn sample_pixel(&self, x: f32, y: f32, rng: &mut impl Rng) -> (Color, f32) {
+        let (depth, lighting) = self.cast_ray_from_camera(x, y, rng);
+        (self.anti_alias(lighting.sum(), x, y, rng), depth)
+    }
+
+    /// Average the central sample of a pixel with jittered camera rays cast around it.
+    fn anti_alias(&self, center: Color, x: f32, y: f32, rng: &mut impl Rng) -> Color {
+        let mut average = RunningMean::default();
+        average.push(self.clamp_sample(center));
+        let (extent_x, extent_y) = self.jitter_extent();
+
+        for _ in 0..self.anti_aliasing {
+            let (jitter_x, jitter_y) = (
+                rng.gen_range(-extent_x..extent_x),
+                rng.gen_range(-extent_y..extent_y),
+            );
+            let (_, lighting) = self.cast_ray_from_camera(x + jitter_x, y + jitter_y, rng);
+            average.push(self.clamp_sample(lighting.sum()));
+        }
+
+        average.mean()
+    }
+
+    /// Half extents in pixels of the anti-aliasing jitter along both axes.
+    ///
+    /// The kernel spans one pixel along the shortest side of the camera's pixels and as much
+    /// image space along the other, so that it stays square on non-square pixels.
+    fn jitter_extent(&self) -> (f32, f32) {
+        let aspect = self.scene.camera.pixel_aspect();
+        ((1. / aspect).min(1.), aspect.min(1.))
+    }
+
+    /// Scale a camera sample down so that none of its channels exceed the
+    /// [firefly clamp](Self::set_firefly_clamp), keeping its hue.
+    fn clamp_sample(&self, sample: Color) -> Color {
+        let brightest = sample.r.max(sample.g).max(sample.b);
+
+        match self.firefly_clamp {
+            Some(max) if brightest > max => sample * (max / brightest),
+            _ => sample,
+        }
     }
 
     /// Use [render](Self::render) to render a frame and save the result as a file to a given path,
@@ -92,108 +543,200 @@ impl Engine<Building> {
             std::fs::create_dir_all(parent)?;
         }
 
-        self.image.save_as_ppm(path);
+        if self.dither {
+            self.image.save_as_ppm_dithered(path);
+        } else {
+            self.image.save_as_ppm(path);
+        }
 
         Ok(&self.image)
     }
 
-    fn cast_ray_from_camera(&self, x: f32, y: f32) -> impl Iterator<Item = Color> {
+    fn cast_ray_from_camera(
+        &self,
+        x: f32,
+        y: f32,
+        rng: &mut impl Rng,
+    ) -> (f32, impl Iterator<Item = Color>) {
         let (width, height) = self.scene.camera.size();
 
         let ray = self
             .scene
             .camera
-            .pixel_to_ray(x / width as f32, y / height as f32);
+            .pixel_to_ray_differential(x / width as f32, y / height as f32)
+            .into();
 
-        self.cast_ray(&ray, 0)
+        Counters::increment(&self.counters.primary_rays);
+        self.cast_ray(&ray, Bounces::default(), rng)
     }
 
-    // fn cast_ray(&self, x: f32, y: f32, bounce_depth: usize) -> impl Iterator<Item = Color> {
-    fn cast_ray(&self, ray: &Ray, bounce_depth: usize) -> impl Iterator<Item = Color> {
+    /// Nearest hit along the [Ray] closer than `max_dist`, along with its distance to the ray's
+    /// start.
+    fn nearest_hit(&self, ray: &Ray, max_dist: f32) -> Option<(f32, RaycastHit)> {
+        self.scene
+            .intersections_counted(ray, &self.counters.triangles_tested)
+            .map(|hit| ((hit.position - *ray.start()).len(), hit))
+            .filter(|(dist, _)| *dist < max_dist)
+            .min_by(|(lhs, _), (rhs, _)| lhs.total_cmp(rhs))
+    }
+
+    /// Cast a ray, returning the distance to its nearest hit ([f32::INFINITY] on a miss) and the
+    /// lighting contributions along it.
+    ///
+    /// Hits are composited front to back according to their material's alpha until they become
+    /// opaque, the background filling in for any remaining transparency. Global illumination
+    /// bounces are sampled with `rng`.
+    fn cast_ray(
+        &self,
+        ray: &Ray,
+        bounces: Bounces,
+        rng: &mut impl Rng,
+    ) -> (f32, impl Iterator<Item = Color>) {
         let mut lighting: Vec<Color> = Vec::new();
-        let mut closest: Option<f32> = None;
+        let mut dist = f32::INFINITY;
+        let mut transmittance = 1.;
 
-        for object in self.scene.objects.iter() {
-            let Some(RaycastHit { face_index: _, position, normal, material }) = object.intersects(ray) else {continue;};
+        let hits = self
+            .scene
+            .intersect_all_counted(ray, &self.counters.triangles_tested);
+        for (_, hit) in hits {
+            dist = dist.min((hit.position - *ray.start()).len());
 
-            // Ignore if further than closest encountered
-            let dist_sq = (position - self.scene.camera.center).len_sq();
-            if closest.is_none() || dist_sq < closest.unwrap() {
-                closest = Some(dist_sq);
-                lighting.clear();
+            let material = if self.clay {
+                clay_material()
             } else {
-                continue;
+                hit.material
+            };
+
+            let alpha = material.alpha.unwrap_or(1.).clamp(0., 1.);
+            let weight = transmittance * alpha;
+            if material.unlit {
+                lighting.push(material.color.unwrap_or_default() * weight);
+            } else {
+                lighting.extend(
+                    self.shade(ray, hit.position, hit.normal, &material, bounces, rng)
+                        .into_iter()
+                        .map(|color| color * weight),
+                );
             }
 
-            let color: Color = material.color.unwrap_or_default();
+            transmittance *= 1. - alpha;
+            if transmittance <= MIN_TRANSMITTANCE {
+                return (dist, lighting.into_iter());
+            }
+        }
 
-            for light in self
-                .scene
-                .lights
-                .iter()
-                .filter(|light| light.variant != LightVariant::Ambient)
-            {
-                if self.reaches_light(
-                    &Ray::new(
-                        position + normal * 0.1,
-                        light.transform.translation() - position,
-                    ),
-                    light,
-                ) {
-                    let mut prod = normal
-                        .dot_product(&(light.transform.translation() - position))
-                        .clamp(0., 1.);
-
-                    if prod.is_nan() {
-                        prod = 0.;
-                    }
+        let background = self
+            .scene
+            .environment_radiance(ray.dir())
+            .unwrap_or(Color::new(0.1, 0.1, 0.2));
+        lighting.push(background * transmittance);
 
-                    let falloff = 1. / (light.transform.translation() - position).len();
+        (dist, lighting.into_iter())
+    }
 
-                    let diffusion = color
-                        * light.color
-                        * material.diffuse.unwrap_or(0.5)
-                        * prod
-                        * light.brightness
-                        * falloff;
-
-                    let specular_power = material.specular_power.unwrap_or(1.);
-                    let specular = {
-                        // w = v - 2 * (v x n) * n
-                        let reflected = *ray.dir() - normal * 2. * (ray.dir().dot_product(&normal));
-                        let res = (material.specular.unwrap_or(0.5)
-                            * light.brightness
-                            * reflected
-                                .normalize()
-                                .dot_product(
-                                    &(light.transform.translation() - position).normalize(),
-                                )
-                                .powf(specular_power))
-                        .clamp(0., 1.);
-                        Color::new(res, res, res)
-                    } * falloff.powf(specular_power).clamp(0., 1.);
-
-                    let result = diffusion + specular;
-
-                    lighting.push(result);
-                }
+    /// Lighting contributions at a hit `position` with the given `material`.
+    fn shade(
+        &self,
+        ray: &Ray,
+        position: Vector,
+        normal: Vector,
+        material: &MaterialOutputBundle,
+        bounces: Bounces,
+        rng: &mut impl Rng,
+    ) -> Vec<Color> {
+        let mut lighting: Vec<Color> = Vec::new();
+
+        let color: Color = material.color.unwrap_or_default();
 
-                let reflection = material.reflection.unwrap_or(0.);
-                if bounce_depth < self.bounces && reflection != 0. {
-                    let start = position + normal * 0.1;
-                    let dir = *ray.dir() - normal * 2. * (ray.dir().dot_product(&normal));
-                    let ray = Ray::new(start, dir);
+        for light in self
+            .scene
+            .lights
+            .iter()
+            .filter(|light| light.variant != LightVariant::Ambient)
+        {
+            let visibility = self.light_visibility(position, normal, light);
+            if visibility > 0. {
+                let (toward, distance) = light.toward(position);
+                let mut prod = normal.dot_product(&toward).clamp(0., 1.);
 
-                    lighting.extend(
-                        self.cast_ray(&ray, bounce_depth + 1)
-                            .map(|color| color * reflection),
-                    );
+                if prod.is_nan() {
+                    prod = 0.;
                 }
+
+                // Directional lights are infinitely far away, hence without any falloff
+                let falloff = if distance.is_finite() {
+                    1. / distance
+                } else {
+                    1.
+                };
+
+                let diffusion = color
+                    * light.color
+                    * material.diffuse.unwrap_or(DEFAULT_DIFFUSE)
+                    * prod
+                    * light.brightness
+                    * falloff;
+
+                let specular_power = material.specular_power.unwrap_or(1.);
+                let specular = {
+                    // w = v - 2 * (v x n) * n
+                    let reflected = *ray.dir() - normal * 2. * (ray.dir().dot_product(&normal));
+                    let res = (material.specular.unwrap_or(0.5)
+                        * light.brightness
+                        * reflected
+                            .normalize()
+                            .dot_product(&toward.normalize())
+                            .powf(specular_power))
+                    .clamp(0., 1.);
+                    Color::new(res, res, res)
+                } * falloff.powf(specular_power).clamp(0., 1.);
+
+                let result = (diffusion + specular) * visibility;
+
+                lighting.push(result);
             }
 
-            // if let Some(ref ambient) = self.scene.ambient {
-            //     lighting.push(ambient.color * props.diffusion * ambient.brightness);
-            // }
+            let reflection = material.reflection.unwrap_or(0.);
+            if bounces.reflection < self.reflection_depth && reflection != 0. {
+                let start = position + normal * 0.1;
+                let dir = *ray.dir() - normal * 2. * (ray.dir().dot_product(&normal));
+                let ray = Ray::new(start, dir);
+                let bounces = Bounces {
+                    reflection: bounces.reflection + 1,
+                    ..bounces
+                };
+
+                Counters::increment(&self.counters.reflection_rays);
+                lighting.extend(
+                    self.cast_ray(&ray, bounces, rng)
+                        .1
+                        .map(|color| color * reflection),
+                );
+            }
+        }
+
+        let diffuse = material.diffuse.unwrap_or(DEFAULT_DIFFUSE);
+        if bounces.diffuse < self.gi_depth && diffuse != 0. {
+            let dir = Vector::sample_cosine_hemisphere(&normal, rng);
+            let ray = Ray::new(position + normal * DEFAULT_SHADOW_BIAS, dir);
+            let bounces = Bounces {
+                diffuse: bounces.diffuse + 1,
+                ..bounces
+            };
+
+            // The cosine term cancels out with the sampling density
+            let indirect: Color = self.cast_ray(&ray, bounces, rng).1.sum();
+            lighting.push(color * indirect * diffuse);
+        }
+
+        // if let Some(ref ambient) = self.scene.ambient {
+        //     lighting.push(ambient.color * props.diffusion * ambient.brightness);
+        // }
+        if let Some(irradiance) = self.scene.environment_irradiance(&normal) {
+            // Image-based ambient term
+            lighting.push(color * irradiance * material.diffuse.unwrap_or(DEFAULT_DIFFUSE));
+        } else if self.gi_depth == 0 {
             for ambient in self
                 .scene
                 .lights
@@ -202,28 +745,1091 @@ impl Engine<Building> {
             {
                 lighting.push(
                     ambient.color.min(&color)
-                        * material.diffuse.unwrap_or(0.5)
+                        * material.diffuse.unwrap_or(DEFAULT_DIFFUSE)
                         * ambient.brightness,
                 );
             }
         }
 
-        if closest.is_none() {
-            lighting.push(Color::new(0.1, 0.1, 0.2));
+        lighting
+    }
+
+    /// Fraction of the [Light] visible from `position`, sampling points on the light's surface
+    /// when it has a non-zero `shadow_softness`.
+    ///
+    /// [Directional](LightVariant::Directional) lights are seen as a disk in the sky instead, its
+    /// samples offsetting the direction toward the light.
+    fn light_visibility(&self, position: Vector, normal: Vector, light: &Light) -> f32 {
+        let start = position + normal * light.shadow_bias;
+        let (toward, distance) = light.toward(start);
+
+        // Shadow ray toward the light's surface point at `offset` from its center
+        let reaches = |offset: Vector| {
+            let dir = toward + offset;
+            let distance = if distance.is_finite() {
+                dir.len()
+            } else {
+                distance
+            };
+            self.reaches(&Ray::new(start, dir), distance)
+        };
+
+        if light.shadow_softness <= 0. {
+            return reaches(Vector::default()) as u32 as f32;
+        }
+
+        // Fibonacci sphere, evenly spreading the samples over the light's surface
+        let golden_angle = std::f32::consts::PI * (3. - 5f32.sqrt());
+        let visible = (0..SHADOW_SAMPLES)
+            .filter(|&i| {
+                let y = 1. - 2. * (i as f32 + 0.5) / SHADOW_SAMPLES as f32;
+                let radius = (1. - y * y).sqrt();
+                let phi = golden_angle * i as f32;
+
+                let offset = Vector::new(phi.cos() * radius, y, phi.sin() * radius);
+                reaches(offset * light.shadow_softness)
+            })
+            .count();
+
+        visible as f32 / SHADOW_SAMPLES as f32
+    }
+
+    fn reaches(&self, ray: &Ray, distance: f32) -> bool {
+        Counters::increment(&self.counters.shadow_rays);
+
+        // Anything past the target cannot occlude it
+        self.nearest_hit(ray, distance).is_none()
+    }
+}
+
+/// Whether the pixel differs from one of its direct neighbors by more than `threshold` on any
+/// channel.
+fn is_edge(image: &Image<Color>, x: u32, y: u32, threshold: f32) -> bool {
+    let pixel = image.mod_get(x, y);
+
+    [(-1, 0), (1, 0), (0, -1), (0, 1)]
+        .into_iter()
+        .map(|(dx, dy)| (x as i64 + dx, y as i64 + dy))
+        .filter(|&(nx, ny)| {
+            (0..image.width as i64).contains(&nx) && (0..image.height as i64).contains(&ny)
+        })
+        .any(|(nx, ny)| {
+            let neighbor = image.mod_get(nx as u32, ny as u32);
+            (pixel.r - neighbor.r).abs() > threshold
+                || (pixel.g - neighbor.g).abs() > threshold
+                || (pixel.b - neighbor.b).abs() > threshold
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{graph, shader::graph::SocketValue};
+
+    fn setup_engine(color: Color) -> Engine<Building> {
+        let mut object = Object::default();
+        object
+            .vertices(
+                [
+                    Vector::new(-10., -10., 0.),
+                    Vector::new(10., -10., 0.),
+                    Vector::new(0., 10., 0.),
+                ]
+                .into_iter(),
+            )
+            .normals(std::iter::once(Vector::new(0., 0., 1.)));
+
+        let vertex = |position| Vertex {
+            position,
+            normal: Vector::new(0., 0., 1.),
+            uvs: Default::default(),
+        };
+        object.faces.push(Triangle::new(
+            vertex(Vector::new(-10., -10., 0.)),
+            vertex(Vector::new(10., -10., 0.)),
+            vertex(Vector::new(0., 10., 0.)),
+        ));
+
+        object.material = Material::builder(
+            graph! {
+                inputs,
+                nodes,
+                outputs:
+                    "color": (None, SocketValue::IColor(Some(Image::new(1, 1, color)))),
+            }
+            .validate()
+            .unwrap(),
+        )
+        .with_output(StandardMaterialOutput::Color, "color")
+        .build()
+        .unwrap();
+
+        let mut engine = Engine::new((8, 8), 0, 0).unwrap();
+        engine
+            .scene()
+            .set_camera(Camera {
+                center: Vector::new(0., 0., 5.),
+                width: 8,
+                ..Default::default()
+            })
+            .add_light(Light {
+                transform: Transform::default().apply_translation(Vector::new(0., 0., 1.)),
+                variant: LightVariant::Point,
+                color: Color::new(1., 1., 1.),
+                brightness: 10.,
+                shadow_softness: 0.,
+                shadow_bias: DEFAULT_SHADOW_BIAS,
+            })
+            .add_object(object.build().unwrap());
+
+        engine
+    }
+
+    #[test]
+    fn hdr_buffer() {
+        let mut engine = setup_engine(Color::new(1., 1., 1.));
+        engine.render();
+
+        assert!(
+            engine.raw_image().pixels.iter().any(|pixel| pixel.r > 1.),
+            "Expected HDR values in the raw buffer"
+        );
+        assert!(engine.as_vectors().pixels.iter().any(|pixel| pixel[0] > 1.));
+    }
+
+    #[test]
+    fn clay_override() {
+        let background = Color::new(0.1, 0.1, 0.2);
+
+        let mut engine = setup_engine(Color::new(1., 0., 0.));
+        let colored = engine.render().clone();
+        let clay = engine.set_clay(true).render();
+
+        for (colored, clay) in colored.pixels.iter().zip(clay.pixels.iter()) {
+            assert_eq!(
+                *colored == background,
+                *clay == background,
+                "Clay mode should not change geometry"
+            );
+
+            if *clay != background {
+                assert!(
+                    colored.r != colored.g,
+                    "Expected a colored pixel, got {colored:?}"
+                );
+                assert!(
+                    clay.r == clay.g && clay.g == clay.b && clay.r > 0.,
+                    "Expected a grey pixel, got {clay:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn lazy_time() {
+        use crate::{get_sv, node, shader::graph::SocketType, ssref};
+
+        let mut engine = setup_engine(Color::new(1., 1., 1.));
+        engine.set_time(0.5);
+
+        // Material changed after the time was set, only picking it up when rendering
+        engine.scene().objects[0].material = Material::builder(
+            graph! {
+                inputs:
+                    "time": SocketType::Value.into(),
+                nodes:
+                    "clock": node! {
+                        inputs:
+                            "time": (ssref!(graph "time"), SocketType::Value),
+                        outputs:
+                            "color": SocketType::IColor.into();
+                        |inputs, outputs| {
+                            get_sv!( input | inputs  . "time" : Value > time);
+                            get_sv!(output | outputs . "color" : IColor > out);
+
+                            let time = time.unwrap_or(0.);
+                            out.replace(Image::new(1, 1, Color::new(time, time, time)));
+
+                            Ok(())
+                        }
+                    },
+                outputs:
+                    "color": (ssref!(node "clock" "color"), SocketType::IColor.into()),
+            }
+            .validate()
+            .unwrap(),
+        )
+        .with_output(StandardMaterialOutput::Color, "color")
+        .build()
+        .unwrap();
+
+        engine.render_to_image();
+        let color =
+            |engine: &mut Engine<Building>| engine.scene().objects[0].material.get(0.5, 0.5).color;
+        assert_eq!(Some(Color::new(0.5, 0.5, 0.5)), color(&mut engine));
+
+        engine.set_time(1.).render_preview(0.5);
+        assert_eq!(Some(Color::new(1., 1., 1.)), color(&mut engine));
+    }
+
+    #[test]
+    fn invalid_dimensions() {
+        assert!(matches!(
+            Engine::new((0, 8), 0, 0),
+            Err(Error::InvalidDimensions {
+                width: 0,
+                height: 8
+            })
+        ));
+        assert!(Engine::new((8, 0), 0, 0).is_err());
+    }
+
+    #[test]
+    fn shadow_softness() {
+        // Occluder covering the x < 0 half-space of the z = 0 plane, facing down
+        let vertex = |position| Vertex {
+            position,
+            normal: Vector::new(0., 0., -1.),
+            uvs: Default::default(),
+        };
+        let positions = [
+            Vector::new(0., -20., 0.),
+            Vector::new(-20., 0., 0.),
+            Vector::new(0., 20., 0.),
+        ];
+
+        let mut occluder = Object::default();
+        occluder
+            .vertices(positions.into_iter())
+            .normals(std::iter::once(Vector::new(0., 0., -1.)));
+        occluder.faces.push(Triangle::new(
+            vertex(positions[0]),
+            vertex(positions[1]),
+            vertex(positions[2]),
+        ));
+
+        let light = |shadow_softness| Light {
+            transform: Transform::default().apply_translation(Vector::new(0., 0., 2.)),
+            variant: LightVariant::Point,
+            color: Color::new(1., 1., 1.),
+            brightness: 1.,
+            shadow_softness,
+            shadow_bias: DEFAULT_SHADOW_BIAS,
+        };
+        let (hard, soft) = (light(0.), light(1.));
+
+        let mut engine = Engine::new((8, 8), 0, 0).unwrap();
+        engine
+            .scene()
+            .add_light(hard.clone())
+            .add_light(soft.clone())
+            .add_object(occluder.build().unwrap());
+
+        // Points on a floor below the occluder, across the shadow's edge
+        let up = Vector::new(0., 0., 1.);
+        let visibility = |light: &Light| {
+            (-10..=10)
+                .map(|x| engine.light_visibility(Vector::new(x as f32 / 10., 0., -1.), up, light))
+                .collect::<Vec<f32>>()
+        };
+        let (hard, soft) = (visibility(&hard), visibility(&soft));
+
+        assert!(
+            hard.iter().all(|&v| v == 0. || v == 1.),
+            "Expected a hard shadow edge, got {hard:?}"
+        );
+        assert!(
+            soft.iter().filter(|&&v| 0. < v && v < 1.).count() > 2,
+            "Expected a penumbra, got {soft:?}"
+        );
+        assert_eq!((hard[0], hard[20]), (0., 1.));
+        assert_eq!((soft[0], soft[20]), (0., 1.));
+    }
+
+    #[test]
+    fn directional_light() {
+        // Occluder covering the x < 0 half-space of the z = 0 plane, facing down
+        let (up, down) = (Vector::new(0., 0., 1.), Vector::new(0., 0., -1.));
+        let positions = [
+            Vector::new(0., -20., 0.),
+            Vector::new(-20., 0., 0.),
+            Vector::new(0., 20., 0.),
+        ];
+
+        let mut occluder = Object::default();
+        occluder
+            .vertices(positions.into_iter())
+            .normals(std::iter::once(down));
+        occluder.faces.push(Triangle::new(
+            Vertex::new(positions[0], down, Default::default()),
+            Vertex::new(positions[1], down, Default::default()),
+            Vertex::new(positions[2], down, Default::default()),
+        ));
+
+        let sun = Light::directional(Vector::new(0., 0., -1.));
+        let mut engine = Engine::new((8, 8), 0, 0).unwrap();
+        engine
+            .scene()
+            .add_light(sun.clone())
+            .add_object(occluder.build().unwrap());
+
+        // Shadows reach infinitely far below the occluder
+        for z in [-1., -100.] {
+            let visibility = |x| engine.light_visibility(Vector::new(x, 0., z), up, &sun);
+            assert_eq!((visibility(-0.5), visibility(0.5)), (0., 1.));
         }
 
-        lighting.into_iter()
+        // No falloff, a lit point is as bright at any distance
+        let material = clay_material();
+        let ray = Ray::new(Vector::new(0., 0., 10.), Vector::new(0., 0., -1.));
+        let shade = |z| {
+            let (position, bounces) = (Vector::new(5., 0., z), Bounces::default());
+            engine.shade(&ray, position, up, &material, bounces, &mut thread_rng())[0]
+        };
+        assert_eq!(shade(-1.), shade(-100.));
+        assert!(shade(-1.).r > 0.);
+    }
+
+    #[test]
+    fn preview() {
+        let mut engine = setup_engine(Color::new(1., 1., 1.));
+
+        let full = engine.render_to_image().clone();
+        let full_rays = engine.primary_rays();
+
+        let preview = engine.render_preview(0.5);
+
+        assert_eq!(full_rays, 64);
+        assert_eq!(engine.primary_rays(), full_rays / 4);
+        assert_eq!((preview.width, preview.height), (full.width, full.height));
+        assert_eq!(preview.mod_get(0, 0), preview.mod_get(1, 1));
+    }
+
+    #[test]
+    fn render_stats() {
+        let mut engine = setup_engine(Color::new(1., 1., 1.));
+        engine.render_to_image();
+
+        let stats = engine.stats();
+        let hits = engine
+            .depth()
+            .pixels
+            .iter()
+            .filter(|depth| depth.is_finite())
+            .count();
+
+        assert_eq!(stats.primary_rays, 8 * 8);
+        // One shadow ray toward the hard point light per hit, no mirrors to bounce off of
+        assert_eq!(stats.shadow_rays, hits);
+        assert_eq!(stats.reflection_rays, 0);
+        // Each hit means the single triangle has been tested at least once
+        assert!(hits > 0 && stats.triangles_tested >= hits);
+        assert!(stats.duration > Duration::ZERO);
+
+        // Counts start over on every frame
+        engine.render_to_image();
+        assert_eq!(engine.stats().primary_rays, 8 * 8);
+    }
+
+    #[test]
+    fn render_region() {
+        let full = setup_engine(Color::new(1., 1., 1.))
+            .render_to_image()
+            .clone();
+
+        let mut engine = setup_engine(Color::new(1., 1., 1.));
+        let region = engine.render_region(2, 3, 4, 2).clone();
+
+        for (x, y, pixel) in region.enumerate_pixels() {
+            if (2..6).contains(&x) && (3..5).contains(&y) {
+                assert_eq!(
+                    *pixel,
+                    full.mod_get(x, y),
+                    "Pixel ({x}, {y}) should be rendered"
+                );
+            } else {
+                assert_eq!(
+                    *pixel,
+                    Color::default(),
+                    "Pixel ({x}, {y}) should be untouched"
+                );
+            }
+        }
+        assert_eq!(engine.stats().primary_rays, 4 * 2);
+
+        // Clipped to the image
+        engine.render_region(6, 6, 10, 10);
+        assert_eq!(engine.raw_image().mod_get(7, 7), full.mod_get(7, 7));
+        assert_eq!(engine.stats().primary_rays, 2 * 2);
+    }
+
+    #[test]
+    fn depth_bound() {
+        let mut engine = setup_engine(Color::new(1., 1., 1.));
+
+        // Smaller occluder in front of the base triangle
+        let vertex = |position| Vertex {
+            position,
+            normal: Vector::new(0., 0., 1.),
+            uvs: Default::default(),
+        };
+        let positions = [
+            Vector::new(-1., -1., 2.),
+            Vector::new(1., -1., 2.),
+            Vector::new(0., 1., 2.),
+        ];
+        let mut occluder = Object::default();
+        occluder
+            .vertices(positions.into_iter())
+            .normals(std::iter::once(Vector::new(0., 0., 1.)));
+        occluder.faces.push(Triangle::new(
+            vertex(positions[0]),
+            vertex(positions[1]),
+            vertex(positions[2]),
+        ));
+        engine.scene().add_object(occluder.build().unwrap());
+
+        engine.render();
+
+        let (width, height) = engine.scene.camera.size();
+        let depths = engine.depth().pixels.clone();
+        assert!(depths.iter().any(|&depth| (depth - 3.).abs() < 0.5));
+        assert!(depths.iter().any(|&depth| (depth - 5.).abs() < 0.5));
+
+        for y in 0..height {
+            for x in 0..width {
+                let ray = engine
+                    .scene
+                    .camera
+                    .pixel_to_ray(x as f32 / width as f32, y as f32 / height as f32);
+                let bound = engine.depth().mod_get(x, y) + 1e-3;
+
+                let unbounded = engine.nearest_hit(&ray, f32::INFINITY);
+                let bounded = engine.nearest_hit(&ray, bound);
+                assert_eq!(
+                    unbounded.map(|(_, hit)| hit.position),
+                    bounded.map(|(_, hit)| hit.position),
+                    "Nearest hit changed at ({x}, {y}) with a bound of {bound}"
+                );
+            }
+        }
     }
 
-    fn reaches_light(&self, ray: &Ray, light: &Light) -> bool {
-        let dist = (light.transform.translation() - *ray.start()).len();
+    #[test]
+    fn adaptive_anti_aliasing() {
+        let mut engine = setup_engine(Color::new(1., 1., 1.));
+        engine.scene().lights = vec![Light {
+            transform: Transform::default(),
+            variant: LightVariant::Ambient,
+            color: Color::new(1., 1., 1.),
+            brightness: 1.,
+            shadow_softness: 0.,
+            shadow_bias: DEFAULT_SHADOW_BIAS,
+        }];
 
-        for object in self.scene.objects.iter() {
-            if let Some(intersection) = object.intersects(ray) {
-                return (intersection.position - *ray.start()).len() > dist;
+        // Flat shading, only the triangle's edges differ from their neighbors
+        let flat = engine.render_to_image().clone();
+
+        engine.anti_aliasing = 4;
+        engine.set_adaptive_anti_aliasing(Some(0.01));
+        engine.render_to_image();
+
+        let (mut edges, mut interior) = (0, 0);
+        for y in 0..flat.height {
+            for x in 0..flat.width {
+                let samples = engine.samples().mod_get(x, y);
+                if is_edge(&flat, x, y, 0.01) {
+                    edges += 1;
+                    assert_eq!(samples, 5, "Expected a supersampled edge at ({x}, {y})");
+                } else {
+                    interior += 1;
+                    assert_eq!(samples, 1, "Expected a single sample at ({x}, {y})");
+                }
             }
         }
 
-        true
+        assert!(edges > 0 && interior > 0);
+        assert_eq!(engine.primary_rays(), edges * 5 + interior);
+    }
+
+    #[test]
+    fn running_mean() {
+        let sample = Color::new(0.1, 0.7, 1e-3);
+
+        let mut mean = RunningMean::default();
+        (0..10_000).for_each(|_| mean.push(sample));
+        assert_eq!(sample, mean.mean());
+
+        // Flat areas keep their color however many samples are averaged
+        let mut engine = setup_engine(Color::new(1., 1., 1.));
+        engine.scene().lights = vec![Light::ambient()];
+        let flat = engine.render_to_image().mod_get(4, 4);
+
+        engine.anti_aliasing = 8;
+        assert_eq!(flat, engine.render_to_image().mod_get(4, 4));
+    }
+
+    #[test]
+    fn firefly_clamp() {
+        let mut engine = setup_engine(Color::new(1., 1., 1.));
+        engine.anti_aliasing = 4;
+
+        let firefly = Color::new(1e6, 1e5, 0.);
+        let mut rng = rand::thread_rng();
+
+        let unclamped = engine.anti_alias(firefly, 4., 4., &mut rng);
+        assert!(unclamped.r > 1e5);
+
+        engine.set_firefly_clamp(Some(1.));
+        assert!(engine
+            .clamp_sample(firefly)
+            .approx_eq(&Color::new(1., 0.1, 0.), 1e-6));
+        assert_eq!(
+            engine.clamp_sample(Color::new(0.5, 0.5, 0.5)),
+            Color::new(0.5, 0.5, 0.5)
+        );
+
+        let clamped = engine.anti_alias(firefly, 4., 4., &mut rng);
+        assert!(
+            clamped.r < 2.,
+            "Expected the firefly not to dominate the pixel, got {clamped:?}"
+        );
+
+        engine.render_to_image();
+        assert!(engine
+            .raw_image()
+            .pixels
+            .iter()
+            .all(|pixel| pixel.r.max(pixel.g).max(pixel.b) < 2.));
+    }
+
+    #[test]
+    fn anamorphic_jitter() {
+        let mut engine = setup_engine(Color::new(1., 1., 1.));
+        assert_eq!(engine.jitter_extent(), (1., 1.));
+
+        // Twice as wide a field of view on as many pixels: pixels are twice as wide as they
+        // are tall
+        engine.scene().set_camera(Camera {
+            fov: Fov(60., 30.),
+            width: 8,
+            height: Some(8),
+            ..Default::default()
+        });
+        assert_eq!(engine.scene.camera.pixel_aspect(), 2.);
+
+        let (extent_x, extent_y) = engine.jitter_extent();
+        assert_eq!((extent_x, extent_y), (0.5, 1.));
+        assert_ne!(extent_x, extent_y);
+    }
+
+    /// Single-triangle object with flat `normal`s.
+    fn triangle(positions: [Vector; 3], normal: Vector) -> Object<Building> {
+        let vertex = |position| Vertex {
+            position,
+            normal,
+            uvs: Default::default(),
+        };
+
+        let mut object = Object::default();
+        object
+            .vertices(positions.into_iter())
+            .normals(std::iter::once(normal));
+        object.faces.push(Triangle::new(
+            vertex(positions[0]),
+            vertex(positions[1]),
+            vertex(positions[2]),
+        ));
+        object
+    }
+
+    #[test]
+    fn mirror() {
+        let render = |bounces| {
+            let mut mirror = triangle(
+                [
+                    Vector::new(-10., -10., 0.),
+                    Vector::new(10., -10., 0.),
+                    Vector::new(0., 10., 0.),
+                ],
+                Vector::new(0., 0., 1.),
+            );
+            mirror.material = Material::mirror();
+
+            // Red wall behind the camera, facing the mirror
+            let mut wall = triangle(
+                [
+                    Vector::new(-20., -20., 8.),
+                    Vector::new(0., 20., 8.),
+                    Vector::new(20., -20., 8.),
+                ],
+                Vector::new(0., 0., -1.),
+            );
+            wall.material = Material::diffuse(Color::new(1., 0., 0.));
+
+            let mut engine = Engine::new((8, 8), bounces, 0).unwrap();
+            engine
+                .scene()
+                .set_camera(Camera {
+                    center: Vector::new(0., 0., 5.),
+                    width: 8,
+                    ..Default::default()
+                })
+                .add_light(Light {
+                    transform: Transform::default().apply_translation(Vector::new(0., 0., 6.)),
+                    variant: LightVariant::Point,
+                    color: Color::new(1., 1., 1.),
+                    brightness: 5.,
+                    shadow_softness: 0.,
+                    shadow_bias: DEFAULT_SHADOW_BIAS,
+                })
+                .add_object(mirror.build().unwrap())
+                .add_object(wall.build().unwrap());
+
+            engine.render_to_image().mod_get(4, 4)
+        };
+
+        let (direct, reflected) = (render(0), render(1));
+
+        assert!(
+            direct.r < 0.1,
+            "Expected a dark mirror without reflections, got {direct:?}"
+        );
+        assert!(
+            reflected.r > direct.r + 0.1 && reflected.r > reflected.g + 0.1,
+            "Expected the red wall reflected in the mirror, got {reflected:?}"
+        );
+    }
+
+    #[test]
+    fn reflection_depth() {
+        let render = |reflection_depth| {
+            let mirror = |positions, normal| {
+                let mut mirror = triangle(positions, normal);
+                mirror.material = Material::mirror();
+                mirror.build().unwrap()
+            };
+
+            let mut engine = Engine::new((8, 8), reflection_depth, 0).unwrap();
+            engine
+                .set_gi_depth(0)
+                .scene()
+                .set_camera(Camera {
+                    center: Vector::new(0., 0., 5.),
+                    width: 8,
+                    ..Default::default()
+                })
+                .add_light(Light {
+                    transform: Transform::default().apply_translation(Vector::new(0., 0., 6.)),
+                    variant: LightVariant::Point,
+                    color: Color::new(1., 1., 1.),
+                    brightness: 5.,
+                    shadow_softness: 0.,
+                    shadow_bias: DEFAULT_SHADOW_BIAS,
+                })
+                // Facing mirrors on both sides of the camera, bouncing rays back and forth
+                .add_object(mirror(
+                    [
+                        Vector::new(-10., -10., 0.),
+                        Vector::new(10., -10., 0.),
+                        Vector::new(0., 10., 0.),
+                    ],
+                    Vector::new(0., 0., 1.),
+                ))
+                .add_object(mirror(
+                    [
+                        Vector::new(-20., -20., 8.),
+                        Vector::new(0., 20., 8.),
+                        Vector::new(20., -20., 8.),
+                    ],
+                    Vector::new(0., 0., -1.),
+                ));
+
+            engine.render_to_image().mod_get(4, 4)
+        };
+
+        let (single, multiple) = (render(1), render(6));
+
+        assert!(
+            multiple.r > single.r,
+            "Expected more light gathered over several reflections, got {multiple:?} against \
+             {single:?}"
+        );
+        // Without any randomly sampled diffuse bounce, renders are deterministic
+        assert_eq!(multiple, render(6));
+    }
+
+    #[test]
+    fn auto_exposure_levels() {
+        let render = |brightness| {
+            let mut plane = triangle(
+                [
+                    Vector::new(-10., -10., 0.),
+                    Vector::new(10., -10., 0.),
+                    Vector::new(0., 10., 0.),
+                ],
+                Vector::new(0., 0., 1.),
+            );
+            plane.material = Material::diffuse(Color::new(1., 1., 1.));
+
+            let mut engine = Engine::new((8, 8), 0, 0).unwrap();
+            engine
+                .set_auto_exposure(true)
+                .scene()
+                .set_camera(Camera {
+                    center: Vector::new(0., 0., 5.),
+                    width: 8,
+                    ..Default::default()
+                })
+                .add_light(Light {
+                    transform: Transform::default().apply_translation(Vector::new(0., 0., 2.)),
+                    variant: LightVariant::Point,
+                    color: Color::new(1., 1., 1.),
+                    brightness,
+                    shadow_softness: 0.,
+                    shadow_bias: DEFAULT_SHADOW_BIAS,
+                })
+                .add_object(plane.build().unwrap());
+
+            let median = |image: &Image<Color>| {
+                let mut luminances = image
+                    .pixels
+                    .iter()
+                    .map(Color::luminance)
+                    .collect::<Vec<_>>();
+                luminances.sort_by(f32::total_cmp);
+                luminances[luminances.len() / 2]
+            };
+
+            let exposed = median(engine.render_to_image());
+            (engine.exposure(), exposed)
+        };
+
+        let ((dark, dark_median), (bright, bright_median)) = (render(0.05), render(20.));
+
+        assert!(
+            dark > 1.,
+            "Expected a dark scene to be brightened, got {dark}"
+        );
+        assert!(
+            bright < 1.,
+            "Expected a bright scene to be darkened, got {bright}"
+        );
+        assert!(dark > bright);
+
+        // Both end up around middle grey, within a histogram bucket
+        for median in [dark_median, bright_median] {
+            assert!(
+                (median.log2() - AUTO_EXPOSURE_KEY.log2()).abs() < 0.25,
+                "Got {median}"
+            );
+        }
+    }
+
+    #[test]
+    fn auto_exposure_ignores_background() {
+        let mut object = triangle(
+            [
+                Vector::new(-1., -1., 0.),
+                Vector::new(1., -1., 0.),
+                Vector::new(0., 1., 0.),
+            ],
+            Vector::new(0., 0., 1.),
+        );
+        object.material = Material::diffuse(Color::new(1., 1., 1.));
+
+        let mut engine = Engine::new((16, 16), 0, 0).unwrap();
+        engine
+            .set_auto_exposure(true)
+            .scene()
+            .set_camera(Camera {
+                center: Vector::new(0., 0., 5.),
+                width: 16,
+                ..Default::default()
+            })
+            // Black background
+            .set_environment(Image::new(1, 1, Color::default()))
+            .add_light(Light::point(Vector::new(0., 0., 2.)))
+            .add_object(object.build().unwrap());
+
+        let image = engine.render_to_image();
+        let mut lit = image
+            .pixels
+            .iter()
+            .map(Color::luminance)
+            .filter(|&luminance| luminance > 0.)
+            .collect::<Vec<_>>();
+        assert!(
+            !lit.is_empty() && lit.len() * 2 < image.pixels.len(),
+            "Expected a small object on a black background, {} pixels are lit",
+            lit.len()
+        );
+
+        // The object ends up around middle grey instead of being blown out
+        lit.sort_by(f32::total_cmp);
+        let median = lit[lit.len() / 2];
+        assert!(
+            (median.log2() - AUTO_EXPOSURE_KEY.log2()).abs() < 0.25,
+            "Got {median}"
+        );
+    }
+
+    #[test]
+    fn diffuse_preset() {
+        let mut plane = triangle(
+            [
+                Vector::new(-10., -10., 0.),
+                Vector::new(10., -10., 0.),
+                Vector::new(0., 10., 0.),
+            ],
+            Vector::new(0., 0., 1.),
+        );
+        plane.material = Material::diffuse(Color::new(1., 0., 0.));
+
+        let mut engine = Engine::new((8, 8), 0, 0).unwrap();
+        engine
+            .scene()
+            .set_camera(Camera {
+                center: Vector::new(0., 0., 5.),
+                width: 8,
+                ..Default::default()
+            })
+            .add_light(Light {
+                transform: Transform::default().apply_translation(Vector::new(0., 0., 2.)),
+                variant: LightVariant::Point,
+                color: Color::new(1., 1., 1.),
+                brightness: 2.,
+                shadow_softness: 0.,
+                shadow_bias: DEFAULT_SHADOW_BIAS,
+            })
+            .add_object(plane.build().unwrap());
+
+        let pixel = engine.render_to_image().mod_get(4, 4);
+        assert!(
+            pixel.r > 0.1 && pixel.g == 0. && pixel.b == 0.,
+            "Expected a red pixel, got {pixel:?}"
+        );
+    }
+
+    #[test]
+    fn unlit() {
+        let color = Color::new(0.2, 0.4, 0.6);
+        let mut plane = triangle(
+            [
+                Vector::new(-10., -10., 0.),
+                Vector::new(10., -10., 0.),
+                Vector::new(0., 10., 0.),
+            ],
+            Vector::new(0., 0., 1.),
+        );
+        plane.material = Material::unlit(color);
+
+        let mut engine = Engine::new((8, 8), 0, 0).unwrap();
+        engine
+            .scene()
+            .set_camera(Camera {
+                center: Vector::new(0., 0., 5.),
+                width: 8,
+                ..Default::default()
+            })
+            .add_object(plane.build().unwrap());
+
+        let unlit = engine.render_to_image().clone();
+        assert_eq!(unlit.mod_get(4, 4), color);
+
+        engine.scene().add_light(Light {
+            transform: Transform::default().apply_translation(Vector::new(0., 0., 2.)),
+            variant: LightVariant::Point,
+            color: Color::new(1., 0., 0.),
+            brightness: 10.,
+            shadow_softness: 0.5,
+            shadow_bias: DEFAULT_SHADOW_BIAS,
+        });
+
+        assert_eq!(engine.render_to_image(), &unlit);
+        assert_eq!(engine.stats().shadow_rays, 0);
+    }
+
+    #[test]
+    fn seeded_global_illumination() {
+        use rand::rngs::StdRng;
+
+        let mut engine = setup_engine(Color::new(1., 1., 1.));
+        engine.scene().lights = vec![Light::ambient()];
+        let ambient = engine.render_to_image().mod_get(4, 4);
+
+        engine.set_gi_depth(2).update_materials().unwrap();
+        let sample = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            engine.sample_pixel(4., 4., &mut rng).0
+        };
+        assert_eq!(sample(0), sample(0));
+
+        // Only the sky is gathered, the ambient light no longer adding up to it
+        let gi = sample(0);
+        assert!(
+            gi.r < ambient.r / 2.,
+            "Expected no ambient term with GI, got {gi:?}"
+        );
+    }
+
+    #[test]
+    fn color_bleeding() {
+        let render = |global_illumination| {
+            let mut floor = triangle(
+                [
+                    Vector::new(-10., -10., 0.),
+                    Vector::new(10., -10., 0.),
+                    Vector::new(0., 10., 0.),
+                ],
+                Vector::new(0., 0., 1.),
+            );
+            floor.material = Material::diffuse(Color::new(1., 1., 1.));
+
+            // Red wall standing on the white one, facing -X
+            let mut wall = triangle(
+                [
+                    Vector::new(1., -20., -1.),
+                    Vector::new(1., 0., 20.),
+                    Vector::new(1., 20., -1.),
+                ],
+                Vector::new(-1., 0., 0.),
+            );
+            wall.material = Material::diffuse(Color::new(1., 0., 0.));
+
+            let mut engine = Engine::new((8, 8), 1, 32).unwrap();
+            engine
+                .set_gi_depth(global_illumination as usize)
+                .scene()
+                .set_camera(Camera {
+                    center: Vector::new(0., 0., 5.),
+                    width: 8,
+                    ..Default::default()
+                })
+                .add_light(Light {
+                    transform: Transform::default().apply_translation(Vector::new(0., 0., 3.)),
+                    variant: LightVariant::Point,
+                    color: Color::new(1., 1., 1.),
+                    brightness: 5.,
+                    shadow_softness: 0.,
+                    shadow_bias: DEFAULT_SHADOW_BIAS,
+                })
+                .add_object(floor.build().unwrap())
+                .add_object(wall.build().unwrap());
+
+            // White wall, next to the red one
+            engine.render_to_image().mod_get(2, 4)
+        };
+
+        let (direct, indirect) = (render(false), render(true));
+
+        assert_eq!(
+            direct.r, direct.g,
+            "Expected a white wall without GI, got {direct:?}"
+        );
+        assert!(
+            indirect.r > indirect.g + 0.01,
+            "Expected red bleeding onto the white wall with GI, got {indirect:?}"
+        );
+    }
+
+    #[test]
+    fn alpha_compositing() {
+        let plane = |z, material| {
+            let mut object = triangle(
+                [
+                    Vector::new(-10., -10., z),
+                    Vector::new(10., -10., z),
+                    Vector::new(0., 10., z),
+                ],
+                Vector::new(0., 0., 1.),
+            );
+            object.material = material;
+            object.build().unwrap()
+        };
+
+        let mut engine = Engine::new((8, 8), 0, 0).unwrap();
+        engine
+            .scene()
+            .set_camera(Camera {
+                center: Vector::new(0., 0., 5.),
+                width: 8,
+                ..Default::default()
+            })
+            // Ambient lighting leaves each plane's color untouched
+            .add_light(Light {
+                transform: Transform::default(),
+                variant: LightVariant::Ambient,
+                color: Color::new(1., 1., 1.),
+                brightness: 1. / DEFAULT_DIFFUSE,
+                shadow_softness: 0.,
+                shadow_bias: DEFAULT_SHADOW_BIAS,
+            })
+            // Added back to front to check the hits' sorting
+            .add_object(plane(0., Material::diffuse(Color::new(0., 0., 1.))))
+            .add_object(plane(
+                0.5,
+                Material::transparent(Color::new(0., 1., 0.), 0.5),
+            ))
+            .add_object(plane(
+                1.,
+                Material::transparent(Color::new(1., 0., 0.), 0.5),
+            ));
+
+        let pixel = engine.render_to_image().mod_get(4, 4);
+        assert!(
+            pixel.approx_eq(&Color::new(0.5, 0.25, 0.25), 1e-5),
+            "Expected red over green over blue, got {pixel:?}"
+        );
+        assert!((engine.depth().mod_get(4, 4) - 4.).abs() < 0.1);
+    }
+
+    #[test]
+    fn environment_lighting() {
+        // Dark toward -X, bright toward +X
+        let mut environment = Image::new(8, 1, Color::default());
+        (4..8).for_each(|x| environment.set(x, 0, Color::new(1., 1., 1.)));
+
+        // Two halves of the z = 0 plane, shaded as if tilted toward -X and +X respectively
+        let (left, right) = (Vector::new(-1., 0., 1.), Vector::new(1., 0., 1.));
+        let half = |positions, normal: Vector| {
+            let mut object = triangle(positions, normal.normalize());
+            object.material = Material::diffuse(Color::new(1., 1., 1.));
+            object.build().unwrap()
+        };
+
+        let mut engine = Engine::new((8, 8), 0, 0).unwrap();
+        engine
+            .scene()
+            .set_camera(Camera {
+                center: Vector::new(0., 0., 5.),
+                width: 8,
+                ..Default::default()
+            })
+            .set_environment(environment)
+            .add_object(half(
+                [
+                    Vector::new(0., -10., 0.),
+                    Vector::new(0., 10., 0.),
+                    Vector::new(-10., 0., 0.),
+                ],
+                left,
+            ))
+            .add_object(half(
+                [
+                    Vector::new(0., -10., 0.),
+                    Vector::new(10., 0., 0.),
+                    Vector::new(0., 10., 0.),
+                ],
+                right,
+            ));
+
+        let image = engine.render_to_image();
+        let (dark, bright) = (image.mod_get(2, 4), image.mod_get(5, 4));
+
+        assert!(
+            bright.r > dark.r + 0.1,
+            "Expected the surface facing the bright side to be brighter, got {dark:?} and {bright:?}"
+        );
     }
 }
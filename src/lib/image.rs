@@ -2,7 +2,7 @@
 
 use std::{
     fs::OpenOptions,
-    io::{BufWriter, Write},
+    io::{BufWriter, Read, Write},
     path::Path,
 };
 
@@ -10,6 +10,12 @@ use crate::vector::Vector;
 
 use super::color::Color;
 
+/// 4x4 ordered dithering matrix, each index giving the threshold `(index + 0.5) / 16`.
+const BAYER: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// Range of log2 luminance covered by [luminance histograms](Image::luminance_histogram).
+pub const LUMINANCE_HISTOGRAM_RANGE: (f32, f32) = (-10., 6.);
+
 #[derive(Clone, Debug, PartialEq)]
 /// Generic image struct. Usage of the word `pixel` in this documentation refers to an instance of
 /// the stored data type.
@@ -41,46 +47,402 @@ impl<T: Clone> Image<T> {
     pub fn set(&mut self, x: u32, y: u32, value: T) {
         self.pixels[(y * self.width + x) as usize] = value;
     }
+
+    /// Repeat the image to fill the given dimensions, see [mod_get](Self::mod_get).
+    pub fn tile(&self, width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: (0..height)
+                .flat_map(|y| (0..width).map(move |x| self.mod_get(x, y)))
+                .collect(),
+        }
+    }
+}
+
+impl<T> Image<T> {
+    /// Iterate over every pixel along with its x/y coordinates, row by row.
+    pub fn enumerate_pixels(&self) -> impl Iterator<Item = (u32, u32, &T)> {
+        let width = self.width.max(1);
+        self.pixels
+            .iter()
+            .enumerate()
+            .map(move |(index, pixel)| (index as u32 % width, index as u32 / width, pixel))
+    }
+
+    /// Same as [enumerate_pixels](Self::enumerate_pixels) with mutable pixels.
+    pub fn enumerate_pixels_mut(&mut self) -> impl Iterator<Item = (u32, u32, &mut T)> {
+        let width = self.width.max(1);
+        self.pixels
+            .iter_mut()
+            .enumerate()
+            .map(move |(index, pixel)| (index as u32 % width, index as u32 / width, pixel))
+    }
+
+    /// Create an image of the same dimensions by applying `f` to every pixel.
+    pub fn map<U>(&self, f: impl Fn(&T) -> U) -> Image<U> {
+        Image {
+            width: self.width,
+            height: self.height,
+            pixels: self.pixels.iter().map(f).collect(),
+        }
+    }
+
+    /// Create an image of the same dimensions by combining every pixel with the matching one in
+    /// `other`, which is [tiled](Image::tile) if its dimensions differ.
+    pub fn zip_with<U: Clone, V>(&self, other: &Image<U>, f: impl Fn(&T, &U) -> V) -> Image<V> {
+        let other = if (other.width, other.height) == (self.width, self.height) {
+            std::borrow::Cow::Borrowed(other)
+        } else {
+            std::borrow::Cow::Owned(other.tile(self.width, self.height))
+        };
+
+        Image {
+            width: self.width,
+            height: self.height,
+            pixels: self
+                .pixels
+                .iter()
+                .zip(other.pixels.iter())
+                .map(|(lhs, rhs)| f(lhs, rhs))
+                .collect(),
+        }
+    }
+}
+
+/// Quantize the pixel at (`x`, `y`) to 8-bit, optionally applying [BAYER] dithering.
+fn quantize(pixel: &Color, x: usize, y: usize, dither: bool) -> [u8; 3] {
+    if !dither {
+        return pixel.as_bytes();
+    }
+
+    let threshold = (BAYER[y % 4][x % 4] as f32 + 0.5) / 16.;
+    [pixel.r, pixel.g, pixel.b].map(|value| (value * 255. + threshold) as u8)
+}
+
+/// Error for malformed .ppm data.
+fn invalid_ppm(message: &str) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!("Invalid PPM: {message}"),
+    )
+}
+
+/// Pop the next whitespace-separated .ppm header token, skipping `#` comments.
+fn ppm_token<'a>(bytes: &mut &'a [u8]) -> std::io::Result<&'a [u8]> {
+    loop {
+        match bytes.first() {
+            Some(byte) if byte.is_ascii_whitespace() => *bytes = &bytes[1..],
+            Some(b'#') => {
+                let end = bytes
+                    .iter()
+                    .position(|&byte| byte == b'\n')
+                    .unwrap_or(bytes.len());
+                *bytes = &bytes[end..];
+            }
+            Some(_) => break,
+            None => return Err(invalid_ppm("unexpected end of data")),
+        }
+    }
+
+    let end = bytes
+        .iter()
+        .position(|byte| byte.is_ascii_whitespace() || *byte == b'#')
+        .unwrap_or(bytes.len());
+    let (token, rest) = bytes.split_at(end);
+    *bytes = rest;
+
+    Ok(token)
+}
+
+/// Parse the next .ppm header token as a number.
+fn ppm_number(bytes: &mut &[u8]) -> std::io::Result<u32> {
+    let token = ppm_token(bytes)?;
+    std::str::from_utf8(token)
+        .ok()
+        .and_then(|token| token.parse().ok())
+        .ok_or_else(|| invalid_ppm(&format!("expected a number, got {token:?}")))
 }
 
 impl Image<Color> {
-    /// Save current state as a .ppm according to the path given as argument
+    /// Load an image file (PNG or JPEG), mapping 8-bit channels to the [0, 1] range.
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        let image = ::image::open(path)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?
+            .into_rgb32f();
+
+        Ok(Self {
+            width: image.width(),
+            height: image.height(),
+            pixels: image
+                .pixels()
+                .map(|pixel| Color::new(pixel[0], pixel[1], pixel[2]))
+                .collect(),
+        })
+    }
+
+    /// Read a binary (P6) or ASCII (P3) .ppm from any [Read] source, such as stdin, mapping
+    /// channels to the [0, 1] range. Rows are expected bottom first, mirroring
+    /// [write_ppm](Self::write_ppm).
+    pub fn from_ppm_reader<R: Read>(reader: &mut R) -> std::io::Result<Self> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+
+        Self::from_ppm_bytes(&bytes)
+    }
+
+    /// Read a binary (P6) or ASCII (P3) .ppm held in memory, see
+    /// [from_ppm_reader](Self::from_ppm_reader).
+    pub fn from_ppm_bytes(mut bytes: &[u8]) -> std::io::Result<Self> {
+        let binary = match ppm_token(&mut bytes)? {
+            b"P6" => true,
+            b"P3" => false,
+            magic => return Err(invalid_ppm(&format!("unsupported format {magic:?}"))),
+        };
+
+        let (width, height) = (ppm_number(&mut bytes)?, ppm_number(&mut bytes)?);
+        let max = ppm_number(&mut bytes)?;
+        if !(1..=u16::MAX as u32).contains(&max) {
+            return Err(invalid_ppm(&format!("maximum value {max} out of range")));
+        }
+
+        let count = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|count| count.checked_mul(3))
+            .ok_or_else(|| invalid_ppm(&format!("image size {width}x{height} too large")))?;
+        let samples = if binary {
+            // Exactly one whitespace byte separates the header from the samples
+            if !bytes.first().is_some_and(u8::is_ascii_whitespace) {
+                return Err(invalid_ppm("missing whitespace after header"));
+            }
+            let bytes = &bytes[1..];
+
+            let size = if max > u8::MAX as u32 { 2 } else { 1 };
+            let length = count
+                .checked_mul(size)
+                .ok_or_else(|| invalid_ppm(&format!("image size {width}x{height} too large")))?;
+            if bytes.len() < length {
+                return Err(invalid_ppm("unexpected end of data"));
+            }
+
+            bytes[..length]
+                .chunks_exact(size)
+                .map(|sample| sample.iter().fold(0, |acc, &byte| acc << 8 | byte as u32))
+                .collect()
+        } else {
+            (0..count)
+                .map(|_| ppm_number(&mut bytes))
+                .collect::<std::io::Result<Vec<u32>>>()?
+        };
+
+        let mut pixels = samples
+            .chunks_exact(3)
+            .map(|rgb| {
+                let [r, g, b] = [rgb[0], rgb[1], rgb[2]].map(|value| value as f32 / max as f32);
+                Color::new(r, g, b)
+            })
+            .collect::<Vec<Color>>();
+
+        // Stored rows go top first
+        if width > 0 {
+            pixels = pixels
+                .chunks_exact(width as usize)
+                .rev()
+                .flatten()
+                .copied()
+                .collect();
+        }
+
+        Ok(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Save current state as a .ppm according to the path given as argument.
     pub fn save_as_ppm(&self, path: &Path) {
-        let mut file = OpenOptions::new()
+        self.save_ppm(path, false);
+    }
+
+    /// Save current state as a dithered .ppm, see [to_bytes](Self::to_bytes).
+    pub fn save_as_ppm_dithered(&self, path: &Path) {
+        self.save_ppm(path, true);
+    }
+
+    fn save_ppm(&self, path: &Path, dither: bool) {
+        let file = OpenOptions::new()
             .create(true)
             .write(true)
             .truncate(true)
             .open(path)
             .expect("Error saving image");
 
-        file.write_all(format!("P6 {} {} {}\n", self.width, self.height, u8::MAX).as_bytes())
-            .expect("Error writing image headers");
-
         let mut writer = BufWriter::new(file);
 
-        for row in self
-            .pixels
-            .windows(self.width as usize)
-            .step_by(self.width as usize)
-            .rev()
-            .map(|row| row.iter().map(Color::as_bytes))
-        {
-            for rgb in row {
-                writer.write_all(&rgb).expect("Error writing pixel value");
+        self.write_ppm_with(&mut writer, dither)
+            .expect("Error writing image");
+
+        writer.flush().unwrap();
+    }
+
+    /// Save current state as a .png according to the path given as argument.
+    pub fn save_as_png(&self, path: &Path) -> std::io::Result<()> {
+        self.save_png(path, false)
+    }
+
+    /// Save current state as a dithered .png, see [to_bytes](Self::to_bytes).
+    pub fn save_as_png_dithered(&self, path: &Path) -> std::io::Result<()> {
+        self.save_png(path, true)
+    }
+
+    fn save_png(&self, path: &Path, dither: bool) -> std::io::Result<()> {
+        ::image::RgbImage::from_raw(self.width, self.height, self.to_bytes(dither))
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "Pixel count does not match image dimensions",
+                )
+            })?
+            .save(path)
+            .map_err(std::io::Error::other)
+    }
+
+    /// Stream the image as a binary .ppm to any [Write] sink, bottom row first.
+    ///
+    /// # Panics
+    /// If the pixel count does not match the image dimensions.
+    pub fn write_ppm<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.write_ppm_with(writer, false)
+    }
+
+    /// Stream the image as a dithered binary .ppm, see [write_ppm](Self::write_ppm) and
+    /// [to_bytes](Self::to_bytes).
+    ///
+    /// # Panics
+    /// If the pixel count does not match the image dimensions.
+    pub fn write_ppm_dithered<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.write_ppm_with(writer, true)
+    }
+
+    fn write_ppm_with<W: Write>(&self, writer: &mut W, dither: bool) -> std::io::Result<()> {
+        assert_eq!(
+            self.pixels.len(),
+            (self.width * self.height) as usize,
+            "Pixel count does not match image dimensions"
+        );
+
+        writer.write_all(format!("P6 {} {} {}\n", self.width, self.height, u8::MAX).as_bytes())?;
+
+        let rows = self.pixels.chunks_exact(self.width as usize).enumerate();
+        for (y, row) in rows.rev() {
+            for (x, pixel) in row.iter().enumerate() {
+                writer.write_all(&quantize(pixel, x, y, dither))?;
             }
         }
 
-        writer.flush().unwrap();
+        Ok(())
+    }
+
+    /// Quantize the image to 8-bit RGB triplets, top row first.
+    ///
+    /// Plain quantization truncates every channel, which shows up as banding on smooth gradients.
+    /// With `dither`, a 4x4 ordered (Bayer) threshold pattern is added beforehand instead,
+    /// trading the bands for a fine, regular noise that averages out to the original values.
+    pub fn to_bytes(&self, dither: bool) -> Vec<u8> {
+        self.enumerate_pixels()
+            .flat_map(|(x, y, pixel)| quantize(pixel, x as usize, y as usize, dither))
+            .collect()
+    }
+
+    /// Count pixels by [luminance](Color::luminance) into `bins` buckets evenly spread over the
+    /// log2 luminance range [LUMINANCE_HISTOGRAM_RANGE], darker and brighter pixels falling into
+    /// the first and last bucket respectively.
+    pub fn luminance_histogram(&self, bins: usize) -> Vec<u32> {
+        let mut histogram = vec![0; bins];
+        if bins == 0 {
+            return histogram;
+        }
+
+        let (min, max) = LUMINANCE_HISTOGRAM_RANGE;
+        for pixel in self.pixels.iter() {
+            // Black pixels map to -inf, saturating to the first bucket
+            let position = (pixel.luminance().log2() - min) / (max - min);
+            let bin = ((position * bins as f32) as usize).min(bins - 1);
+            histogram[bin] += 1;
+        }
+
+        histogram
+    }
+
+    /// Compare with another image of the same dimensions, returning the per-pixel absolute
+    /// difference and the mean squared error over every channel.
+    ///
+    /// # Panics
+    /// If the image dimensions differ.
+    pub fn diff(&self, other: &Self) -> (Image<Color>, f64) {
+        self.assert_same_size(other);
+
+        let pixels = self
+            .pixels
+            .iter()
+            .zip(other.pixels.iter())
+            .map(|(lhs, rhs)| {
+                Color::new(
+                    (lhs.r - rhs.r).abs(),
+                    (lhs.g - rhs.g).abs(),
+                    (lhs.b - rhs.b).abs(),
+                )
+            })
+            .collect::<Vec<Color>>();
+
+        let squared_sum = pixels
+            .iter()
+            .flat_map(|Color { r, g, b }| [r, g, b])
+            .map(|&channel| (channel as f64).powi(2))
+            .sum::<f64>();
+        let mse = squared_sum / (pixels.len() * 3).max(1) as f64;
+
+        (
+            Image {
+                width: self.width,
+                height: self.height,
+                pixels,
+            },
+            mse,
+        )
+    }
+
+    /// Largest absolute difference between two matching channels of the images.
+    ///
+    /// # Panics
+    /// If the image dimensions differ.
+    pub fn max_abs_diff(&self, other: &Self) -> f32 {
+        self.assert_same_size(other);
+
+        self.pixels
+            .iter()
+            .zip(other.pixels.iter())
+            .flat_map(|(lhs, rhs)| [lhs.r - rhs.r, lhs.g - rhs.g, lhs.b - rhs.b])
+            .fold(0., |max, diff| diff.abs().max(max))
+    }
+
+    fn assert_same_size(&self, other: &Self) {
+        assert_eq!(
+            (self.width, self.height),
+            (other.width, other.height),
+            "Image dimensions differ"
+        );
     }
 }
 
 /// Allows for easy conversion between different image types.
-pub trait Convertible<Target, Source: Into<Target>> {
+pub trait Convertible<Target, Source> {
     /// Convert image type if the underlying pixel type can be converted.
     fn convert_image(self, method: fn(Source) -> Target) -> Image<Target>;
 }
 
-impl<Target, Source: Into<Target>> Convertible<Target, Source> for Image<Source> {
+impl<Target, Source> Convertible<Target, Source> for Image<Source> {
     fn convert_image(self, method: fn(Source) -> Target) -> Image<Target> {
         let Self {
             width,
@@ -198,6 +560,219 @@ mod test {
         }
     }
 
+    #[test]
+    fn write_ppm_to_memory() {
+        let image = Image::new(3, 2, Color::new(1., 0.5, 0.));
+
+        let mut buffer = Vec::new();
+        image.write_ppm(&mut buffer).unwrap();
+
+        let header = b"P6 3 2 255\n";
+        assert_eq!(header, &buffer[..header.len()]);
+        assert_eq!(3 * 3 * 2, buffer.len() - header.len());
+        assert_eq!([255, 127, 0], buffer[header.len()..header.len() + 3]);
+    }
+
+    #[test]
+    fn write_ppm_rows_bottom_up() {
+        let (width, height) = (2, 3);
+        let mut image = Image::new(width, height, Color::default());
+        for (index, pixel) in image.pixels.iter_mut().enumerate() {
+            let value = index as f32 / 255.;
+            *pixel = Color::new(value, value, value);
+        }
+
+        let mut buffer = Vec::new();
+        image.write_ppm(&mut buffer).unwrap();
+
+        let header = b"P6 2 3 255\n".len();
+        let written = buffer[header..]
+            .chunks_exact(3)
+            .map(|rgb| rgb[0])
+            .collect::<Vec<u8>>();
+        assert_eq!(vec![4, 5, 2, 3, 0, 1], written);
+    }
+
+    #[test]
+    fn from_ppm_bytes() {
+        let mut image = Image::new(2, 3, Color::default());
+        for (index, pixel) in image.pixels.iter_mut().enumerate() {
+            let value = index as f32 / 255.;
+            *pixel = Color::new(value, 1. - value, 0.);
+        }
+
+        let mut buffer = Vec::new();
+        image.write_ppm(&mut buffer).unwrap();
+        let read = Image::from_ppm_reader(&mut buffer.as_slice()).unwrap();
+        assert!(image.max_abs_diff(&read) < 1. / 255.);
+
+        let ascii = b"P3\n# comment\n2 1\n4\n0 2 4  4 2 0\n";
+        let read = Image::from_ppm_bytes(ascii).unwrap();
+        assert_eq!(
+            vec![Color::new(0., 0.5, 1.), Color::new(1., 0.5, 0.)],
+            read.pixels
+        );
+
+        assert!(Image::from_ppm_bytes(b"P6 2 2 255\n\0\0\0").is_err());
+        assert!(Image::from_ppm_bytes(b"P5 1 1 255\n\0").is_err());
+    }
+
+    #[test]
+    fn from_ppm_bytes_huge_header() {
+        for header in [
+            b"P6 4294967295 4294967295 255\n\0".as_slice(),
+            b"P3 4294967295 4294967295 255\n0".as_slice(),
+        ] {
+            let err = Image::from_ppm_bytes(header).unwrap_err();
+            assert_eq!(std::io::ErrorKind::InvalidData, err.kind());
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn write_ppm_size_mismatch() {
+        let mut image = Image::new(3, 2, Color::default());
+        image.pixels.pop();
+
+        image.write_ppm(&mut Vec::new()).unwrap();
+    }
+
+    #[test]
+    fn dithering_breaks_up_bands() {
+        // A gradient spanning less than one 8-bit step, collapsing into a single band when
+        // truncated
+        let width = 64;
+        let mut image = Image::new(width, 4, Color::default());
+        for (x, _, pixel) in image.enumerate_pixels_mut() {
+            *pixel = Color::from((100.1 + 0.8 * x as f32 / width as f32) / 255.);
+        }
+
+        let distinct = |bytes: Vec<u8>| {
+            let mut scanline = bytes[..width as usize * 3].to_vec();
+            scanline.sort();
+            scanline.dedup();
+            scanline.len()
+        };
+
+        assert_eq!(1, distinct(image.to_bytes(false)));
+        assert!(distinct(image.to_bytes(true)) > 1);
+
+        // Dithered values average out to the original ones
+        let bytes = image.to_bytes(true);
+        let mean = bytes.iter().map(|&byte| byte as f32).sum::<f32>() / bytes.len() as f32;
+        assert!(
+            (mean - 100.5).abs() < 0.1,
+            "Expected a mean of 100.5, got {mean}"
+        );
+
+        let (mut plain, mut dithered) = (Vec::new(), Vec::new());
+        image.write_ppm(&mut plain).unwrap();
+        image.write_ppm_dithered(&mut dithered).unwrap();
+        assert_ne!(plain, dithered);
+    }
+
+    #[test]
+    fn luminance_histogram() {
+        let mut image = Image::new(4, 1, Color::default());
+        image.pixels[1] = Color::from(2f32.powi(-10));
+        image.pixels[2] = Color::from(0.5);
+        image.pixels[3] = Color::from(1000.);
+
+        // One bucket per stop
+        let histogram = image.luminance_histogram(16);
+        assert_eq!(4, histogram.iter().sum::<u32>());
+        assert_eq!(2, histogram[0]);
+        assert_eq!(1, histogram[9]);
+        assert_eq!(1, histogram[15]);
+
+        assert!(image.luminance_histogram(0).is_empty());
+    }
+
+    #[test]
+    fn enumerate_pixels() {
+        let (width, height) = (3, 2);
+        let mut image = Image::new(width, height, (0, 0));
+
+        for (x, y, pixel) in image.enumerate_pixels_mut() {
+            *pixel = (x, y);
+        }
+
+        let enumerated = image.enumerate_pixels().collect::<Vec<_>>();
+        assert_eq!((width * height) as usize, enumerated.len());
+        for (x, y, &pixel) in enumerated {
+            assert_eq!((x, y), pixel);
+            assert_eq!(pixel, image.mod_get(x, y));
+        }
+    }
+
+    #[test]
+    fn map() {
+        let image = Image {
+            width: 2,
+            height: 2,
+            pixels: vec![1., 2., 3., 4.],
+        };
+
+        let squared = image.map(|v| v * v);
+        assert_eq!((2, 2), (squared.width, squared.height));
+        assert_eq!(vec![1., 4., 9., 16.], squared.pixels);
+    }
+
+    #[test]
+    fn zip_with() {
+        let lhs = Image {
+            width: 2,
+            height: 2,
+            pixels: vec![1., 2., 3., 4.],
+        };
+        let rhs = Image {
+            width: 2,
+            height: 2,
+            pixels: vec![10., 20., 30., 40.],
+        };
+
+        assert_eq!(
+            vec![11., 22., 33., 44.],
+            lhs.zip_with(&rhs, |l, r| l + r).pixels
+        );
+
+        // Smaller images are tiled
+        let column = Image {
+            width: 1,
+            height: 2,
+            pixels: vec![100., 200.],
+        };
+        let sum = lhs.zip_with(&column, |l, r| l + r);
+        assert_eq!((2, 2), (sum.width, sum.height));
+        assert_eq!(vec![101., 102., 203., 204.], sum.pixels);
+    }
+
+    #[test]
+    fn diff() {
+        let image = Image::new(2, 2, Color::new(0.2, 0.4, 0.6));
+
+        let (diff, mse) = image.diff(&image);
+        assert_eq!(Image::new(2, 2, Color::default()), diff);
+        assert_eq!(0., mse);
+        assert_eq!(0., image.max_abs_diff(&image));
+
+        let mut changed = image.clone();
+        changed.set(1, 0, Color::new(0.7, 0.4, 0.6));
+
+        let (diff, mse) = image.diff(&changed);
+        assert!(diff.mod_get(1, 0).approx_eq(&Color::new(0.5, 0., 0.), 1e-6));
+        assert_eq!(Color::default(), diff.mod_get(0, 0));
+        // A single 0.5 channel difference over 4 pixels of 3 channels
+        assert!((mse - 0.25 / 12.).abs() < 1e-6, "Got an MSE of {mse}");
+        assert!((image.max_abs_diff(&changed) - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    #[should_panic]
+    fn diff_size_mismatch() {
+        Image::new(2, 2, Color::default()).diff(&Image::new(2, 1, Color::default()));
+    }
+
     #[test]
     fn mod_get() {
         let mut image = Image::new(10, 10, 0);
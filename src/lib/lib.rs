@@ -13,9 +13,14 @@ pub mod matrix;
 pub mod object;
 pub mod primitives;
 pub mod raycasting;
+pub mod sampler;
 pub mod scene;
 pub mod shader;
+#[cfg(feature = "spectral")]
+pub mod spectrum;
 pub mod vector;
+#[cfg(feature = "viewer")]
+pub mod viewer;
 
 pub mod engine;
 
@@ -37,7 +42,9 @@ states! {
     Building,
     /// Object's properties have been fixed and it can safely be used for rendering.
     Built,
-    /// Object has been converted to an [OpenGLObject] and cannot be converted again.
+    /// Object has been converted to an [OpenGLObject](object::OpenGLObject) and cannot be
+    /// converted again.
+    #[cfg(feature = "opengl")]
     GLConsumed
 }
 
@@ -45,6 +52,9 @@ states! {
 pub mod prelude {
     pub use super::{
         camera::*, color::*, image::*, light::*, material::*, matrix::*, object::*, primitives::*,
-        raycasting::*, scene::*, vector::*,
+        raycasting::*, sampler::*, scene::*, vector::*,
     };
+
+    #[cfg(feature = "spectral")]
+    pub use super::spectrum::*;
 }
@@ -1,9 +1,16 @@
 //! Light definition.
 
-use crate::{color::Color, matrix::Transform};
+use crate::{color::Color, matrix::Transform, vector::Vector};
+
+/// Shadow ray offset along the surface normal used by [Light]s unless specified otherwise.
+pub const DEFAULT_SHADOW_BIAS: f32 = 0.1;
 
 #[derive(Clone, Debug)]
+#[non_exhaustive]
 /// Light object that adds... light.
+///
+/// Built from one of the [point](Self::point), [directional](Self::directional) or
+/// [ambient](Self::ambient) constructors, so that adding fields does not break other crates.
 pub struct Light {
     /// 3D transform.
     pub transform: Transform,
@@ -13,6 +20,83 @@ pub struct Light {
     pub color: Color,
     /// Light brightness level.
     pub brightness: f32,
+
+    /// Radius of the light's surface, 0 casts hard shadows while larger values widen the penumbra.
+    pub shadow_softness: f32,
+    /// Offset along the surface normal applied to shadow rays to avoid self-shadowing (acne), see
+    /// [DEFAULT_SHADOW_BIAS].
+    pub shadow_bias: f32,
+}
+
+impl Light {
+    /// White [Point](LightVariant::Point) light of brightness 1 at `position`.
+    pub fn point(position: Vector<3, f32>) -> Self {
+        Self::new(
+            LightVariant::Point,
+            Transform::default().apply_translation(position),
+        )
+    }
+
+    /// White [Directional](LightVariant::Directional) light of brightness 1 shining along
+    /// `direction`.
+    pub fn directional(direction: Vector<3, f32>) -> Self {
+        Self::new(
+            LightVariant::Directional(direction.normalize()),
+            Transform::default(),
+        )
+    }
+
+    /// White [Ambient](LightVariant::Ambient) light of brightness 1.
+    pub fn ambient() -> Self {
+        Self::new(LightVariant::Ambient, Transform::default())
+    }
+
+    fn new(variant: LightVariant, transform: Transform) -> Self {
+        Self {
+            transform,
+            variant,
+            color: Color::new(1., 1., 1.),
+            brightness: 1.,
+            shadow_softness: 0.,
+            shadow_bias: DEFAULT_SHADOW_BIAS,
+        }
+    }
+
+    /// Set the light's color.
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Set the light's brightness level.
+    pub fn brightness(mut self, brightness: f32) -> Self {
+        self.brightness = brightness;
+        self
+    }
+
+    /// Set the light's [shadow_softness](Self::shadow_softness).
+    pub fn shadow_softness(mut self, shadow_softness: f32) -> Self {
+        self.shadow_softness = shadow_softness;
+        self
+    }
+
+    /// Set the light's [shadow_bias](Self::shadow_bias).
+    pub fn shadow_bias(mut self, shadow_bias: f32) -> Self {
+        self.shadow_bias = shadow_bias;
+        self
+    }
+
+    /// Direction from `position` toward the light, unnormalized for point lights, along with the
+    /// distance to it, infinite for directional ones.
+    pub fn toward(&self, position: Vector<3, f32>) -> (Vector<3, f32>, f32) {
+        match self.variant {
+            LightVariant::Directional(direction) => (direction * -1., f32::INFINITY),
+            _ => {
+                let toward = self.transform.translation() - position;
+                (toward, toward.len())
+            }
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -20,6 +104,37 @@ pub struct Light {
 pub enum LightVariant {
     /// Point light that shines in all directions.
     Point,
+    /// Infinitely far light, e.g. the sun, shining along a normalized direction without any
+    /// falloff. Its [transform](Light::transform) is ignored.
+    Directional(Vector<3, f32>),
     /// Ambient light pointing in a certain direction.
     Ambient,
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn builder() {
+        let light = Light::point(Vector::new(1., 2., 3.))
+            .color(Color::new(1., 0.5, 0.))
+            .brightness(2.);
+
+        assert_eq!(Vector::new(1., 2., 3.), light.transform.translation());
+        assert_eq!(LightVariant::Point, light.variant);
+        assert_eq!(Color::new(1., 0.5, 0.), light.color);
+        assert_eq!(2., light.brightness);
+        assert_eq!(DEFAULT_SHADOW_BIAS, light.shadow_bias);
+
+        let sun = Light::directional(Vector::new(0., -2., 0.));
+        assert_eq!(
+            LightVariant::Directional(Vector::new(0., -1., 0.)),
+            sun.variant
+        );
+        assert_eq!(
+            (Vector::new(0., 1., 0.), f32::INFINITY),
+            sun.toward(Vector::new(5., 0., 5.))
+        );
+    }
+}
@@ -1,70 +1,238 @@
 //! Material shader definition.
 
-use std::collections::HashMap;
+use std::{collections::HashMap, path::Path};
 
 use crate::{
     color::Color,
+    image::Image,
+    primitives::{Triangle, UV_CHANNELS},
+    sampler::{Footprint, Sampler},
     shader::{
-        graph::{Error, Graph, Name, SocketValue, Validated},
+        graph::{Error, Graph, Name, SocketValue, Unvalidated, Validated},
         shader::Side,
     },
+    vector::Vector,
 };
 
+/// Diffuse coefficient used by [Material::diffuse], and by the renderer for materials without a
+/// diffuse output.
+pub const DEFAULT_DIFFUSE: f32 = 0.5;
+
+/// Name of the reserved graph input set to the current time by [Material::update].
+pub const TIME_INPUT: &str = "time";
+
 #[derive(Debug, Clone, Default)]
 /// A material to be associated with an [Object] for rendering.
 pub struct Material {
     selected_outputs: HashMap<StandardMaterialOutput, Name>,
     graph: Graph<Validated>,
     recompute: bool,
+    sampler: Sampler,
+    uv_channel: usize,
+    unlit: bool,
 }
 
-impl From<(Graph<Validated>, HashMap<StandardMaterialOutput, Name>)> for Material {
-    fn from(
+impl TryFrom<(Graph<Validated>, HashMap<StandardMaterialOutput, Name>)> for Material {
+    type Error = Error;
+
+    /// Create a [Material], checking that all selected outputs exist in the graph.
+    fn try_from(
         (graph, selected_outputs): (Graph<Validated>, HashMap<StandardMaterialOutput, Name>),
-    ) -> Self {
-        Material {
+    ) -> Result<Self, Self::Error> {
+        if let Some(name) = selected_outputs
+            .values()
+            .find(|&name| !graph.outputs.contains_key(name))
+        {
+            let mut available = graph.outputs.keys().cloned().collect::<Vec<Name>>();
+            available.sort_by_key(|name| name.to_string());
+
+            return Err(Error::UnknownOutput {
+                name: name.clone(),
+                available,
+            });
+        }
+
+        Ok(Material {
             selected_outputs,
             graph,
             recompute: true,
-        }
+            sampler: Sampler::default(),
+            uv_channel: 0,
+            unlit: false,
+        })
     }
 }
 
 impl Material {
+    /// Start building a [Material] around the given [Graph].
+    /// # Example
+    /// ```
+    /// use eray::{graph, prelude::*, shader::graph::SocketValue};
+    ///
+    /// let graph = graph! {
+    ///     inputs,
+    ///     nodes,
+    ///     outputs:
+    ///         "color": (None, SocketValue::IColor(Some(Image::default()))),
+    /// };
+    ///
+    /// let material = Material::builder(graph.validate().unwrap())
+    ///     .with_output(StandardMaterialOutput::Color, "color")
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn builder(graph: Graph<Validated>) -> MaterialBuilder {
+        MaterialBuilder {
+            selected_outputs: HashMap::new(),
+            graph,
+            sampler: Sampler::default(),
+            uv_channel: 0,
+            unlit: false,
+        }
+    }
+
+    /// Matte material of a single `color`, with a [DEFAULT_DIFFUSE] coefficient and no specular
+    /// highlight.
+    pub fn diffuse(color: Color) -> Self {
+        Self::preset(
+            color,
+            [
+                (StandardMaterialOutput::Diffuse, DEFAULT_DIFFUSE),
+                (StandardMaterialOutput::Specular, 0.),
+            ],
+        )
+    }
+
+    /// Matte material like [diffuse](Self::diffuse) letting through `1 - alpha` of what is behind
+    /// it.
+    pub fn transparent(color: Color, alpha: f32) -> Self {
+        Self::preset(
+            color,
+            [
+                (StandardMaterialOutput::Diffuse, DEFAULT_DIFFUSE),
+                (StandardMaterialOutput::Specular, 0.),
+                (StandardMaterialOutput::Alpha, alpha.clamp(0., 1.)),
+            ],
+        )
+    }
+
+    /// [Unlit](Self::set_unlit) material of a single `color`, e.g. for emissive surfaces.
+    pub fn unlit(color: Color) -> Self {
+        let mut material = Self::preset(color, []);
+        material.set_unlit(true);
+        material
+    }
+
+    /// Near-black perfect mirror, only showing what it reflects.
+    pub fn mirror() -> Self {
+        Self::preset(
+            Color::new(0.02, 0.02, 0.02),
+            [
+                (StandardMaterialOutput::Diffuse, 0.1),
+                (StandardMaterialOutput::Specular, 0.),
+                (StandardMaterialOutput::Reflection, 1.),
+            ],
+        )
+    }
+
+    /// Grey glossy material, going from a sharp highlight and strong reflection at a `roughness`
+    /// of 0 to a wide highlight and no reflection at 1.
+    pub fn glossy(roughness: f32) -> Self {
+        let roughness = roughness.clamp(0., 1.);
+
+        Self::preset(
+            Color::new(0.5, 0.5, 0.5),
+            [
+                (StandardMaterialOutput::Diffuse, 0.5),
+                (StandardMaterialOutput::Specular, 1. - roughness * 0.5),
+                // Blinn-Phong exponent approximating the roughness
+                (
+                    StandardMaterialOutput::SpecularPower,
+                    2. / roughness.max(0.05).powi(2) - 2.,
+                ),
+                (StandardMaterialOutput::Reflection, 1. - roughness),
+            ],
+        )
+    }
+
+    /// Material with a constant color and constant values for the other given outputs.
+    fn preset(
+        color: Color,
+        values: impl IntoIterator<Item = (StandardMaterialOutput, f32)>,
+    ) -> Self {
+        let values = values
+            .into_iter()
+            .map(|(output, value)| (output, SocketValue::IValue(Some(Image::new(1, 1, value)))));
+        let outputs = std::iter::once((
+            StandardMaterialOutput::Color,
+            SocketValue::IColor(Some(Image::new(1, 1, color))),
+        ))
+        .chain(values)
+        .collect::<Vec<_>>();
+
+        let graph = Graph::<Unvalidated> {
+            inputs: HashMap::new(),
+            outputs: outputs
+                .iter()
+                .map(|(output, value)| (output.name(), (None, value.clone())))
+                .collect(),
+            nodes: HashMap::new(),
+            output_policy: Default::default(),
+            state: std::marker::PhantomData,
+        }
+        .validate()
+        .expect("Preset graphs have no nodes to validate");
+
+        outputs
+            .into_iter()
+            .fold(Material::builder(graph), |builder, (output, _value)| {
+                let name = output.name();
+                builder.with_output(output, name)
+            })
+            .build()
+            .expect("Preset outputs all exist in the graph")
+    }
+
     /// Recomputes the inner graph if needed.
-    pub fn update(&mut self) -> Result<(), Error> {
+    ///
+    /// If the graph has a [TIME_INPUT] input, it is set to `time` and the graph is recomputed
+    /// whenever that value changes.
+    pub fn update(&mut self, time: f32) -> Result<(), Error> {
+        if let Some(input) = self.graph.inputs.get_mut(&TIME_INPUT.into()) {
+            let time = SocketValue::Value(Some(time));
+            if *input != time {
+                *input = time;
+                self.recompute = true;
+            }
+        }
+
         if self.recompute {
+            self.graph.reset();
             self.graph.run()?;
             self.recompute = false;
         }
 
-        #[cfg(debug_assertions)]
-        self.selected_outputs
-            .get(&StandardMaterialOutput::Color)
-            .and_then(|name| self.graph.outputs.get(name))
-            .map(|(_ref, value)| match value {
-                SocketValue::IColor(image) => image
-                    .as_ref()
-                    .map(|image| image.save_as_ppm(std::path::Path::new("color.ppm"))),
-                _ => panic!(),
-            });
-
         Ok(())
     }
 
     /// Retrieve all standard information about a pixel in the shader graph's result.
     pub fn get(&self, x: f32, y: f32) -> MaterialOutputBundle {
+        self.get_filtered(x, y, &Footprint::default())
+    }
+
+    /// Same as [get](Self::get), letting the material's [Sampler] filter the graph's output
+    /// images over the given UV [Footprint].
+    pub fn get_filtered(&self, x: f32, y: f32, footprint: &Footprint) -> MaterialOutputBundle {
+        let uv = Vector::from([x, y]);
+
         let get_value = |output: StandardMaterialOutput| {
             self.selected_outputs
                 .get(&output)
                 .and_then(|name| self.graph.outputs.get(name))
                 .and_then(|(_ref, value)| match value {
-                    SocketValue::IValue(image) => image.as_ref().map(|image| {
-                        image.mod_get(
-                            (x * image.width as f32) as u32,
-                            (y * image.height as f32) as u32,
-                        )
-                    }),
+                    SocketValue::IValue(image) => image
+                        .as_ref()
+                        .map(|image| self.sampler.sample(image, uv, footprint)),
                     _ => None,
                 })
         };
@@ -78,19 +246,134 @@ impl Material {
                     res
                 })
                 .and_then(|(_ref, value)| match value {
-                    SocketValue::IColor(image) => image.as_ref().map(|image| {
-                        image.mod_get(
-                            (x * image.width as f32) as u32,
-                            (y * image.height as f32) as u32,
-                        )
-                    }),
+                    SocketValue::IColor(image) => image
+                        .as_ref()
+                        .map(|image| self.sampler.sample(image, uv, footprint)),
                     _ => None,
                 }),
             diffuse: get_value(StandardMaterialOutput::Diffuse),
             specular: get_value(StandardMaterialOutput::Specular),
             specular_power: get_value(StandardMaterialOutput::SpecularPower),
             reflection: get_value(StandardMaterialOutput::Reflection),
+            alpha: get_value(StandardMaterialOutput::Alpha),
+            unlit: self.unlit,
+        }
+    }
+
+    /// Evaluate the material's color over the whole UV square into a `width` by `height` texture,
+    /// sampling at texel centers. Texels without a color are left black.
+    pub fn bake(&self, width: u32, height: u32) -> Image<Color> {
+        let mut image = Image::new(width, height, Color::default());
+        for (x, y, pixel) in image.enumerate_pixels_mut() {
+            *pixel = self.bake_texel(x, y, width, height);
+        }
+        image
+    }
+
+    /// Same as [bake](Self::bake), only filling the texels whose center lies within one of the
+    /// `faces` as unwrapped by the material's [UV channel](Self::uv_channel), e.g. to bake a mesh's
+    /// texture atlas. Uncovered texels are left black.
+    pub fn bake_faces(&self, faces: &[Triangle], width: u32, height: u32) -> Image<Color> {
+        let mut image = Image::new(width, height, Color::default());
+        let size = Vector::from([width as f32, height as f32]);
+
+        // Twice the signed area of the UV triangle (o, a, b)
+        let cross = |o: Vector<2, f32>, a: Vector<2, f32>, b: Vector<2, f32>| {
+            (a[0] - o[0]) * (b[1] - o[1]) - (a[1] - o[1]) * (b[0] - o[0])
+        };
+
+        for face in faces {
+            let [a, b, c] = [&face.a, &face.b, &face.c].map(|vertex| {
+                let uv = vertex.uvs[self.uv_channel];
+                Vector::from([uv[0] * size[0], uv[1] * size[1]])
+            });
+
+            let area = cross(a, b, c);
+            if area.abs() < f32::EPSILON {
+                continue;
+            }
+
+            // Texel-space bounding box, clamped to the texture
+            let range = |axis: usize, size: u32| {
+                let coords = [a[axis], b[axis], c[axis]];
+                let min = coords
+                    .into_iter()
+                    .fold(f32::INFINITY, f32::min)
+                    .floor()
+                    .max(0.);
+                let max = coords.into_iter().fold(f32::NEG_INFINITY, f32::max).ceil();
+                min as u32..(max.max(0.) as u32).min(size)
+            };
+
+            for y in range(1, height) {
+                for x in range(0, width) {
+                    let center = Vector::from([x as f32 + 0.5, y as f32 + 0.5]);
+                    let inside = [
+                        cross(b, c, center),
+                        cross(c, a, center),
+                        cross(a, b, center),
+                    ]
+                    .into_iter()
+                    .all(|weight| weight / area >= -1e-5);
+
+                    if inside {
+                        image.set(x, y, self.bake_texel(x, y, width, height));
+                    }
+                }
+            }
         }
+
+        image
+    }
+
+    /// Color at the center of texel (`x`, `y`) of a `width` by `height` texture, filtered over
+    /// that texel.
+    fn bake_texel(&self, x: u32, y: u32, width: u32, height: u32) -> Color {
+        let (du, dv) = (1. / width as f32, 1. / height as f32);
+        let footprint = Footprint {
+            dx: Vector::from([du, 0.]),
+            dy: Vector::from([0., dv]),
+        };
+
+        self.get_filtered((x as f32 + 0.5) * du, (y as f32 + 0.5) * dv, &footprint)
+            .color
+            .unwrap_or_default()
+    }
+
+    /// Set the [Sampler] used to read the graph's output images.
+    pub fn set_sampler(&mut self, sampler: Sampler) -> &mut Self {
+        self.sampler = sampler;
+        self
+    }
+
+    /// Get the [Vertex](crate::primitives::Vertex) UV channel used to read the graph's output
+    /// images.
+    pub fn uv_channel(&self) -> usize {
+        self.uv_channel
+    }
+
+    /// Set the [Vertex](crate::primitives::Vertex) UV channel used to read the graph's output
+    /// images.
+    ///
+    /// # Panics
+    /// Panics if `channel` is not below [UV_CHANNELS].
+    pub fn set_uv_channel(&mut self, channel: usize) -> &mut Self {
+        assert!(channel < UV_CHANNELS, "UV channel {channel} out of range");
+        self.uv_channel = channel;
+        self
+    }
+
+    /// Whether the material's color is shown as is, see [set_unlit](Self::set_unlit).
+    pub fn is_unlit(&self) -> bool {
+        self.unlit
+    }
+
+    /// Show the material's color as is, skipping lighting, shadows, reflections and global
+    /// illumination altogether. Only [alpha](StandardMaterialOutput::Alpha) is still taken into
+    /// account.
+    pub fn set_unlit(&mut self, unlit: bool) -> &mut Self {
+        self.unlit = unlit;
+        self
     }
 
     /// Set the value of a graph input.
@@ -99,8 +382,85 @@ impl Material {
             .inputs
             .get_mut(name)
             .ok_or_else(|| Error::Missing(Side::Input, name.clone()))
-            .map(|old| *old = value)
-            .map(|_| self)
+            .map(|old| *old = value)?;
+
+        self.recompute = true;
+        Ok(self)
+    }
+}
+
+/// Create a [Material] whose color output is the image file at `path` (PNG or JPEG), sampled
+/// through the material's [Sampler].
+pub fn texture_material(path: &Path) -> std::io::Result<Material> {
+    let image = Image::load(path)?;
+    let graph = crate::graph! {
+        inputs,
+        nodes,
+        outputs:
+            "color": (None, SocketValue::IColor(Some(image))),
+    }
+    .validate()
+    .expect("Texture graph output should hold the loaded image");
+
+    Ok(Material::builder(graph)
+        .with_output(StandardMaterialOutput::Color, "color")
+        .build()
+        .expect("Texture graph should have a color output"))
+}
+
+#[derive(Debug, Clone)]
+/// Builder for a [Material], see [Material::builder].
+pub struct MaterialBuilder {
+    selected_outputs: HashMap<StandardMaterialOutput, Name>,
+    graph: Graph<Validated>,
+    sampler: Sampler,
+    uv_channel: usize,
+    unlit: bool,
+}
+
+impl MaterialBuilder {
+    /// Map a [StandardMaterialOutput] to one of the graph's outputs.
+    pub fn with_output(mut self, output: StandardMaterialOutput, name: impl Into<Name>) -> Self {
+        self.selected_outputs.insert(output, name.into());
+        self
+    }
+
+    /// Set the [Sampler] used to read the graph's output images.
+    pub fn with_sampler(mut self, sampler: Sampler) -> Self {
+        self.sampler = sampler;
+        self
+    }
+
+    /// Set the UV channel used to read the graph's output images, see [Material::set_uv_channel].
+    pub fn with_uv_channel(mut self, channel: usize) -> Self {
+        self.uv_channel = channel;
+        self
+    }
+
+    /// Show the material's color as is, see [Material::set_unlit].
+    pub fn with_unlit(mut self, unlit: bool) -> Self {
+        self.unlit = unlit;
+        self
+    }
+
+    /// Check that all selected outputs exist in the graph and build the [Material].
+    ///
+    /// # Errors
+    /// [UvChannelOutOfRange](Error::UvChannelOutOfRange) if the UV channel is not below
+    /// [UV_CHANNELS], [UnknownOutput](Error::UnknownOutput) for a missing selected output.
+    pub fn build(self) -> Result<Material, Error> {
+        if self.uv_channel >= UV_CHANNELS {
+            return Err(Error::UvChannelOutOfRange {
+                channel: self.uv_channel,
+                available: UV_CHANNELS,
+            });
+        }
+
+        let mut material = Material::try_from((self.graph, self.selected_outputs))?;
+        material.sampler = self.sampler;
+        material.unlit = self.unlit;
+        material.set_uv_channel(self.uv_channel);
+        Ok(material)
     }
 }
 
@@ -113,6 +473,22 @@ pub enum StandardMaterialOutput {
     Specular,
     SpecularPower,
     Reflection,
+    Alpha,
+}
+
+impl StandardMaterialOutput {
+    /// Conventional graph output name for this output, e.g. `specular_power`.
+    pub fn name(&self) -> Name {
+        match self {
+            StandardMaterialOutput::Color => "color",
+            StandardMaterialOutput::Diffuse => "diffuse",
+            StandardMaterialOutput::Specular => "specular",
+            StandardMaterialOutput::SpecularPower => "specular_power",
+            StandardMaterialOutput::Reflection => "reflection",
+            StandardMaterialOutput::Alpha => "alpha",
+        }
+        .into()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -128,4 +504,223 @@ pub struct MaterialOutputBundle {
     pub specular_power: Option<f32>,
     /// How much light is reflected.
     pub reflection: Option<f32>,
+    /// Opacity at point, from fully transparent at 0 to opaque at 1 (default).
+    pub alpha: Option<f32>,
+    /// Whether the color is shown as is, ignoring lighting, see [Material::set_unlit].
+    pub unlit: bool,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{get_sv, graph, node, primitives::Vertex, shader::graph::SocketType, ssref};
+
+    fn setup_graph() -> Graph<Validated> {
+        graph! {
+            inputs,
+            nodes,
+            outputs:
+                "color": (None, SocketValue::IColor(Some(Image::default()))),
+                "diffuse": (None, SocketValue::IValue(Some(Image::default()))),
+        }
+        .validate()
+        .unwrap()
+    }
+
+    #[test]
+    fn builder() {
+        let material = Material::builder(setup_graph())
+            .with_output(StandardMaterialOutput::Color, "color")
+            .with_output(StandardMaterialOutput::Diffuse, "diffuse")
+            .build();
+
+        assert!(material.is_ok(), "Expected a success, got `{material:?}`");
+    }
+
+    #[test]
+    fn builder_missing_output() {
+        let material = Material::builder(setup_graph())
+            .with_output(StandardMaterialOutput::Color, "colour")
+            .build();
+
+        assert_eq!(
+            material.unwrap_err(),
+            Error::UnknownOutput {
+                name: "colour".into(),
+                available: vec!["color".into(), "diffuse".into()],
+            }
+        );
+    }
+
+    #[test]
+    fn builder_uv_channel_out_of_range() {
+        let material = Material::builder(setup_graph())
+            .with_output(StandardMaterialOutput::Color, "color")
+            .with_uv_channel(UV_CHANNELS)
+            .build();
+
+        assert_eq!(
+            material.unwrap_err(),
+            Error::UvChannelOutOfRange {
+                channel: UV_CHANNELS,
+                available: UV_CHANNELS,
+            }
+        );
+    }
+
+    #[test]
+    fn unknown_output_error() {
+        let error = Material::try_from((
+            setup_graph(),
+            [(StandardMaterialOutput::Specular, Name::from("specular"))]
+                .into_iter()
+                .collect(),
+        ))
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "Unknown graph output `specular`, available outputs are: color, diffuse"
+        );
+    }
+
+    #[test]
+    fn time_dependent_update() {
+        let graph = graph! {
+            inputs:
+                "time": SocketType::Value.into(),
+            nodes:
+                "clock": node! {
+                    inputs:
+                        "time": (ssref!(graph "time"), SocketType::Value),
+                    outputs:
+                        "value": SocketType::IValue.into();
+                    |inputs, outputs| {
+                        get_sv!( input | inputs  . "time" : Value > time);
+                        get_sv!(output | outputs . "value" : IValue > out);
+
+                        out.replace(Image::new(2, 2, time.unwrap_or(0.)));
+
+                        Ok(())
+                    }
+                },
+            outputs:
+                "diffuse": (ssref!(node "clock" "value"), SocketType::IValue.into()),
+        };
+
+        let mut material = Material::builder(graph.validate().unwrap())
+            .with_output(StandardMaterialOutput::Diffuse, "diffuse")
+            .build()
+            .unwrap();
+
+        let mut output = |time| {
+            material.update(time).unwrap();
+            material
+                .graph
+                .outputs
+                .get(&"diffuse".into())
+                .unwrap()
+                .1
+                .clone()
+        };
+
+        let (start, end) = (output(0.), output(1.));
+
+        assert_eq!(start, SocketValue::IValue(Some(Image::new(2, 2, 0.))));
+        assert_eq!(end, SocketValue::IValue(Some(Image::new(2, 2, 1.))));
+    }
+
+    #[test]
+    fn texture() {
+        let path =
+            std::env::temp_dir().join(format!("eray_texture_material_{}.png", std::process::id()));
+        let mut file = ::image::RgbImage::new(4, 2);
+        file.put_pixel(1, 0, ::image::Rgb([255, 0, 0]));
+        file.put_pixel(2, 1, ::image::Rgb([0, 51, 255]));
+        file.save(&path).unwrap();
+
+        let material = texture_material(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        // UVs at texel centers
+        assert_eq!(
+            Some(Color::new(1., 0., 0.)),
+            material.get(0.375, 0.25).color
+        );
+        assert_eq!(
+            Some(Color::new(0., 0.2, 1.)),
+            material.get(0.625, 0.75).color
+        );
+        assert_eq!(
+            Some(Color::new(0., 0., 0.)),
+            material.get(0.125, 0.75).color
+        );
+    }
+
+    #[test]
+    fn bake_faces() {
+        let mut texture = Image::new(4, 2, Color::default());
+        for (x, y, pixel) in texture.enumerate_pixels_mut() {
+            *pixel = Color::new(x as f32 / 3., y as f32, 0.5);
+        }
+
+        let graph = graph! {
+            inputs,
+            nodes,
+            outputs:
+                "color": (None, SocketValue::IColor(Some(texture))),
+        };
+        let material = Material::builder(graph.validate().unwrap())
+            .with_output(StandardMaterialOutput::Color, "color")
+            .build()
+            .unwrap();
+
+        // Quad covering the left half of the UV square
+        let vertex = |u, v| {
+            Vertex::new(
+                Vector::new(u, v, 0.),
+                Vector::new(0., 0., 1.),
+                [u, v].into(),
+            )
+        };
+        let faces = [
+            Triangle::new(vertex(0., 0.), vertex(0.5, 0.), vertex(0.5, 1.)),
+            Triangle::new(vertex(0., 0.), vertex(0.5, 1.), vertex(0., 1.)),
+        ];
+
+        let (width, height) = (8, 4);
+        let baked = material.bake_faces(&faces, width, height);
+        let full = material.bake(width, height);
+
+        for (x, y, &texel) in baked.enumerate_pixels() {
+            let (u, v) = (
+                (x as f32 + 0.5) / width as f32,
+                (y as f32 + 0.5) / height as f32,
+            );
+            let expected = if u < 0.5 {
+                material.get(u, v).color.unwrap()
+            } else {
+                Color::default()
+            };
+
+            assert_eq!(expected, texel, "Unexpected texel at ({x}, {y})");
+            if u < 0.5 {
+                assert_eq!(full.mod_get(x, y), texel);
+            }
+        }
+    }
+
+    #[test]
+    fn presets() {
+        let mirror = Material::mirror().get(0.5, 0.5);
+        assert_eq!(Some(1.), mirror.reflection);
+        assert!(mirror.color.unwrap().r < 0.1);
+
+        let (sharp, rough) = (
+            Material::glossy(0.1).get(0., 0.),
+            Material::glossy(0.9).get(0., 0.),
+        );
+        assert!(sharp.reflection.unwrap() > rough.reflection.unwrap());
+        assert!(sharp.specular_power.unwrap() > rough.specular_power.unwrap());
+    }
 }
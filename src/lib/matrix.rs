@@ -4,13 +4,77 @@ use std::ops::Mul;
 
 use crate::vector::Vector;
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, PartialEq)]
 /// 4x4 matrix
 pub struct Mat4 {
     /// Arrays storing the matrix data
     pub inner: [[f32; 4]; 4],
 }
 
+impl Mat4 {
+    /// Identity matrix.
+    pub fn identity() -> Self {
+        let mut res = Mat4::default();
+        (0..4).for_each(|i| res.inner[i][i] = 1.);
+        res
+    }
+
+    /// Swap rows and columns.
+    pub fn transpose(&self) -> Self {
+        let mut res = Mat4::default();
+        for i in 0..4 {
+            for j in 0..4 {
+                res.inner[i][j] = self.inner[j][i];
+            }
+        }
+        res
+    }
+
+    /// Invert an affine transformation matrix (i.e. with a `[0, 0, 0, 1]` last row).
+    ///
+    /// Returns [None] if the matrix is singular.
+    pub fn inverse(&self) -> Option<Self> {
+        let m = &self.inner;
+        let cofactor = |row: usize, col: usize| {
+            let (r0, r1) = ((row + 1) % 3, (row + 2) % 3);
+            let (c0, c1) = ((col + 1) % 3, (col + 2) % 3);
+            m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0]
+        };
+
+        let det = (0..3).map(|col| m[0][col] * cofactor(0, col)).sum::<f32>();
+        if det == 0. || !det.is_finite() {
+            return None;
+        }
+
+        let mut res = Mat4::identity();
+        for row in 0..3 {
+            for col in 0..3 {
+                res.inner[row][col] = cofactor(col, row) / det;
+            }
+        }
+
+        let translation = Vector::new(m[0][3], m[1][3], m[2][3]);
+        let inv_translation = res.transform_vector(translation);
+        for row in 0..3 {
+            res.inner[row][3] = -inv_translation[row];
+        }
+
+        Some(res)
+    }
+
+    /// Apply the matrix to a position, i.e. with an implicit `w` coordinate of 1.
+    pub fn transform_point(&self, point: Vector<3, f32>) -> Vector<3, f32> {
+        self.transform_vector(point)
+            + Vector::new(self.inner[0][3], self.inner[1][3], self.inner[2][3])
+    }
+
+    /// Apply the matrix to a direction, i.e. with an implicit `w` coordinate of 0.
+    pub fn transform_vector(&self, vector: Vector<3, f32>) -> Vector<3, f32> {
+        let row = |i: usize| (0..3).map(|j| self.inner[i][j] * vector[j]).sum::<f32>();
+        Vector::new(row(0), row(1), row(2))
+    }
+}
+
 impl Mul<Mat4> for Mat4 {
     type Output = Mat4;
 
@@ -29,7 +93,21 @@ impl Mul<Mat4> for Mat4 {
     }
 }
 
-#[derive(Clone, Debug, Default)]
+impl From<Vector<3, f32>> for Mat4 {
+    /// Translation matrix.
+    fn from(delta: Vector<3, f32>) -> Self {
+        Transform::new_translation(delta)
+    }
+}
+
+impl From<Mat4> for Vector<3, f32> {
+    /// Translation part of the matrix.
+    fn from(matrix: Mat4) -> Self {
+        Vector::new(matrix.inner[0][3], matrix.inner[1][3], matrix.inner[2][3])
+    }
+}
+
+#[derive(Clone, Debug)]
 /// 3D transformation representation
 pub struct Transform {
     inner: Mat4,
@@ -39,7 +117,24 @@ pub struct Transform {
     scale: Vector<3, f32>,
 }
 
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            inner: Mat4::identity(),
+            translation: Default::default(),
+            rotation: Default::default(),
+            scale: Default::default(),
+        }
+    }
+}
+
 impl Transform {
+    #[inline]
+    /// Inlined O(1) accessor to the object to world space matrix.
+    pub fn matrix(&self) -> &Mat4 {
+        &self.inner
+    }
+
     #[inline]
     /// Inlined O(1) accessor.
     pub fn translation(&self) -> Vector<3, f32> {
@@ -59,7 +154,7 @@ impl Transform {
     }
 
     fn new_translation(delta: Vector<3, f32>) -> Mat4 {
-        let mut res = Mat4::default();
+        let mut res = Mat4::identity();
 
         res.inner[0][3] = delta[0];
         res.inner[1][3] = delta[1];
@@ -69,7 +164,7 @@ impl Transform {
     }
 
     fn new_scaling(delta: Vector<3, f32>) -> Mat4 {
-        let mut res = Mat4::default();
+        let mut res = Mat4::identity();
 
         res.inner[0][0] = delta[0];
         res.inner[1][1] = delta[1];
@@ -79,7 +174,7 @@ impl Transform {
     }
 
     fn new_rotation(axis: Vector<3, f32>, angle: f32) -> Mat4 {
-        let mut res = Mat4::default();
+        let mut res = Mat4::identity();
 
         let asin = angle.sin();
         let acos = angle.cos();
@@ -94,7 +189,7 @@ impl Transform {
         res.inner[1][2] = axis[1] * axis[2] * ncos - axis[0] * asin;
 
         res.inner[2][0] = axis[2] * axis[0] * ncos - axis[1] * asin;
-        res.inner[2][1] = axis[2] * axis[1] * ncos - axis[0] * asin;
+        res.inner[2][1] = axis[2] * axis[1] * ncos + axis[0] * asin;
         res.inner[2][2] = acos + axis[2].powi(2) * ncos;
 
         res
@@ -121,3 +216,74 @@ impl Transform {
         self
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn default_is_identity() {
+        let point = Vector::new(-1., 0.5, 4.);
+        assert_eq!(point, Transform::default().matrix().transform_point(point));
+
+        assert_eq!(
+            point * 2.,
+            Transform::default()
+                .apply_scale(Vector::new(2., 2., 2.))
+                .matrix()
+                .transform_point(point)
+        );
+    }
+
+    #[test]
+    fn rotation() {
+        let quarter = std::f32::consts::FRAC_PI_2;
+        let rotate = |axis: Vector<3, f32>, point: Vector<3, f32>| {
+            Transform::default()
+                .apply_rotation(axis, quarter)
+                .matrix()
+                .transform_point(point)
+        };
+
+        // Counter-clockwise quarter turns around each axis
+        let (x, y, z) = (
+            Vector::new(1., 0., 0.),
+            Vector::new(0., 1., 0.),
+            Vector::new(0., 0., 1.),
+        );
+        for (axis, point, expected) in [(x, y, z), (x, z, y * -1.), (y, z, x), (z, x, y)] {
+            let rotated = rotate(axis, point);
+            assert!(
+                (rotated - expected).len() < 1e-5,
+                "Got {rotated:?}, expected {expected:?}"
+            );
+        }
+
+        // Rotations around any axis preserve lengths
+        let axis = Vector::new(1., 2., 3.);
+        let point = Vector::new(-1., 0.5, 4.);
+        assert!((rotate(axis, point).len() - point.len()).abs() < 1e-5);
+    }
+
+    #[test]
+    fn inverse() {
+        let transform = Transform::default()
+            .apply_translation(Vector::new(1., 2., 3.))
+            .apply_rotation(Vector::new(0., 1., 0.), 0.5)
+            .apply_scale(Vector::new(2., 2., 2.));
+        let inverse = transform.matrix().inverse().unwrap();
+
+        let point = Vector::new(-1., 0.5, 4.);
+        let round_trip = inverse.transform_point(transform.matrix().transform_point(point));
+        assert!(round_trip.approx_eq(&point, 1e-5), "Got {round_trip:?}");
+
+        assert_eq!(
+            Vector::new(1., 2., 3.),
+            Transform::default()
+                .apply_translation(Vector::new(1., 2., 3.))
+                .matrix()
+                .transform_point(Vector::new(0., 0., 0.))
+        );
+        assert!(Mat4::default().inverse().is_none());
+    }
+}
@@ -1,24 +1,35 @@
-//! [Object] and [OpenGLObject] definitions along with auxilliary / helper functions and data
-//! structures.
+//! [Object] and `OpenGLObject` (with the `opengl` feature) definitions along with auxilliary /
+//! helper functions and data structures.
 
 use std::{
-    mem::{size_of, size_of_val},
+    collections::HashMap,
     ops::Range,
     path::Path,
     str::SplitWhitespace,
+    sync::atomic::{AtomicUsize, Ordering},
 };
 
+#[cfg(feature = "opengl")]
+use std::mem::{size_of, size_of_val};
+
 use crate::{
     material::Material,
     primitives::{Triangle, Vertex},
     raycasting::{Ray, RaycastHit},
+    sampler::Footprint,
     vector::Vector,
-    Building, Built, GLConsumed,
+    Building, Built,
 };
 
+#[cfg(feature = "opengl")]
+use crate::GLConsumed;
+
+#[cfg(feature = "opengl")]
 #[derive(Debug)]
 /// OpenGL-ready helper struct.
 pub struct OpenGLObject {
+    /// OpenGL index of the VAO holding this object's vertex attributes setup.
+    pub vao: u32,
     /// OpenGL index of this object's vertices VBO.
     pub vertices_vbo: u32,
     /// OpenGL index of this object's normals VBO.
@@ -43,6 +54,11 @@ pub struct Object<State> {
 
     /// All faces are 3-gons (i.e. [Triangle] instances).
     pub faces: Vec<Triangle<3, f32>>,
+    /// Smoothing group of each face as set by OBJ `s` directives, [None] for flat shaded ones.
+    ///
+    /// Vertex normals are recomputed from it when [building](Object::build) if it covers every
+    /// face, and left untouched otherwise.
+    pub smoothing_groups: Vec<Option<u32>>,
 
     /// Min and max coordinates of the object in x, y and z.
     pub bounding_box: BoundingBox,
@@ -56,22 +72,43 @@ impl Object<Built> {
     ///
     /// Uses the contained [BoundingBox] to ignore objects.
     pub fn intersects(&self, ray: &Ray) -> Option<RaycastHit> {
+        self.intersects_counted(ray, &AtomicUsize::default())
+    }
+
+    /// Same as [intersects](Self::intersects), adding the number of triangles tested against the
+    /// ray to `tested`.
+    pub fn intersects_counted(&self, ray: &Ray, tested: &AtomicUsize) -> Option<RaycastHit> {
         if !self.bounding_box.intersects(ray) {
             return None;
         }
 
         for (index, face) in self.faces.iter().enumerate() {
+            tested.fetch_add(1, Ordering::Relaxed);
+
             if let Some((position, normal, barycentric)) = face.intersects(ray) {
                 return Some(RaycastHit {
                     face_index: index,
                     position,
                     normal,
                     material: {
-                        let uv = face.a.uv * barycentric[2]
-                            + face.b.uv * barycentric[0]
-                            + face.c.uv * barycentric[1];
-
-                        self.material.get(uv[0], uv[1])
+                        let channel = self.material.uv_channel();
+                        let uv = face.uv_at(channel, &barycentric);
+
+                        // UV change toward the neighbouring pixels, if the ray knows them
+                        let offset = |ray: &Ray| {
+                            Some(face.uv_at(channel, &face.plane_barycentric(ray)?) - uv)
+                        };
+                        let footprint = ray
+                            .differentials()
+                            .and_then(|(dx, dy)| {
+                                Some(Footprint {
+                                    dx: offset(&dx)?,
+                                    dy: offset(&dy)?,
+                                })
+                            })
+                            .unwrap_or_default();
+
+                        self.material.get_filtered(uv[0], uv[1], &footprint)
                     },
                 });
             }
@@ -90,6 +127,7 @@ impl Default for Object<Building> {
             normals: vec![],
             uvs: vec![],
             faces: vec![],
+            smoothing_groups: vec![],
             bounding_box: BoundingBox::default(),
             material: Material::default(),
         }
@@ -103,6 +141,7 @@ impl Object<Building> {
 
         // let mut object = Self::default();
         let mut object = Object::<Building>::default();
+        let mut smoothing_group = None;
 
         for (line, line_content) in content.lines().enumerate() {
             if line_content.is_empty() || line_content.chars().next().unwrap_or('#') == '#' {
@@ -122,19 +161,22 @@ impl Object<Building> {
                     println!("Parsing group `{}`", tokens.next().unwrap());
                 }
                 "s" => {
-                    println!(
-                        "Smooth shading would now be {}",
-                        match tokens.next().unwrap() {
-                            "1" | "on" => "on",
-                            "0" | "off" => "off",
-                            v => panic!("Unhandled smooth shading setting `{v}`"),
-                        }
-                    );
+                    smoothing_group = match tokens.next().unwrap() {
+                        "0" | "off" => None,
+                        "on" => Some(1),
+                        v => match v.parse() {
+                            Ok(group) => Some(group),
+                            Err(_) => panic!("Unhandled smooth shading setting `{v}`"),
+                        },
+                    };
                 }
                 "v" => object.push_vertex(line, tokens),
                 "vn" => object.push_normal(line, tokens),
                 "vt" => object.push_uv(line, tokens),
-                "f" => object.push_face(line, tokens),
+                "f" => {
+                    object.push_face(line, tokens);
+                    object.smoothing_groups.push(smoothing_group);
+                }
                 _ => panic!("Unhandled marker {marker}"),
             }
         }
@@ -144,7 +186,13 @@ impl Object<Building> {
 
     fn push_vertex(&mut self, line: usize, tokens: SplitWhitespace) {
         let coords = parse_coords(tokens, Some(line));
-        self.vertices.push(coords[0..=2].into());
+        let vertex = coords[0..=2].into();
+        if self.vertices.is_empty() {
+            self.bounding_box = BoundingBox::around(&vertex);
+        } else {
+            self.bounding_box.stretch_to(&vertex);
+        }
+        self.vertices.push(vertex);
     }
 
     fn push_normal(&mut self, line: usize, tokens: SplitWhitespace) {
@@ -161,11 +209,14 @@ impl Object<Building> {
         let vertices = tokens
             .map(|token| {
                 let indices = parse_indices(token);
-                Vertex {
-                    position: self.vertices[indices[0].unwrap() - 1],
-                    uv: self.uvs[indices[1].unwrap() - 1],
-                    normal: self.normals[indices[2].unwrap() - 1],
-                }
+                let index = |i: usize| indices.get(i).copied().flatten().map(|index| index - 1);
+
+                // Missing normals are generated when building the object
+                Vertex::new(
+                    self.vertices[index(0).unwrap()],
+                    index(2).map(|i| self.normals[i]).unwrap_or_default(),
+                    index(1).map(|i| self.uvs[i]).unwrap_or_default(),
+                )
             })
             .collect::<Vec<_>>();
 
@@ -195,34 +246,58 @@ impl Object<Building> {
     pub fn vertices(&mut self, vertices: impl Iterator<Item = Vector<3, f32>>) -> &mut Self {
         self.vertices = vertices.collect();
 
-        self.bounding_box = BoundingBox::default();
-        self.vertices
-            .iter()
-            .for_each(|v| self.bounding_box.stretch_to(v));
+        // The first vertex sets the box so that it does not have to contain the origin
+        let mut vertices = self.vertices.iter();
+        self.bounding_box = vertices.next().map(BoundingBox::around).unwrap_or_default();
+        vertices.for_each(|v| self.bounding_box.stretch_to(v));
 
         self
     }
 
-    /// Set object normals (mandatory).
+    /// Set object normals, generated from the faces' geometry when building if left empty.
     pub fn normals(&mut self, normals: impl Iterator<Item = Vector<3, f32>>) -> &mut Self {
         self.normals = normals.collect();
         self
     }
 
     /// Lock object's fields and allow for OpenGL conversion.
+    ///
+    /// If any face is in a [smoothing group](Object::smoothing_groups), the faces' vertex normals
+    /// are generated from their geometry following them. Without any normals, they are generated
+    /// flat instead. Authored normals are kept otherwise.
     pub fn build(self) -> Result<Object<Built>, &'static str> {
         if self.vertices.is_empty() {
             Err("Missing vertices")
-        } else if self.normals.is_empty() {
-            Err("Missing normals")
+        } else if self.normals.is_empty() && self.faces.is_empty() {
+            // Normals can only be generated from faces
+            Err("Missing faces")
         } else {
+            let mut faces = self.faces;
+            let smoothed = self.smoothing_groups.iter().any(Option::is_some);
+            if smoothed && self.smoothing_groups.len() == faces.len() {
+                smooth_normals(&mut faces, &self.smoothing_groups);
+            } else if self.normals.is_empty() {
+                let flat = vec![None; faces.len()];
+                smooth_normals(&mut faces, &flat);
+            }
+
+            let normals = if self.normals.is_empty() {
+                faces
+                    .iter()
+                    .flat_map(|face| [face.a.normal, face.b.normal, face.c.normal])
+                    .collect()
+            } else {
+                self.normals
+            };
+
             Ok(Object::<Built> {
                 state: std::marker::PhantomData,
                 name: self.name,
                 vertices: self.vertices,
-                normals: self.normals,
+                normals,
                 uvs: self.uvs,
-                faces: self.faces,
+                faces,
+                smoothing_groups: self.smoothing_groups,
                 bounding_box: self.bounding_box,
                 material: self.material,
             })
@@ -230,24 +305,53 @@ impl Object<Building> {
     }
 }
 
+#[cfg(feature = "opengl")]
 impl Object<Built> {
     /// Convert into an [OpenGLObject] and mark as consumed.
+    ///
+    /// Vertices and normals are bound to the attribute locations 0 and 1 of the returned VAO,
+    /// which is left unbound.
     pub fn to_opengl(self) -> (Object<GLConsumed>, OpenGLObject) {
+        // Generate a Vertex Array Object to record the attributes setup
+        let mut vao = 0;
+        unsafe {
+            gl::GenVertexArrays(1, &mut vao);
+            assert_ne!(vao, 0);
+
+            gl::BindVertexArray(vao);
+        }
+
         let vbos = [&self.vertices, &self.normals]
             .into_iter()
             .enumerate()
-            .map(|(index, array)| (index as u32, array, array[0].len() as i32))
-            .map(|(index, array, len)| {
-                if len == 0 {
+            .map(|(index, array)| {
+                if array.is_empty() {
                     return None;
                 }
 
                 unsafe {
+                    // Generate a Vertex Buffer Object
+                    let mut vbo = 0;
+                    gl::GenBuffers(1, &mut vbo);
+                    assert_ne!(vbo, 0);
+
+                    // Bind it
+                    gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+
+                    // Pass data to it
+                    gl::BufferData(
+                        gl::ARRAY_BUFFER,
+                        (array.len() * size_of_val(&array[0])) as isize,
+                        array.as_ptr().cast(),
+                        gl::STATIC_DRAW,
+                    );
+
+                    // Point the attribute at the bound VBO
                     gl::VertexAttribPointer(
                         // Index
-                        index,
+                        index as u32,
                         // Component count
-                        len,
+                        (size_of::<Vector<3, f32>>() / size_of::<f32>()) as i32,
                         // Component type
                         gl::FLOAT,
                         // Normalized?
@@ -258,31 +362,17 @@ impl Object<Built> {
                         std::ptr::null(),
                     );
 
-                    gl::EnableVertexAttribArray(index);
-
-                    // Generate a Vertex Buffer Object
-                    let mut vbo = 0;
-                    {
-                        gl::GenBuffers(1, &mut vbo);
-                        assert_ne!(vbo, 0);
-
-                        // Bind it
-                        gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
-
-                        // Pass data to it
-                        gl::BufferData(
-                            gl::ARRAY_BUFFER,
-                            (array.len() * size_of_val(&array[0])) as isize,
-                            array.as_ptr().cast(),
-                            gl::STATIC_DRAW,
-                        );
-                    }
+                    gl::EnableVertexAttribArray(index as u32);
 
                     Some(vbo)
                 }
             })
             .collect::<Vec<_>>();
 
+        unsafe {
+            gl::BindVertexArray(0);
+        }
+
         (
             Object::<GLConsumed> {
                 state: std::marker::PhantomData,
@@ -291,10 +381,12 @@ impl Object<Built> {
                 normals: self.normals,
                 uvs: self.uvs,
                 faces: self.faces,
+                smoothing_groups: self.smoothing_groups,
                 bounding_box: self.bounding_box,
                 material: self.material,
             },
             OpenGLObject {
+                vao,
                 vertices_vbo: vbos[0].unwrap(),
                 normals_vbo: vbos[1],
             },
@@ -302,6 +394,34 @@ impl Object<Built> {
     }
 }
 
+/// Replace the vertex normals of flat shaded faces by their surface normal, and those of smooth
+/// shaded faces by the area-weighted average of the surface normals of the faces sharing that
+/// vertex's position within the same smoothing group.
+fn smooth_normals(faces: &mut [Triangle], smoothing_groups: &[Option<u32>]) {
+    let key = |position: &Vector<3, f32>| [position[0], position[1], position[2]].map(f32::to_bits);
+
+    let mut sums = HashMap::<(u32, [u32; 3]), Vector<3, f32>>::new();
+    for (face, group) in faces.iter().zip(smoothing_groups) {
+        if let Some(group) = *group {
+            // The surface normal's length already weights it by the face's area
+            for vertex in [&face.a, &face.b, &face.c] {
+                *sums.entry((group, key(&vertex.position))).or_default() += face.surface_normal();
+            }
+        }
+    }
+
+    for (face, group) in faces.iter_mut().zip(smoothing_groups) {
+        let flat = face.surface_normal().normalize();
+
+        for vertex in [&mut face.a, &mut face.b, &mut face.c] {
+            vertex.normal = match group {
+                Some(group) => sums[&(*group, key(&vertex.position))].normalize(),
+                None => flat,
+            };
+        }
+    }
+}
+
 // TODO: Make N-dimensional..?
 #[derive(Debug, Default)]
 /// Spatial limits of the object's vertices relative to its origin.
@@ -378,17 +498,24 @@ impl BoundingBox {
         true
     }
 
-    fn stretch_to(&mut self, pos: &Vector<3, f32>) {
-        if pos[0] < self.x.start {
-            self.x.start = pos[0];
-        } else if pos[0] > self.x.end {
-            self.x.end = pos[0];
+    /// Empty [BoundingBox] located at `pos`.
+    fn around(pos: &Vector<3, f32>) -> Self {
+        Self {
+            x: pos[0]..pos[0],
+            y: pos[1]..pos[1],
+            z: pos[2]..pos[2],
         }
+    }
 
-        if pos[1] < self.y.start {
-            self.y.start = pos[1];
-        } else if pos[1] > self.y.end {
-            self.y.end = pos[1];
+    fn stretch_to(&mut self, pos: &Vector<3, f32>) {
+        let axes = [&mut self.x, &mut self.y, &mut self.z];
+        for (range, value) in axes.into_iter().zip([pos[0], pos[1], pos[2]]) {
+            if value < range.start {
+                range.start = value;
+            }
+            if value > range.end {
+                range.end = value;
+            }
         }
     }
 }
@@ -419,3 +546,367 @@ fn parse_indices(string: &str) -> Vec<Option<usize>> {
         .map(|index| index.parse::<usize>().ok())
         .collect()
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Mock OpenGL functions recording the calls made through the `gl` crate.
+    #[cfg(feature = "opengl")]
+    mod mock {
+        use gl::types::*;
+        use std::{
+            ffi::c_void,
+            sync::{
+                atomic::{AtomicU32, Ordering},
+                Mutex,
+            },
+        };
+
+        pub static CALLS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+        static NEXT_ID: AtomicU32 = AtomicU32::new(1);
+
+        fn record(call: String) {
+            CALLS.lock().unwrap().push(call);
+        }
+
+        extern "system" fn gen_vertex_arrays(n: GLsizei, arrays: *mut GLuint) {
+            let id = NEXT_ID.fetch_add(n as u32, Ordering::SeqCst);
+            unsafe { *arrays = id };
+            record(format!("GenVertexArrays -> {id}"));
+        }
+
+        extern "system" fn bind_vertex_array(array: GLuint) {
+            record(format!("BindVertexArray({array})"));
+        }
+
+        extern "system" fn gen_buffers(n: GLsizei, buffers: *mut GLuint) {
+            let id = NEXT_ID.fetch_add(n as u32, Ordering::SeqCst);
+            unsafe { *buffers = id };
+            record(format!("GenBuffers -> {id}"));
+        }
+
+        extern "system" fn bind_buffer(_target: GLenum, buffer: GLuint) {
+            record(format!("BindBuffer({buffer})"));
+        }
+
+        extern "system" fn buffer_data(
+            _target: GLenum,
+            size: GLsizeiptr,
+            _data: *const c_void,
+            _usage: GLenum,
+        ) {
+            record(format!("BufferData({size})"));
+        }
+
+        extern "system" fn vertex_attrib_pointer(
+            index: GLuint,
+            size: GLint,
+            _type: GLenum,
+            _normalized: GLboolean,
+            _stride: GLsizei,
+            _pointer: *const c_void,
+        ) {
+            record(format!("VertexAttribPointer({index}, {size})"));
+        }
+
+        extern "system" fn enable_vertex_attrib_array(index: GLuint) {
+            record(format!("EnableVertexAttribArray({index})"));
+        }
+
+        pub fn load() {
+            gl::load_with(|name| match name {
+                "glGenVertexArrays" => gen_vertex_arrays as *const c_void,
+                "glBindVertexArray" => bind_vertex_array as *const c_void,
+                "glGenBuffers" => gen_buffers as *const c_void,
+                "glBindBuffer" => bind_buffer as *const c_void,
+                "glBufferData" => buffer_data as *const c_void,
+                "glVertexAttribPointer" => vertex_attrib_pointer as *const c_void,
+                "glEnableVertexAttribArray" => enable_vertex_attrib_array as *const c_void,
+                _ => std::ptr::null(),
+            });
+        }
+    }
+
+    #[cfg(feature = "opengl")]
+    #[test]
+    fn opengl_call_order() {
+        mock::load();
+
+        let mut object = Object::default();
+        object
+            .vertices([Vector::new(0., 0., 0.), Vector::new(1., 0., 0.)].into_iter())
+            .normals([Vector::new(0., 0., 1.), Vector::new(0., 0., 1.)].into_iter());
+
+        let (_, gl_object) = object.build().unwrap().to_opengl();
+
+        assert_ne!(gl_object.vao, 0);
+        assert_ne!(gl_object.vertices_vbo, 0);
+        assert!(matches!(gl_object.normals_vbo, Some(vbo) if vbo != 0));
+
+        let (vao, vertices, normals) = (
+            gl_object.vao,
+            gl_object.vertices_vbo,
+            gl_object.normals_vbo.unwrap(),
+        );
+        assert_eq!(
+            *mock::CALLS.lock().unwrap(),
+            [
+                format!("GenVertexArrays -> {vao}"),
+                format!("BindVertexArray({vao})"),
+                format!("GenBuffers -> {vertices}"),
+                format!("BindBuffer({vertices})"),
+                "BufferData(24)".to_owned(),
+                "VertexAttribPointer(0, 3)".to_owned(),
+                "EnableVertexAttribArray(0)".to_owned(),
+                format!("GenBuffers -> {normals}"),
+                format!("BindBuffer({normals})"),
+                "BufferData(24)".to_owned(),
+                "VertexAttribPointer(1, 3)".to_owned(),
+                "EnableVertexAttribArray(1)".to_owned(),
+                "BindVertexArray(0)".to_owned(),
+            ]
+        );
+    }
+
+    #[test]
+    fn bounding_box() {
+        let mut object = Object::default();
+        object.vertices(
+            [(-1., 0., -2.), (1., 1., 3.), (0., -1., 1.)]
+                .into_iter()
+                .map(|(x, y, z)| Vector::new(x, y, z)),
+        );
+
+        let BoundingBox { x, y, z } = &object.bounding_box;
+        assert_eq!((x, y, z), (&(-1.0..1.0), &(-1.0..1.0), &(-2.0..3.0)));
+    }
+
+    #[test]
+    fn off_origin_bounding_box() {
+        let vertices =
+            [(2., 3., -4.), (5., 3.5, -6.), (3., 4., -5.)].map(|(x, y, z)| Vector::new(x, y, z));
+
+        let mut object = Object::default();
+        object.vertices(vertices.into_iter());
+
+        let BoundingBox { x, y, z } = &object.bounding_box;
+        assert_eq!((x, y, z), (&(2.0..5.0), &(3.0..4.0), &(-6.0..-4.0)));
+
+        // Same box when loading the vertices from a .obj
+        let obj = vertices
+            .iter()
+            .map(|v| format!("v {} {} {}\n", v[0], v[1], v[2]))
+            .collect::<String>();
+        let path = std::env::temp_dir().join("eray_off_origin_bounding_box.obj");
+        std::fs::write(&path, obj).unwrap();
+        let loaded = Object::load_obj(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let BoundingBox { x, y, z } = &loaded.bounding_box;
+        assert_eq!((x, y, z), (&(2.0..5.0), &(3.0..4.0), &(-6.0..-4.0)));
+    }
+
+    #[test]
+    fn obj_bounding_box() {
+        let cube = Object::load_obj(Path::new("./objects/cube.obj")).unwrap();
+
+        let BoundingBox { x, y, z } = &cube.bounding_box;
+        assert_eq!((x, y, z), (&(-1.0..1.0), &(-1.0..1.0), &(-1.0..1.0)));
+    }
+
+    #[test]
+    fn build_without_faces() {
+        let mut object = Object::default();
+        object.vertices(std::iter::once(Vector::new(0., 0., 0.)));
+        assert_eq!(Some("Missing faces"), object.build().err());
+
+        // Nothing to generate when the normals are given
+        let mut object = Object::default();
+        object
+            .vertices(std::iter::once(Vector::new(0., 0., 0.)))
+            .normals(std::iter::once(Vector::new(0., 0., 1.)));
+        assert!(object.build().is_ok());
+    }
+
+    #[test]
+    fn uv_channels() {
+        use crate::{
+            color::Color, graph, image::Image, material::StandardMaterialOutput,
+            shader::graph::SocketValue,
+        };
+
+        // Red on the left half of the texture, green on the right one
+        let mut texture = Image::new(2, 1, Color::new(1., 0., 0.));
+        texture.set(1, 0, Color::new(0., 1., 0.));
+
+        let mut cube = Object::load_obj(Path::new("./objects/cube.obj")).unwrap();
+        cube.material = Material::builder(
+            graph! {
+                inputs,
+                nodes,
+                outputs:
+                    "color": (None, SocketValue::IColor(Some(texture))),
+            }
+            .validate()
+            .unwrap(),
+        )
+        .with_output(StandardMaterialOutput::Color, "color")
+        .build()
+        .unwrap();
+
+        // Lightmap-like second channel pointing every vertex at the right half
+        for face in cube.faces.iter_mut() {
+            for vertex in [&mut face.a, &mut face.b, &mut face.c] {
+                vertex.uvs[1] = Vector::from([0.9, 0.5]);
+            }
+        }
+
+        let mut cube = cube.build().unwrap();
+        let ray = Ray::new(Vector::new(0.5, 0.5, 5.), Vector::new(0., 0., -1.));
+
+        // Front face UVs all lie on the left third of the texture
+        let hit = cube.intersects(&ray).unwrap();
+        assert_eq!(0, cube.material.uv_channel());
+        assert_eq!(Some(Color::new(1., 0., 0.)), hit.material.color);
+
+        cube.material.set_uv_channel(1);
+        let hit = cube.intersects(&ray).unwrap();
+        assert_eq!(Some(Color::new(0., 1., 0.)), hit.material.color);
+    }
+
+    #[test]
+    fn authored_normals() {
+        let triangle = Object::load_obj(Path::new("./objects/authored_normals.obj"))
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let authored = Vector::new(0., 0.6, 0.8);
+        let face = &triangle.faces[0];
+        for vertex in [&face.a, &face.b, &face.c] {
+            assert_eq!(authored, vertex.normal);
+        }
+        assert_eq!(vec![authored], triangle.normals);
+    }
+
+    #[test]
+    fn smoothing_groups() {
+        let cube = Object::load_obj(Path::new("./objects/mixed_smoothing_cube.obj"))
+            .unwrap()
+            .build()
+            .unwrap();
+
+        // Top and front faces come first in the file, in smoothing group 1
+        assert_eq!(cube.smoothing_groups[..4], [Some(1); 4]);
+        assert!(cube.smoothing_groups[4..].iter().all(Option::is_none));
+
+        let edge = Vector::new(1., 1., 1.);
+        fn corners(face: &Triangle) -> [&Vertex; 3] {
+            [&face.a, &face.b, &face.c]
+        }
+
+        for face in cube.faces[..4].iter() {
+            let vertex = corners(face)
+                .into_iter()
+                .find(|vertex| vertex.position == edge)
+                .unwrap();
+
+            assert!(
+                vertex
+                    .normal
+                    .approx_eq(&Vector::new(0., 1., 1.).normalize(), 1e-5),
+                "Expected a smooth top-front edge, got {:?}",
+                vertex.normal
+            );
+        }
+
+        for face in cube.faces[4..].iter() {
+            let flat = face.surface_normal().normalize();
+
+            assert!(
+                corners(face).iter().all(|vertex| vertex.normal == flat),
+                "Expected flat normals on a face outside of any smoothing group"
+            );
+        }
+
+        // Right face, sharing the top-front edge's corner without being smoothed with it
+        assert!(cube.faces[4..].iter().any(|face| {
+            corners(face)
+                .iter()
+                .any(|vertex| vertex.position == edge && vertex.normal == Vector::new(1., 0., 0.))
+        }));
+    }
+
+    #[test]
+    fn generated_normals() {
+        use crate::{engine::Engine, prelude::*};
+
+        let cube = Object::load_obj(Path::new("./objects/cube_no_normals.obj"))
+            .unwrap()
+            .build()
+            .unwrap();
+
+        assert!(!cube.normals.is_empty());
+        for face in cube.faces.iter() {
+            let flat = face.surface_normal().normalize();
+
+            assert!(
+                [&face.a, &face.b, &face.c]
+                    .iter()
+                    .all(|vertex| vertex.normal == flat),
+                "Expected flat normals, got {flat:?}"
+            );
+            assert!(
+                flat.dot_product(&face.centroid()) > 0.,
+                "Expected an outward normal, got {flat:?}"
+            );
+        }
+
+        let mut engine = Engine::new((8, 8), 0, 0).unwrap();
+        engine
+            .scene()
+            .set_camera(Camera {
+                center: Vector::new(0., 0., 5.),
+                width: 8,
+                ..Default::default()
+            })
+            .add_light(Light {
+                transform: Transform::default().apply_translation(Vector::new(0., 0., 3.)),
+                variant: LightVariant::Point,
+                color: Color::new(1., 1., 1.),
+                brightness: 2.,
+                shadow_softness: 0.,
+                shadow_bias: DEFAULT_SHADOW_BIAS,
+            })
+            .add_object(cube);
+
+        // Front face, lit head-on
+        let pixel = engine.render_to_image().mod_get(4, 4);
+        assert!(pixel.r > 0.2, "Expected a lit front face, got {pixel:?}");
+    }
+
+    #[cfg(not(feature = "opengl"))]
+    #[test]
+    fn without_opengl() {
+        use crate::{engine::Engine, prelude::*};
+
+        // Objects go straight from building to rendering without any GL state involved
+        let cube = Object::load_obj(Path::new("./objects/cube.obj"))
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let mut engine = Engine::new((4, 4), 0, 0).unwrap();
+        engine
+            .scene()
+            .set_camera(Camera {
+                center: Vector::new(0., 0., 5.),
+                width: 4,
+                ..Default::default()
+            })
+            .add_object(cube);
+
+        assert!(engine.render_to_image().mod_get(2, 2) != Color::new(0.1, 0.1, 0.2));
+    }
+}
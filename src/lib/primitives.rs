@@ -1,7 +1,12 @@
 //! Basic primitives necessary for rendering
 
+use rand::Rng;
+
 use crate::{raycasting::Ray, vector::Vector};
 
+/// Number of UV sets held by each [Vertex], e.g. for a base color texture and a lightmap.
+pub const UV_CHANNELS: usize = 2;
+
 #[derive(Debug, Default)]
 /// A mesh vertex.
 pub struct Vertex<const DIM: usize = 3, TYPE: Copy = f32> {
@@ -9,8 +14,31 @@ pub struct Vertex<const DIM: usize = 3, TYPE: Copy = f32> {
     pub position: Vector<DIM, TYPE>,
     /// XYZ normal.
     pub normal: Vector<DIM, TYPE>,
-    /// UV(W) texture coordinates.
-    pub uv: Vector<2, TYPE>,
+    /// UV texture coordinates of each channel, the first one being filled by the OBJ loader.
+    pub uvs: [Vector<2, TYPE>; UV_CHANNELS],
+}
+
+impl<const DIM: usize, TYPE: Copy + Default> Vertex<DIM, TYPE> {
+    /// Create a vertex with a single set of UV coordinates in channel 0, the others being zeroed.
+    pub fn new(
+        position: Vector<DIM, TYPE>,
+        normal: Vector<DIM, TYPE>,
+        uv: Vector<2, TYPE>,
+    ) -> Self {
+        let mut uvs = [Vector::default(); UV_CHANNELS];
+        uvs[0] = uv;
+
+        Self {
+            position,
+            normal,
+            uvs,
+        }
+    }
+
+    /// UV coordinates of channel 0.
+    pub fn uv(&self) -> Vector<2, TYPE> {
+        self.uvs[0]
+    }
 }
 
 #[derive(Debug, Default)]
@@ -38,6 +66,8 @@ impl Triangle {
     /// Check for intersection with the provided [Ray] with backface culling.
     ///
     /// Returns a world-space position, world-space normalized surface normal vector and a barycentric position.
+    ///
+    /// The barycentric position holds the weights of `b`, `c` and `a` in that order.
     pub fn intersects(&self, ray: &Ray) -> Option<(Vector, Vector, Vector)> {
         let [a, b, c] = [self.a.position, self.b.position, self.c.position];
 
@@ -64,13 +94,76 @@ impl Triangle {
         (det >= 1e-6 && t >= 0. && u >= 0. && v >= 0. && (u + v) <= 1.0).then(|| {
             (
                 *ray.start() + *ray.dir() * t,
-                (self.a.normal * u + self.b.normal * v + self.c.normal * t).normalize(),
-                // TODO: This is invalid, figure out how the fuck barycentric coordinates work
+                (self.a.normal * (1. - u - v) + self.b.normal * u + self.c.normal * v).normalize(),
                 Vector::new(u, v, 1. - u - v),
             )
         })
     }
 
+    /// Barycentric coordinates, as returned by [intersects](Self::intersects), of the point where
+    /// the ray crosses the triangle's plane, even outside of the triangle itself.
+    ///
+    /// Returns [None] if the ray is parallel to the plane.
+    pub fn plane_barycentric(&self, ray: &Ray) -> Option<Vector> {
+        let [a, b, c] = [self.a.position, self.b.position, self.c.position];
+
+        let e1 = b - a;
+        let e2 = c - a;
+        let n = e1.cross_product(&e2);
+
+        let det = -ray.dir().dot_product(&n);
+        if det.abs() < 1e-6 {
+            return None;
+        }
+
+        let dao = (*ray.start() - a).cross_product(ray.dir());
+        let u = e2.dot_product(&dao) / det;
+        let v = -e1.dot_product(&dao) / det;
+
+        Some(Vector::new(u, v, 1. - u - v))
+    }
+
+    /// Interpolate the UV coordinates of the given `channel` at a barycentric position as returned
+    /// by [intersects](Self::intersects).
+    ///
+    /// # Panics
+    /// Panics if `channel` is not below [UV_CHANNELS].
+    pub fn uv_at(&self, channel: usize, barycentric: &Vector) -> Vector<2, f32> {
+        self.a.uvs[channel] * barycentric[2]
+            + self.b.uvs[channel] * barycentric[0]
+            + self.c.uvs[channel] * barycentric[1]
+    }
+
+    /// Unnormalized surface normal, i.e. (b - a) x (c - a) whose length is twice the
+    /// triangle's [area](Self::area).
+    pub fn surface_normal(&self) -> Vector {
+        self.normal
+    }
+
+    /// Surface area of the triangle, i.e. half the magnitude of its edges' cross product.
+    pub fn area(&self) -> f32 {
+        self.normal.len() / 2.
+    }
+
+    /// Average of the triangle's vertex positions.
+    pub fn centroid(&self) -> Vector {
+        (self.a.position + self.b.position + self.c.position) / 3.
+    }
+
+    /// Uniformly sample a point on the triangle's surface, returning it along with the vertex
+    /// normals interpolated at that point.
+    pub fn sample_point(&self, rng: &mut impl Rng) -> (Vector, Vector) {
+        // Folding the unit square onto the triangle with a square root keeps the density uniform
+        let s = rng.gen::<f32>().sqrt();
+        let t = rng.gen::<f32>();
+        let [wa, wb, wc] = [1. - s, s * (1. - t), s * t];
+
+        (
+            self.a.position * wa + self.b.position * wb + self.c.position * wc,
+            (self.a.normal * wa + self.b.normal * wb + self.c.normal * wc).normalize(),
+        )
+    }
+
     /// Returns the projected coordinates of the point on the triangle.
     pub fn project(&self, point: Vector) -> Vector {
         let v = point - self.a.position;
@@ -88,21 +181,21 @@ mod test {
     #[test]
     fn projection() {
         let triangle = Triangle::new(
-            Vertex {
-                position: Vector::new(-0.5, 0., -0.5),
-                normal: Vector::new(0., 1., 0.),
-                uv: Vector::from([0., 0.]),
-            },
-            Vertex {
-                position: Vector::new(0., 0., 0.5),
-                normal: Vector::new(0., 1., 0.),
-                uv: Vector::from([0.5, 1.]),
-            },
-            Vertex {
-                position: Vector::new(0.5, 0., -0.5),
-                normal: Vector::new(0., 1., 0.),
-                uv: Vector::from([1., 0.]),
-            },
+            Vertex::new(
+                Vector::new(-0.5, 0., -0.5),
+                Vector::new(0., 1., 0.),
+                Vector::from([0., 0.]),
+            ),
+            Vertex::new(
+                Vector::new(0., 0., 0.5),
+                Vector::new(0., 1., 0.),
+                Vector::from([0.5, 1.]),
+            ),
+            Vertex::new(
+                Vector::new(0.5, 0., -0.5),
+                Vector::new(0., 1., 0.),
+                Vector::from([1., 0.]),
+            ),
         );
 
         let point = Vector::new(0.2, 0.1, 0.);
@@ -110,4 +203,101 @@ mod test {
 
         assert_eq!(Vector::new(0.2, 0., 0.), proj);
     }
+
+    fn unit_right_triangle() -> Triangle {
+        let vertex = |position| Vertex {
+            position,
+            normal: Vector::new(0., 0., 1.),
+            uvs: Default::default(),
+        };
+
+        Triangle::new(
+            vertex(Vector::new(0., 0., 0.)),
+            vertex(Vector::new(1., 0., 0.)),
+            vertex(Vector::new(0., 1., 0.)),
+        )
+    }
+
+    #[test]
+    fn area_and_centroid() {
+        let triangle = unit_right_triangle();
+
+        assert_eq!(0.5, triangle.area());
+        assert!(triangle
+            .centroid()
+            .approx_eq(&Vector::new(1. / 3., 1. / 3., 0.), 1e-6));
+    }
+
+    #[test]
+    fn uniform_sampling() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let triangle = unit_right_triangle();
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let count = 10_000;
+        let mut sum = Vector::new(0., 0., 0.);
+        for _ in 0..count {
+            let (point, normal) = triangle.sample_point(&mut rng);
+
+            assert!(
+                point[0] >= 0. && point[1] >= 0. && point[0] + point[1] <= 1. + 1e-6,
+                "Sampled point {point:?} is outside of the triangle"
+            );
+            assert_eq!(0., point[2]);
+            assert!(normal.approx_eq(&Vector::new(0., 0., 1.), 1e-6));
+
+            sum += point;
+        }
+
+        let mean = sum / count as f32;
+        assert!(
+            mean.approx_eq(&triangle.centroid(), 0.01),
+            "Expected samples to average near the centroid, got {mean:?}"
+        );
+    }
+
+    #[test]
+    fn interpolated_normal() {
+        let vertex = |position, normal| Vertex {
+            position,
+            normal,
+            uvs: Default::default(),
+        };
+        let [a, b, c] = [
+            Vector::new(-0.5, 0., -0.5),
+            Vector::new(0., 0., 0.5),
+            Vector::new(0.5, 0., -0.5),
+        ];
+
+        let up = Vector::new(0., 1., 0.);
+        let flat = Triangle::new(vertex(a, up), vertex(b, up), vertex(c, up));
+
+        for (x, z) in [
+            (0., 0.),
+            (-0.4, -0.45),
+            (0.4, -0.45),
+            (0., 0.4),
+            (0.1, -0.2),
+        ] {
+            let ray = Ray::new(Vector::new(x, 3., z), Vector::new(0., -1., 0.));
+            let (_, normal, _) = flat.intersects(&ray).expect("Ray should hit the triangle");
+            assert_eq!(up, normal, "Wrong normal at ({x}, {z})");
+        }
+
+        let smooth = Triangle::new(
+            vertex(a, Vector::new(1., 0., 0.)),
+            vertex(b, Vector::new(0., 1., 0.)),
+            vertex(c, Vector::new(0., 0., 1.)),
+        );
+        let centroid = (a + b + c) / 3.;
+        let ray = Ray::new(centroid + up * 3., Vector::new(0., -1., 0.));
+        let (_, normal, _) = smooth
+            .intersects(&ray)
+            .expect("Ray should hit the triangle");
+        assert!(
+            normal.approx_eq(&Vector::new(1., 1., 1.).normalize(), 1e-5),
+            "Expected an even blend of the vertex normals, got {normal:?}"
+        );
+    }
 }
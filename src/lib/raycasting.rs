@@ -2,13 +2,15 @@
 
 use super::vector::Vector;
 
-use crate::material::MaterialOutputBundle;
+use crate::{material::MaterialOutputBundle, matrix::Mat4};
 
 #[derive(Clone, Debug, Default)]
 /// A ray defined by its start position and direction.
 pub struct Ray {
     start: Vector<3, f32>,
     dir: Vector<3, f32>,
+    /// Start and direction of the rays offset by one pixel along the screen's x and y axes.
+    differentials: Option<[(Vector<3, f32>, Vector<3, f32>); 2]>,
 }
 
 impl Ray {
@@ -17,14 +19,45 @@ impl Ray {
         Self {
             start,
             dir: dir.normalize(),
+            differentials: None,
         }
     }
 
+    /// Attach the rays offset by one pixel along the screen's x (`dx`) and y (`dy`) axes, used to
+    /// estimate the texture [Footprint](crate::sampler::Footprint) at hits.
+    pub fn with_differentials(mut self, dx: &Ray, dy: &Ray) -> Self {
+        self.differentials = Some([(dx.start, dx.dir), (dy.start, dy.dir)]);
+        self
+    }
+
+    /// Get the rays offset by one pixel along the screen's x and y axes, if any.
+    pub fn differentials(&self) -> Option<(Ray, Ray)> {
+        self.differentials
+            .map(|[dx, dy]| (Ray::new(dx.0, dx.1), Ray::new(dy.0, dy.1)))
+    }
+
     /// Get position at `t` along ray.
     pub fn calc(&self, t: f32) -> Vector<3, f32> {
         self.start + self.dir * t
     }
 
+    /// Transform the [Ray] by `m`, e.g. with an inverse object transform to go from world space to
+    /// object space.
+    ///
+    /// The direction is renormalized, so distances along the ray are only preserved by rigid
+    /// transformations.
+    pub fn transformed(&self, m: &Mat4) -> Self {
+        let transform = |start, dir| (m.transform_point(start), m.transform_vector(dir));
+        let (start, dir) = transform(self.start, self.dir);
+
+        Self {
+            differentials: self
+                .differentials
+                .map(|offsets| offsets.map(|(start, dir)| transform(start, dir))),
+            ..Self::new(start, dir)
+        }
+    }
+
     #[inline]
     /// Get starting position
     pub fn start(&self) -> &Vector<3, f32> {
@@ -38,6 +71,24 @@ impl Ray {
     }
 }
 
+#[derive(Clone, Debug)]
+/// A camera ray along with the rays going through the neighbouring pixels, e.g. to filter
+/// textures over the area a pixel covers.
+pub struct RayDifferential {
+    /// Ray through the sampled point.
+    pub center: Ray,
+    /// Ray offset by one pixel along the screen's x axis.
+    pub dx: Ray,
+    /// Ray offset by one pixel along the screen's y axis.
+    pub dy: Ray,
+}
+
+impl From<RayDifferential> for Ray {
+    fn from(value: RayDifferential) -> Self {
+        value.center.with_differentials(&value.dx, &value.dy)
+    }
+}
+
 #[derive(Debug)]
 /// Information about the hit and hit object.
 pub struct RaycastHit {
@@ -52,3 +103,21 @@ pub struct RaycastHit {
     /// Material properties at the hit point
     pub material: MaterialOutputBundle,
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::matrix::Transform;
+
+    #[test]
+    fn transformed() {
+        let transform = Transform::default()
+            .apply_translation(Vector::new(1., 0., 0.))
+            .apply_rotation(Vector::new(0., 0., 1.), std::f32::consts::FRAC_PI_2);
+        let ray = Ray::new(Vector::new(0., 0., 0.), Vector::new(1., 0., 0.))
+            .transformed(transform.matrix());
+
+        assert!(ray.start().approx_eq(&Vector::new(1., 0., 0.), 1e-6));
+        assert!(ray.dir().approx_eq(&Vector::new(0., 1., 0.), 1e-6));
+    }
+}
@@ -0,0 +1,305 @@
+//! Texture sampling with optional filtering over a pixel's UV footprint.
+
+use std::ops::{Add, Mul};
+
+use crate::{image::Image, vector::Vector};
+
+/// Default maximum number of taps taken by the footprint-based filters.
+pub const DEFAULT_MAX_SAMPLES: usize = 16;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+/// Screen-space derivatives of the UV coordinates at a sampled point, i.e. how much the UV
+/// coordinates change when moving by one pixel in x and in y.
+pub struct Footprint {
+    /// UV change per pixel along the screen's x axis.
+    pub dx: Vector<2, f32>,
+    /// UV change per pixel along the screen's y axis.
+    pub dy: Vector<2, f32>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+/// Filtering method used by a [Sampler].
+pub enum Filter {
+    /// Closest texel, ignores the [Footprint].
+    #[default]
+    Nearest,
+    /// Bilinear interpolation of the 4 closest texels, ignores the [Footprint].
+    Bilinear,
+    /// Box filter over a square covering the [Footprint]'s major axis, similar to what a single
+    /// mip level lookup would give. Blurs surfaces seen at grazing angles.
+    Isotropic {
+        /// Maximum number of taps per axis.
+        max_samples: usize,
+    },
+    /// Bilinear lookups in the two mip levels closest to the [Footprint]'s major axis, blended
+    /// linearly. Blurs surfaces seen at grazing angles.
+    ///
+    /// Mip levels are not stored, each of their texels averaging the image's ones on the fly.
+    Trilinear,
+    /// Bilinear taps spread along the [Footprint]'s major axis.
+    Anisotropic {
+        /// Maximum number of taps.
+        max_samples: usize,
+    },
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+/// Samples [Image]s at normalized UV coordinates, tiling them in both directions.
+pub struct Sampler {
+    /// Filtering method.
+    pub filter: Filter,
+}
+
+impl Sampler {
+    /// Create a [Sampler] using the given [Filter].
+    pub fn new(filter: Filter) -> Self {
+        Self { filter }
+    }
+
+    /// Sample `image` at the `uv` coordinates, filtering over the given [Footprint] if the
+    /// [Filter] supports it.
+    pub fn sample<T>(&self, image: &Image<T>, uv: Vector<2, f32>, footprint: &Footprint) -> T
+    where
+        T: Copy + Add<Output = T> + Mul<f32, Output = T>,
+    {
+        let texels = Vector::from([image.width as f32, image.height as f32]);
+        let to_texels = |v: Vector<2, f32>| Vector::from([v[0] * texels[0], v[1] * texels[1]]);
+
+        let (dx, dy) = (to_texels(footprint.dx), to_texels(footprint.dy));
+        // Major axis in UV space, minor axis in texel space.
+        let (major, minor) = if dx.len_sq() >= dy.len_sq() {
+            (footprint.dx, dy)
+        } else {
+            (footprint.dy, dx)
+        };
+        let major_len = to_texels(major).len();
+
+        match self.filter {
+            Filter::Nearest => nearest(image, uv),
+            Filter::Bilinear => bilinear(image, uv),
+            Filter::Isotropic { max_samples } => {
+                let count = (major_len.ceil() as usize).clamp(1, max_samples.max(1));
+                let side = major.len();
+
+                average((0..count * count).map(|index| {
+                    let offset = Vector::from([
+                        tap_offset(index % count, count),
+                        tap_offset(index / count, count),
+                    ]);
+                    bilinear(image, uv + offset * side)
+                }))
+            }
+            Filter::Trilinear => {
+                let max_level = image.width.max(image.height).max(1).ilog2() as f32;
+                let level = major_len.max(1.).log2().min(max_level);
+                let (low, t) = (level.floor(), level.fract());
+
+                let sample = mip_bilinear(image, low as u32, uv);
+                if t > 0. {
+                    sample * (1. - t) + mip_bilinear(image, low as u32 + 1, uv) * t
+                } else {
+                    sample
+                }
+            }
+            Filter::Anisotropic { max_samples } => {
+                let ratio = major_len / minor.len().max(1.);
+                let count = (ratio.ceil() as usize).clamp(1, max_samples.max(1));
+
+                average(
+                    (0..count).map(|index| bilinear(image, uv + major * tap_offset(index, count))),
+                )
+            }
+        }
+    }
+}
+
+/// Evenly spread offset in the -0.5..0.5 range of the `index`-th of `count` taps.
+fn tap_offset(index: usize, count: usize) -> f32 {
+    (index as f32 + 0.5) / count as f32 - 0.5
+}
+
+fn average<T: Copy + Add<Output = T> + Mul<f32, Output = T>>(
+    mut samples: impl ExactSizeIterator<Item = T>,
+) -> T {
+    let count = samples.len();
+    let first = samples.next().expect("At least one sample is needed");
+    samples.fold(first, |acc, cur| acc + cur) * (1. / count as f32)
+}
+
+fn texel<T: Copy>(image: &Image<T>, x: i64, y: i64) -> T {
+    let x = x.rem_euclid(image.width as i64) as u32;
+    let y = y.rem_euclid(image.height as i64) as u32;
+    image.pixels[(y * image.width + x) as usize]
+}
+
+fn nearest<T: Copy>(image: &Image<T>, uv: Vector<2, f32>) -> T {
+    texel(
+        image,
+        (uv[0] * image.width as f32).floor() as i64,
+        (uv[1] * image.height as f32).floor() as i64,
+    )
+}
+
+fn bilinear<T: Copy + Add<Output = T> + Mul<f32, Output = T>>(
+    image: &Image<T>,
+    uv: Vector<2, f32>,
+) -> T {
+    interpolate((image.width, image.height), uv, |x, y| texel(image, x, y))
+}
+
+/// Bilinear lookup in the `level`-th mip level of `image`, whose texels each average a square
+/// of `2^level` by `2^level` of the image's.
+fn mip_bilinear<T: Copy + Add<Output = T> + Mul<f32, Output = T>>(
+    image: &Image<T>,
+    level: u32,
+    uv: Vector<2, f32>,
+) -> T {
+    let scale = 1u32 << level;
+    let (width, height) = (
+        (image.width >> level).max(1),
+        (image.height >> level).max(1),
+    );
+
+    interpolate((width, height), uv, |x, y| {
+        let corner = |coord: i64, size: u32| coord.rem_euclid(size as i64) * scale as i64;
+        let (x, y) = (corner(x, width), corner(y, height));
+
+        average((0..scale * scale).map(|index| {
+            texel(
+                image,
+                x + (index % scale) as i64,
+                y + (index / scale) as i64,
+            )
+        }))
+    })
+}
+
+/// Bilinear interpolation of the texels returned by `texel` for an image of the given size.
+fn interpolate<T: Copy + Add<Output = T> + Mul<f32, Output = T>>(
+    (width, height): (u32, u32),
+    uv: Vector<2, f32>,
+    texel: impl Fn(i64, i64) -> T,
+) -> T {
+    // Texel centers lie at half-integer coordinates.
+    let x = uv[0] * width as f32 - 0.5;
+    let y = uv[1] * height as f32 - 0.5;
+
+    let (x0, y0) = (x.floor(), y.floor());
+    let (tx, ty) = (x - x0, y - y0);
+    let (x0, y0) = (x0 as i64, y0 as i64);
+
+    let lerp = |a: T, b: T, t: f32| a * (1. - t) + b * t;
+
+    lerp(
+        lerp(texel(x0, y0), texel(x0 + 1, y0), tx),
+        lerp(texel(x0, y0 + 1), texel(x0 + 1, y0 + 1), tx),
+        ty,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Horizontal stripes alternating every texel along v.
+    fn stripes() -> Image<f32> {
+        let (width, height) = (64, 64);
+        Image {
+            width,
+            height,
+            pixels: (0..width * height)
+                .map(|index| ((index / width) % 2) as f32)
+                .collect(),
+        }
+    }
+
+    fn variance(values: &[f32]) -> f32 {
+        let mean = values.iter().sum::<f32>() / values.len() as f32;
+        values.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / values.len() as f32
+    }
+
+    #[test]
+    fn bilinear_texel_centers() {
+        let image = Image {
+            width: 2,
+            height: 1,
+            pixels: vec![0., 1.],
+        };
+        let sampler = Sampler::new(Filter::Bilinear);
+
+        let sample = |u| sampler.sample(&image, Vector::from([u, 0.5]), &Footprint::default());
+
+        assert_eq!(sample(0.25), 0.);
+        assert_eq!(sample(0.75), 1.);
+        assert_eq!(sample(0.5), 0.5);
+    }
+
+    #[test]
+    fn trilinear_between_mip_levels() {
+        let image = Image {
+            width: 4,
+            height: 4,
+            pixels: (0..16)
+                .map(|index| ((index % 4) * (index % 4)) as f32)
+                .collect(),
+        };
+        // Average of each 2 by 2 block
+        let half = Image {
+            width: 2,
+            height: 2,
+            pixels: vec![0.5, 6.5, 0.5, 6.5],
+        };
+
+        let uv = Vector::from([0.3, 0.6]);
+        let bilinear = Sampler::new(Filter::Bilinear);
+        let (full, half) = (
+            bilinear.sample(&image, uv, &Footprint::default()),
+            bilinear.sample(&half, uv, &Footprint::default()),
+        );
+
+        // Halfway between the first two mip levels, sqrt(2) texels wide
+        let footprint = Footprint {
+            dx: Vector::from([std::f32::consts::SQRT_2 / 4., 0.]),
+            dy: Vector::from([0., 0.1 / 4.]),
+        };
+        let sample = Sampler::new(Filter::Trilinear).sample(&image, uv, &footprint);
+
+        assert!(
+            full < sample && sample < half,
+            "Got {sample} from levels {full} and {half}"
+        );
+        assert!((sample - (full + half) / 2.).abs() < 1e-4, "Got {sample}");
+    }
+
+    #[test]
+    fn anisotropic_grazing_angle() {
+        let image = stripes();
+
+        // A plane seen at a grazing angle: moving one pixel covers many texels along u but less
+        // than one along v.
+        let footprint = Footprint {
+            dx: Vector::from([0.25, 0.]),
+            dy: Vector::from([0., 0.5 / 64.]),
+        };
+
+        let render = |filter| {
+            let sampler = Sampler::new(filter);
+            (0..32)
+                .map(|row| Vector::from([0.3, (row as f32 + 0.5) / 64.]))
+                .map(|uv| sampler.sample(&image, uv, &footprint))
+                .collect::<Vec<f32>>()
+        };
+
+        let trilinear = render(Filter::Trilinear);
+        let anisotropic = render(Filter::Anisotropic {
+            max_samples: DEFAULT_MAX_SAMPLES,
+        });
+
+        assert!(
+            variance(&anisotropic) > 4. * variance(&trilinear),
+            "Expected anisotropic filtering to keep the stripes, got variances {} vs {}",
+            variance(&anisotropic),
+            variance(&trilinear)
+        );
+    }
+}
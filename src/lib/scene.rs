@@ -1,8 +1,65 @@
 //! Collection of objects representing a scene to be rendered.
 
-use crate::{camera::Camera, light::Light, object::Object, Building, Built};
+use crate::{
+    camera::Camera,
+    color::Color,
+    image::Image,
+    light::Light,
+    material::Material,
+    matrix::Transform,
+    object::Object,
+    primitives::{Triangle, Vertex},
+    raycasting::{Ray, RaycastHit},
+    sampler::{Filter, Footprint, Sampler},
+    vector::Vector,
+    Building, Built,
+};
 
-use std::fmt::Debug;
+use std::{
+    f32::consts::{PI, TAU},
+    fmt::Debug,
+    sync::atomic::AtomicUsize,
+};
+
+/// Number of cells along each side of a [checker floor](Scene::add_checker_floor).
+pub const CHECKER_FLOOR_CELLS: usize = 8;
+
+/// Size of the irradiance maps convolved from [environment maps](Scene::set_environment).
+const IRRADIANCE_SIZE: (u32, u32) = (32, 16);
+
+/// Size environment maps are averaged down to before convolving them, bounding the cost of
+/// [Scene::set_environment] however large the map.
+const IRRADIANCE_BINS: (u32, u32) = (64, 32);
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+/// Mistakes found by [Scene::validate], which would otherwise silently render a black image.
+pub enum SceneError {
+    #[error("Scene has no objects to render")]
+    /// Neither objects nor instances were added.
+    NoObjects,
+
+    #[error("Scene has neither lights nor an environment map, everything would render black")]
+    /// Nothing lights the scene.
+    NoLights,
+
+    #[error("Object #{index} ({name:?}) has no faces")]
+    /// An object without any geometry to hit.
+    EmptyObject {
+        #[allow(missing_docs)]
+        index: usize,
+        #[allow(missing_docs)]
+        name: Option<String>,
+    },
+
+    #[error("Instance #{index} references object #{object_index} which does not exist")]
+    /// An [Instance] pointing past the scene's [objects](Scene::objects).
+    InvalidInstance {
+        #[allow(missing_docs)]
+        index: usize,
+        #[allow(missing_docs)]
+        object_index: usize,
+    },
+}
 
 #[derive(Default)]
 /// Scene representation with objects, lights and a camera.
@@ -10,18 +67,28 @@ pub struct Scene<State> {
     state: std::marker::PhantomData<State>,
     /// Objects currently in the scene with a bool indicating visibility.
     pub objects: Vec<Object<Built>>,
+    /// Additional placements of [objects](Self::objects) sharing their geometry.
+    pub instances: Vec<Instance>,
     /// Lights currently in the scene.
     pub lights: Vec<Light>,
     /// Scene camera.
     pub camera: Camera,
+    /// Equirectangular environment map, replacing ambient [Light]s and the background color when
+    /// set, see [environment_radiance](Self::environment_radiance).
+    environment: Option<Image<Color>>,
+    /// Cosine convolution of the environment map, see
+    /// [environment_irradiance](Self::environment_irradiance).
+    irradiance: Option<Image<Color>>,
 }
 
 impl<State> Debug for Scene<State> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Scene")
             .field("objects", &self.objects.len())
+            .field("instances", &self.instances.len())
             .field("lights", &self.lights.len())
             .field("camera", &self.camera)
+            .field("environment", &self.environment.is_some())
             .finish()
     }
 }
@@ -41,15 +108,618 @@ impl Scene<Building> {
         self
     }
 
+    /// Adds a horizontal `size` by `size` floor centered under the origin at height `y`, facing
+    /// up and split into [CHECKER_FLOOR_CELLS] by [CHECKER_FLOOR_CELLS] cells alternating between
+    /// `material_a` and `material_b`.
+    ///
+    /// The cells of each material make up one object, each cell spanning the whole UV square.
+    pub fn add_checker_floor(
+        &mut self,
+        size: f32,
+        y: f32,
+        material_a: Material,
+        material_b: Material,
+    ) -> &mut Self {
+        let cell = size / CHECKER_FLOOR_CELLS as f32;
+        let start = -size / 2.;
+        let up = Vector::new(0., 1., 0.);
+
+        for (parity, material) in [material_a, material_b].into_iter().enumerate() {
+            let mut object = Object::default();
+            let mut faces = Vec::new();
+
+            for row in 0..CHECKER_FLOOR_CELLS {
+                let columns = 0..CHECKER_FLOOR_CELLS;
+                for column in columns.filter(|column| (row + column) % 2 == parity) {
+                    let (x, z) = (start + column as f32 * cell, start + row as f32 * cell);
+                    let vertex = |dx: f32, dz: f32| {
+                        Vertex::new(
+                            Vector::new(x + dx * cell, y, z + dz * cell),
+                            up,
+                            Vector::from([dx, dz]),
+                        )
+                    };
+
+                    // Counter-clockwise when seen from above
+                    faces.push(Triangle::new(
+                        vertex(0., 0.),
+                        vertex(0., 1.),
+                        vertex(1., 0.),
+                    ));
+                    faces.push(Triangle::new(
+                        vertex(1., 0.),
+                        vertex(0., 1.),
+                        vertex(1., 1.),
+                    ));
+                }
+            }
+
+            object
+                .name(&format!("checker_floor_{}", ['a', 'b'][parity]))
+                .vertices(
+                    faces
+                        .iter()
+                        .flat_map(|face| [face.a.position, face.b.position, face.c.position]),
+                )
+                .normals(std::iter::once(up));
+            object.faces = faces;
+            object.material = material;
+
+            self.add_object(
+                object
+                    .build()
+                    .expect("Checker floor has vertices and normals"),
+            );
+        }
+
+        self
+    }
+
+    /// Adds an [Instance] of the object at `object_index` placed with `transform`.
+    ///
+    /// Objects referenced by at least one instance are only rendered through their instances.
+    pub fn add_instance(&mut self, object_index: usize, transform: Transform) -> &mut Self {
+        self.instances.push(Instance {
+            object_index,
+            transform,
+        });
+        self
+    }
+
     /// Adss a light to the scene.
     pub fn add_light(&mut self, light: Light) -> &mut Self {
         self.lights.push(light);
         self
     }
 
+    /// Sets the scene's equirectangular environment map, convolving it once into the irradiance
+    /// map lighting diffuse surfaces, see [environment_irradiance](Scene::environment_irradiance).
+    pub fn set_environment(&mut self, environment: Image<Color>) -> &mut Self {
+        self.irradiance = Some(convolve_irradiance(&environment));
+        self.environment = Some(environment);
+        self
+    }
+
     /// Sets the scene camera to the one passed as argument.
     pub fn set_camera(&mut self, camera: Camera) -> &mut Self {
         self.camera = camera;
         self
     }
 }
+
+impl<State> Scene<State> {
+    /// Check the scene for common mistakes before rendering, e.g. forgetting to add lights.
+    pub fn validate(&self) -> Result<(), SceneError> {
+        if self.objects.is_empty() {
+            return Err(SceneError::NoObjects);
+        }
+
+        if self.lights.is_empty() && self.environment.is_none() {
+            return Err(SceneError::NoLights);
+        }
+
+        if let Some((index, object)) = self
+            .objects
+            .iter()
+            .enumerate()
+            .find(|(_, object)| object.faces.is_empty())
+        {
+            return Err(SceneError::EmptyObject {
+                index,
+                name: object.name.clone(),
+            });
+        }
+
+        if let Some((index, instance)) = self
+            .instances
+            .iter()
+            .enumerate()
+            .find(|(_, instance)| instance.object_index >= self.objects.len())
+        {
+            return Err(SceneError::InvalidInstance {
+                index,
+                object_index: instance.object_index,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// The scene's equirectangular environment map, if any.
+    pub fn environment(&self) -> Option<&Image<Color>> {
+        self.environment.as_ref()
+    }
+
+    /// Radiance coming from the environment map in the given direction, if any.
+    ///
+    /// The map's x axis spans a full turn around +Y starting and ending behind the camera's
+    /// default -Z look direction, and its y axis goes from straight down (first row) to straight
+    /// up (last row).
+    pub fn environment_radiance(&self, dir: &Vector<3, f32>) -> Option<Color> {
+        Some(sample_equirect(self.environment.as_ref()?, dir))
+    }
+
+    /// Cosine-weighted average of the environment map's radiance over the hemisphere around
+    /// `normal`, if any, i.e. its irradiance divided by π.
+    ///
+    /// This is the light a white Lambertian surface facing `normal` reflects, looked up in the
+    /// irradiance map convolved by [set_environment](Scene::set_environment).
+    pub fn environment_irradiance(&self, normal: &Vector<3, f32>) -> Option<Color> {
+        Some(sample_equirect(self.irradiance.as_ref()?, normal))
+    }
+
+    /// Iterate over every hit of the [Ray] with the scene's objects and instances, in world space.
+    pub fn intersections<'a>(&'a self, ray: &'a Ray) -> impl Iterator<Item = RaycastHit> + 'a {
+        self.indexed_intersections(ray, None).map(|(_, hit)| hit)
+    }
+
+    /// Same as [intersections](Self::intersections), adding the number of triangles tested
+    /// against the ray to `tested`.
+    pub fn intersections_counted<'a>(
+        &'a self,
+        ray: &'a Ray,
+        tested: &'a AtomicUsize,
+    ) -> impl Iterator<Item = RaycastHit> + 'a {
+        self.indexed_intersections(ray, Some(tested))
+            .map(|(_, hit)| hit)
+    }
+
+    /// Every hit of the [Ray] with the scene's objects and instances along with the index of the
+    /// hit [object](Self::objects), sorted front to back, e.g. for alpha compositing.
+    pub fn intersect_all(&self, ray: &Ray) -> Vec<(usize, RaycastHit)> {
+        self.intersect_all_counted(ray, &AtomicUsize::default())
+    }
+
+    /// Same as [intersect_all](Self::intersect_all), adding the number of triangles tested
+    /// against the ray to `tested`.
+    pub fn intersect_all_counted(
+        &self,
+        ray: &Ray,
+        tested: &AtomicUsize,
+    ) -> Vec<(usize, RaycastHit)> {
+        let mut hits = self
+            .indexed_intersections(ray, Some(tested))
+            .collect::<Vec<_>>();
+
+        let distance = |hit: &RaycastHit| (hit.position - *ray.start()).len();
+        hits.sort_by(|(_, lhs), (_, rhs)| distance(lhs).total_cmp(&distance(rhs)));
+
+        hits
+    }
+
+    fn indexed_intersections<'a>(
+        &'a self,
+        ray: &'a Ray,
+        tested: Option<&'a AtomicUsize>,
+    ) -> impl Iterator<Item = (usize, RaycastHit)> + 'a {
+        let instanced = |index: usize| {
+            self.instances
+                .iter()
+                .any(|instance| instance.object_index == index)
+        };
+
+        let direct = self
+            .objects
+            .iter()
+            .enumerate()
+            .filter(move |(index, _)| !instanced(*index))
+            .filter_map(move |(index, object)| {
+                let hit = object.intersects_counted(ray, tested.unwrap_or(&AtomicUsize::default()));
+                Some((index, hit?))
+            });
+
+        let instances = self.instances.iter().filter_map(move |instance| {
+            let object = self.objects.get(instance.object_index)?;
+            let hit =
+                instance.intersects_counted(object, ray, tested.unwrap_or(&AtomicUsize::default()));
+            Some((instance.object_index, hit?))
+        });
+
+        direct.chain(instances)
+    }
+}
+
+#[derive(Clone, Debug)]
+/// Placement of an [Object] already present in a [Scene], referencing its geometry instead of
+/// duplicating it.
+pub struct Instance {
+    /// Index of the instanced object in the scene's [objects](Scene::objects).
+    pub object_index: usize,
+    /// Object to world space transformation.
+    pub transform: Transform,
+}
+
+impl Instance {
+    /// Check if a world space ray intersects the instanced `object` by transforming the ray into
+    /// object space, returning the hit in world space.
+    pub fn intersects(&self, object: &Object<Built>, ray: &Ray) -> Option<RaycastHit> {
+        self.intersects_counted(object, ray, &AtomicUsize::default())
+    }
+
+    /// Same as [intersects](Self::intersects), adding the number of triangles tested against the
+    /// ray to `tested`.
+    pub fn intersects_counted(
+        &self,
+        object: &Object<Built>,
+        ray: &Ray,
+        tested: &AtomicUsize,
+    ) -> Option<RaycastHit> {
+        let matrix = self.transform.matrix();
+        let inverse = matrix.inverse()?;
+
+        object
+            .intersects_counted(&ray.transformed(&inverse), tested)
+            .map(|hit| RaycastHit {
+                position: matrix.transform_point(hit.position),
+                // Normals are transformed by the inverse transpose to stay orthogonal to the surface
+                normal: inverse.transpose().transform_vector(hit.normal).normalize(),
+                ..hit
+            })
+    }
+}
+
+/// Bilinear lookup in an equirectangular `map` in the given direction, laid out as described in
+/// [Scene::environment_radiance].
+fn sample_equirect(map: &Image<Color>, dir: &Vector<3, f32>) -> Color {
+    let dir = dir.normalize();
+    let u = 0.5 + dir[0].atan2(-dir[2]) / TAU;
+    // Rows don't wrap around the poles, stop at the centers of the first and last ones instead
+    let half_row = 0.5 / map.height as f32;
+    let v = (0.5 + dir[1].clamp(-1., 1.).asin() / PI).clamp(half_row, 1. - half_row);
+
+    Sampler::new(Filter::Bilinear).sample(map, Vector::from([u, v]), &Footprint::default())
+}
+
+/// Direction through the center of texel (`x`, `y`) of a `width` by `height` equirectangular
+/// map, along with the cosine of its latitude, to which the texel's solid angle is proportional.
+fn equirect_direction(x: u32, y: u32, (width, height): (u32, u32)) -> (Vector<3, f32>, f32) {
+    let longitude = ((x as f32 + 0.5) / width as f32 - 0.5) * TAU;
+    let latitude = ((y as f32 + 0.5) / height as f32 - 0.5) * PI;
+    let (sin_latitude, cos_latitude) = latitude.sin_cos();
+
+    let dir = Vector::new(
+        cos_latitude * longitude.sin(),
+        sin_latitude,
+        -cos_latitude * longitude.cos(),
+    );
+    (dir, cos_latitude)
+}
+
+/// Convolve an equirectangular `environment` map with a clamped cosine lobe, each texel of the
+/// resulting [IRRADIANCE_SIZE] map holding the cosine-weighted average radiance over the
+/// hemisphere around its direction.
+fn convolve_irradiance(environment: &Image<Color>) -> Image<Color> {
+    let size = (environment.width, environment.height);
+    // Texels of the map covered by the `bin`-th of `bins`, at least one when magnifying it
+    let span = |bin: u32, bins: u32, size: u32| {
+        let start = bin * size / bins;
+        start..((bin + 1) * size / bins).max(start + 1)
+    };
+
+    // Direction, radiance averaged over the texels it covers and solid angle of each bin
+    let (bins_x, bins_y) = IRRADIANCE_BINS;
+    let bins: Vec<(Vector<3, f32>, Color, f32)> = (0..bins_y)
+        .flat_map(|y| (0..bins_x).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            let (sum, weight) = span(y, bins_y, size.1)
+                .flat_map(|ty| span(x, bins_x, size.0).map(move |tx| (tx, ty)))
+                .fold((Color::default(), 0.), |(sum, weight), (tx, ty)| {
+                    let (_, solid_angle) = equirect_direction(tx, ty, size);
+                    let radiance = environment.mod_get(tx, ty);
+                    (sum + radiance * solid_angle, weight + solid_angle)
+                });
+
+            let (dir, solid_angle) = equirect_direction(x, y, IRRADIANCE_BINS);
+            (dir, sum / weight, solid_angle)
+        })
+        .collect();
+
+    let mut irradiance = Image::new(IRRADIANCE_SIZE.0, IRRADIANCE_SIZE.1, Color::default());
+    for (x, y, texel) in irradiance.enumerate_pixels_mut() {
+        let (normal, _) = equirect_direction(x, y, IRRADIANCE_SIZE);
+        let (sum, weight) = bins.iter().fold(
+            (Color::default(), 0.),
+            |(sum, weight), (dir, radiance, solid_angle)| {
+                let lobe = normal.dot_product(dir).max(0.) * solid_angle;
+                (sum + *radiance * lobe, weight + lobe)
+            },
+        );
+
+        // The weights add up to π but for the discretization, normalizing by them instead keeps
+        // constant maps as they are
+        *texel = sum / weight;
+    }
+
+    irradiance
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use super::*;
+    use crate::{
+        color::Color,
+        engine::Engine,
+        light::{LightVariant, DEFAULT_SHADOW_BIAS},
+        primitives::{Triangle, Vertex},
+        vector::Vector,
+    };
+
+    #[test]
+    fn validate() {
+        let mut scene = Scene::<Building>::default();
+        assert_eq!(Err(SceneError::NoObjects), scene.validate());
+        assert_eq!(
+            "Scene has no objects to render",
+            scene.validate().unwrap_err().to_string()
+        );
+
+        let mut empty = Object::default();
+        empty.vertices.push(Vector::new(0., 0., 0.));
+        empty.normals.push(Vector::new(0., 1., 0.));
+        scene.add_object(empty.build().unwrap());
+        assert_eq!(Err(SceneError::NoLights), scene.validate());
+
+        scene.add_light(Light {
+            transform: Transform::default(),
+            variant: LightVariant::Ambient,
+            color: Color::new(1., 1., 1.),
+            brightness: 1.,
+            shadow_softness: 0.,
+            shadow_bias: DEFAULT_SHADOW_BIAS,
+        });
+        assert_eq!(
+            Err(SceneError::EmptyObject {
+                index: 0,
+                name: Some(String::new())
+            }),
+            scene.validate()
+        );
+
+        scene.objects[0] = Object::load_obj(Path::new("./objects/cube.obj"))
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(Ok(()), scene.validate());
+
+        scene.add_instance(1, Transform::default());
+        assert_eq!(
+            Err(SceneError::InvalidInstance {
+                index: 0,
+                object_index: 1
+            }),
+            scene.validate()
+        );
+    }
+
+    #[test]
+    fn environment_irradiance() {
+        let directions = [
+            Vector::new(0., 1., 0.),
+            Vector::new(0., -1., 0.),
+            Vector::new(1., 0.3, -0.2),
+            Vector::new(0., 0., 1.),
+        ];
+
+        let mut scene = Scene::<Building>::default();
+        scene.set_environment(Image::new(4, 2, Color::new(0.2, 0.4, 0.8)));
+        for dir in &directions {
+            let irradiance = scene.environment_irradiance(dir).unwrap();
+            assert!(
+                irradiance.approx_eq(&Color::new(0.2, 0.4, 0.8), 1e-5),
+                "Expected a constant map to stay constant, got {irradiance:?} toward {dir:?}"
+            );
+        }
+
+        // White sky over a black ground
+        let mut sky = Image::new(16, 8, Color::default());
+        sky.enumerate_pixels_mut()
+            .filter(|(_, y, _)| *y >= 4)
+            .for_each(|(_, _, texel)| *texel = Color::new(1., 1., 1.));
+        scene.set_environment(sky);
+
+        let irradiance = |dir| scene.environment_irradiance(&dir).unwrap().r;
+        assert!((irradiance(Vector::new(0., 1., 0.)) - 1.).abs() < 0.05);
+        assert!(irradiance(Vector::new(0., -1., 0.)) < 0.05);
+        assert!((irradiance(Vector::new(1., 0., 0.)) - 0.5).abs() < 0.05);
+
+        // Surfaces tilted toward the ground still see part of the sky
+        let tilted = Vector::new(1., -0.3, 0.);
+        assert_eq!(scene.environment_radiance(&tilted).unwrap().r, 0.);
+        assert!(irradiance(tilted) > 0.2, "Got {}", irradiance(tilted));
+    }
+
+    #[test]
+    fn checker_floor() {
+        let mut scene = Scene::<Building>::default();
+        scene.add_checker_floor(
+            16.,
+            -1.5,
+            Material::diffuse(Color::new(1., 1., 1.)),
+            Material::diffuse(Color::new(0., 0., 0.)),
+        );
+        assert_eq!(2, scene.objects.len());
+
+        let hit = |x, z| {
+            let ray = Ray::new(Vector::new(x, 10., z), Vector::new(0., -1., 0.));
+            let (index, hit) = scene.intersect_all(&ray).into_iter().next().unwrap();
+            assert!(
+                (hit.position[1] + 1.5).abs() < 1e-5,
+                "Got {:?}",
+                hit.position
+            );
+            assert!(hit.normal.approx_eq(&Vector::new(0., 1., 0.), 1e-6));
+            index
+        };
+
+        // 2 units wide cells, alternating
+        assert_eq!(0, hit(-7., -7.));
+        assert_eq!(1, hit(-5., -7.));
+        assert_eq!(1, hit(-7., -5.));
+        assert_eq!(0, hit(0.5, 0.5));
+        assert_eq!(1, hit(-0.5, 0.5));
+
+        // Nothing past the edges
+        let ray = Ray::new(Vector::new(8.5, 10., 0.), Vector::new(0., -1., 0.));
+        assert!(scene.intersect_all(&ray).is_empty());
+    }
+
+    #[test]
+    fn instance_hit_position() {
+        let cube = Object::load_obj(Path::new("./objects/cube.obj"))
+            .unwrap()
+            .build()
+            .unwrap();
+        let instance = Instance {
+            object_index: 0,
+            transform: Transform::default().apply_translation(Vector::new(3., 0., 0.)),
+        };
+
+        let ray = Ray::new(Vector::new(3.5, 0.5, 5.), Vector::new(0., 0., -1.));
+        let hit = instance.intersects(&cube, &ray).unwrap();
+        assert!(
+            hit.position.approx_eq(&Vector::new(3.5, 0.5, 1.), 1e-5),
+            "Expected a world-space hit on the translated front face, got {:?}",
+            hit.position
+        );
+        assert!(hit.normal.approx_eq(&Vector::new(0., 0., 1.), 1e-5));
+
+        // The untransformed cube is not in the ray's path
+        assert!(cube.intersects(&ray).is_none());
+    }
+
+    #[test]
+    fn instance_non_uniform_scale() {
+        let cube = Object::load_obj(Path::new("./objects/cube.obj"))
+            .unwrap()
+            .build()
+            .unwrap();
+        // Rotated before being stretched along x, so the faces are no longer axis aligned
+        let instance = Instance {
+            object_index: 0,
+            transform: Transform::default()
+                .apply_scale(Vector::new(3., 1., 0.5))
+                .apply_rotation(Vector::new(0., 1., 0.), std::f32::consts::FRAC_PI_4),
+        };
+        let matrix = instance.transform.matrix();
+
+        for x in [-2., -0.5, 0.5, 2.] {
+            let ray = Ray::new(Vector::new(x, 0.25, 10.), Vector::new(0., 0., -1.));
+            let hit = instance.intersects(&cube, &ray).unwrap();
+
+            let face = &cube.faces[hit.face_index];
+            let [a, b, c] = [&face.a, &face.b, &face.c].map(|v| matrix.transform_point(v.position));
+            for edge in [b - a, c - a] {
+                let dot = hit.normal.dot_product(&edge.normalize());
+                assert!(
+                    dot.abs() < 1e-5,
+                    "Normal {:?} not perpendicular to {edge:?}",
+                    hit.normal
+                );
+            }
+
+            assert!((hit.normal.len() - 1.).abs() < 1e-5);
+            assert!(
+                hit.normal.dot_product(ray.dir()) < 0.,
+                "Expected an outward normal"
+            );
+        }
+    }
+
+    #[test]
+    fn instancing() {
+        let cube = Object::load_obj(Path::new("./objects/cube.obj"))
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let mut engine = Engine::new((16, 16), 0, 0).unwrap();
+        engine
+            .scene()
+            .set_camera(Camera {
+                center: Vector::new(0., 0., 5.),
+                width: 16,
+                ..Default::default()
+            })
+            .add_object(cube)
+            .add_instance(
+                0,
+                Transform::default().apply_translation(Vector::new(-2.5, 0., 0.)),
+            )
+            .add_instance(
+                0,
+                Transform::default().apply_translation(Vector::new(2.5, 0., 0.)),
+            );
+
+        let image = engine.render_to_image();
+
+        let background = Color::new(0.1, 0.1, 0.2);
+        assert_ne!(background, image.mod_get(3, 8), "Missing left instance");
+        assert_ne!(background, image.mod_get(12, 8), "Missing right instance");
+        assert_eq!(
+            background,
+            image.mod_get(8, 8),
+            "Instanced object should not be rendered at its origin"
+        );
+    }
+
+    #[test]
+    fn intersect_all() {
+        let plane = |z: f32| {
+            let normal = Vector::new(0., 0., 1.);
+            let vertex = |x, y| Vertex::new(Vector::new(x, y, z), normal, Vector::from([0., 0.]));
+
+            let mut object = Object::default();
+            object
+                .vertices([Vector::new(-1., -1., z), Vector::new(1., 1., z)].into_iter())
+                .normals(std::iter::once(normal));
+            object.faces.push(Triangle::new(
+                vertex(-1., -1.),
+                vertex(1., -1.),
+                vertex(0., 1.),
+            ));
+            object.build().unwrap()
+        };
+
+        let mut scene = Scene::<Building>::default();
+        scene
+            .add_object(plane(1.))
+            .add_object(plane(-1.))
+            .add_object(plane(0.));
+
+        let ray = Ray::new(Vector::new(0., 0., 5.), Vector::new(0., 0., -1.));
+        let hits = scene.intersect_all(&ray);
+
+        assert_eq!(
+            hits.iter().map(|(index, _)| *index).collect::<Vec<_>>(),
+            [0, 2, 1]
+        );
+        assert_eq!(
+            hits.iter()
+                .map(|(_, hit)| hit.position[2])
+                .collect::<Vec<_>>(),
+            [1., 0., -1.]
+        );
+    }
+}
@@ -0,0 +1,277 @@
+//! One-call evaluation of .eray files, to try shaders out without writing any Rust.
+
+use super::{
+    graph::{self, ImportedNode, Name, SocketType, SocketValue, Unvalidated},
+    parsing::{self, parse_shader},
+    shader::Side,
+};
+
+use crate::{
+    color::Color,
+    image::Image,
+    sampler::{Footprint, Sampler},
+    vector::Vector,
+};
+
+use std::{collections::HashMap, path::Path};
+
+/// Graph output baked by [eval_eray_to_image] when the graph has several outputs.
+pub const COLOR_OUTPUT: &str = "color";
+
+#[derive(Debug, thiserror::Error)]
+/// Possible errors returned by [eval_eray_to_image].
+pub enum Error {
+    #[error(transparent)]
+    /// The .eray file could not be read.
+    Io(#[from] std::io::Error),
+
+    #[error(transparent)]
+    /// The .eray file is invalid, boxed as parsing errors are large.
+    Parsing(Box<parsing::Error>),
+
+    #[error(transparent)]
+    /// The parsed graph failed to validate or run.
+    Graph(#[from] graph::Error),
+
+    #[error("Output of type {0:?} cannot be baked as a color")]
+    /// The baked output's type has no conversion to [Color] or to a [Color] image.
+    Bake(SocketType),
+}
+
+impl From<parsing::Error> for Error {
+    fn from(value: parsing::Error) -> Self {
+        Self::Parsing(Box::new(value))
+    }
+}
+
+/// Parse the .eray file at `path` against the `loaded` nodes, set its `inputs`, then run it and
+/// bake its output into a `width` by `height` image.
+///
+/// The baked output is the one named [COLOR_OUTPUT], or the graph's only output, and must be
+/// convertible to a [Color] or to a [Color image](SocketType::IColor), the latter being resampled
+/// to the requested size with the nearest texel of each pixel's center.
+///
+/// Graph inputs named `x` and `y` of type [Value](SocketType::Value) that are not part of
+/// `inputs` are set to each pixel's normalized coordinates, running the graph once per pixel;
+/// otherwise it only runs once.
+///
+/// # Example
+/// ```no_run
+/// use eray::shader::{eval::eval_eray_to_image, graph::SocketValue, procedural::Registry};
+/// use std::path::Path;
+///
+/// // Register the nodes imported by the file beforehand
+/// let mut registry = Registry::new();
+///
+/// let image = eval_eray_to_image(
+///     Path::new("./nodes/constants.eray"),
+///     [("x".into(), SocketValue::Value(Some(0.25)))].into(),
+///     64,
+///     64,
+///     registry.loaded(),
+/// )
+/// .unwrap();
+/// ```
+pub fn eval_eray_to_image(
+    path: &Path,
+    inputs: HashMap<Name, SocketValue>,
+    width: u32,
+    height: u32,
+    loaded: &mut HashMap<Name, Vec<ImportedNode<Unvalidated>>>,
+) -> Result<Image<Color>, Error> {
+    let code = std::fs::read_to_string(path)?;
+    let mut graph = parse_shader(&code, loaded)?;
+
+    for (name, value) in inputs.iter() {
+        let input = graph
+            .inputs
+            .get_mut(name)
+            .ok_or_else(|| graph::Error::Missing(Side::Input, name.clone()))?;
+        *input = value.clone();
+    }
+
+    let output = match graph.outputs.len() {
+        1 => graph.outputs.keys().next().cloned().unwrap(),
+        _ => COLOR_OUTPUT.into(),
+    };
+    if !graph.outputs.contains_key(&output) {
+        return Err(graph::Error::UnknownOutput {
+            name: output,
+            available: graph.outputs.keys().cloned().collect(),
+        }
+        .into());
+    }
+
+    let coordinate = |name: &str| {
+        let name = Name::from(name);
+        (!inputs.contains_key(&name)
+            && matches!(graph.inputs.get(&name), Some(SocketValue::Value(_))))
+        .then_some(name)
+    };
+    let (x_input, y_input) = (coordinate("x"), coordinate("y"));
+    let per_pixel = x_input.is_some() || y_input.is_some();
+
+    let mut graph = graph.validate()?;
+    let mut image = Image::new(width, height, Color::default());
+
+    let sampler = Sampler::default();
+    let mut baked = None;
+    for y in 0..height {
+        for x in 0..width {
+            if per_pixel || baked.is_none() {
+                let coordinates = [
+                    (&x_input, x as f32 / width as f32),
+                    (&y_input, y as f32 / height as f32),
+                ];
+                for (name, value) in coordinates {
+                    if let Some(name) = name {
+                        graph
+                            .inputs
+                            .insert(name.clone(), SocketValue::Value(Some(value)));
+                    }
+                }
+
+                graph.reset();
+                graph.run()?;
+
+                let (_ref, value) = &graph.outputs[&output];
+                baked = Some(bake(value)?);
+            }
+
+            // Texel centers, image outputs being resampled to the requested size
+            let uv = Vector::from([
+                (x as f32 + 0.5) / width as f32,
+                (y as f32 + 0.5) / height as f32,
+            ]);
+            let color = sampler.sample(baked.as_ref().unwrap(), uv, &Footprint::default());
+            image.set(x, y, color);
+        }
+    }
+
+    Ok(image)
+}
+
+/// Convert an output to a [Color] image, scalar outputs making up a 1 by 1 image.
+fn bake(value: &SocketValue) -> Result<Image<Color>, Error> {
+    let image = match value.clone().try_convert(SocketType::Color) {
+        Ok(SocketValue::Color(color)) => Some(Image::new(1, 1, color.unwrap_or_default())),
+        _ => match value.clone().try_convert(SocketType::IColor) {
+            Ok(SocketValue::IColor(image)) => image,
+            _ => return Err(Error::Bake(SocketType::from(value))),
+        },
+    };
+
+    Ok(image.unwrap_or_else(|| Image::new(1, 1, Color::default())))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::shader::{
+        procedural::{ProceduralNode, Registry},
+        shader::Error as ShaderError,
+        Signature,
+    };
+
+    /// Stand-in for a noise node, returning its `x` input as is.
+    struct Gradient;
+
+    impl ProceduralNode for Gradient {
+        fn signature(&self) -> Signature {
+            Signature::new(
+                [
+                    ("x".into(), SocketType::Value),
+                    ("y".into(), SocketType::Value),
+                ],
+                [("value".into(), SocketType::Value)],
+            )
+        }
+
+        fn evaluate(
+            &self,
+            inputs: &HashMap<Name, SocketValue>,
+        ) -> Result<HashMap<Name, SocketValue>, ShaderError> {
+            Ok([("value".into(), inputs[&"x".into()].clone())].into())
+        }
+    }
+
+    /// Adds its `rhs` input's red channel or value to its `lhs` value.
+    struct Add(SocketType);
+
+    impl ProceduralNode for Add {
+        fn signature(&self) -> Signature {
+            Signature::new(
+                [("lhs".into(), SocketType::Value), ("rhs".into(), self.0)],
+                [("value".into(), SocketType::Value)],
+            )
+        }
+
+        fn evaluate(
+            &self,
+            inputs: &HashMap<Name, SocketValue>,
+        ) -> Result<HashMap<Name, SocketValue>, ShaderError> {
+            let rhs = match &inputs[&"rhs".into()] {
+                SocketValue::Value(value) => value.unwrap_or_default(),
+                SocketValue::Color(color) => color.unwrap_or_default().r,
+                other => Err(ShaderError::InvalidType {
+                    name: "rhs".into(),
+                    got: SocketType::from(other),
+                    expected: self.0,
+                })?,
+            };
+            let SocketValue::Value(lhs) = inputs[&"lhs".into()] else {
+                unreachable!()
+            };
+
+            Ok([(
+                "value".into(),
+                SocketValue::Value(Some(lhs.unwrap_or_default() + rhs)),
+            )]
+            .into())
+        }
+    }
+
+    #[test]
+    fn eval_test_file() {
+        let mut registry = Registry::new();
+        registry
+            .register("noise", Gradient)
+            .register("add", Add(SocketType::Value))
+            .register("add", Add(SocketType::Color));
+
+        let image = eval_eray_to_image(
+            Path::new("./nodes/test.eray"),
+            [(
+                "color".into(),
+                SocketValue::Color(Some(Color::new(0.25, 0., 0.))),
+            )]
+            .into(),
+            4,
+            2,
+            registry.loaded(),
+        )
+        .unwrap();
+
+        assert_eq!((image.width, image.height), (4, 2));
+
+        // value = noise(x, y) + x + color.r = 2x + 0.25
+        for (x, y, pixel) in image.enumerate_pixels() {
+            let expected = 2. * x as f32 / 4. + 0.25;
+            assert!(
+                pixel.approx_eq(&Color::from(expected), 1e-6),
+                "Expected {expected} at ({x}, {y}), got {pixel:?}"
+            );
+        }
+
+        assert!(matches!(
+            eval_eray_to_image(
+                Path::new("./nodes/test.eray"),
+                [("missing".into(), SocketValue::Value(Some(0.)))].into(),
+                4,
+                2,
+                registry.loaded(),
+            ),
+            Err(Error::Graph(graph::Error::Missing(Side::Input, _)))
+        ));
+    }
+}
@@ -1,19 +1,26 @@
 //! Flat (non-recursive) [Graph] data structure implementation.
 
 use super::{
-    shader::{Shader, Side},
+    shader::{Error as ShaderError, Shader, Side},
     Signature,
 };
 
-use crate::{color::Color, image::{Image, Convertible}, vector::Vector};
+use crate::{
+    color::Color,
+    image::{Convertible, Image},
+    matrix::Mat4,
+    vector::Vector,
+};
 
 use std::{
-    collections::{HashMap, VecDeque},
+    collections::{hash_map::DefaultHasher, HashMap, HashSet, VecDeque},
     convert::AsRef,
     fmt::Debug,
+    hash::{Hash, Hasher},
     marker::PhantomData,
     str::FromStr,
     string::ToString,
+    time::{Duration, Instant},
 };
 
 use paste::paste;
@@ -80,10 +87,54 @@ macro_rules! socket_value {
 
                     self
                 }
+
+                /// Cheap hash of the socket's type and contents, meant for caching.
+                ///
+                /// Scalars are hashed directly while images are hashed through their dimensions
+                /// and a sampled checksum of their pixels, so two images differing only on
+                /// unsampled pixels will collide.
+                pub fn content_hash(&self) -> u64 {
+                    let mut hasher = DefaultHasher::new();
+                    SocketType::from(self).hash(&mut hasher);
+
+                    match self {
+                        $(
+                            SocketValue::$name(opt) => opt.as_ref().map(|v| v.content_hash(&mut hasher)),
+                            SocketValue::[<I  $name>](opt) => opt.as_ref().map(|v| v.content_hash(&mut hasher)),
+                        )+
+                    };
+
+                    hasher.finish()
+                }
+
+                $(
+                    #[doc = concat!("Get a reference to the contained [", stringify!($name), "](SocketType::", stringify!($name), ") image.")]
+                    ///
+                    /// Meant for use outside of nodes, where [get_sv](crate::get_sv) is not
+                    /// available. As a value does not know its socket, errors carry an empty
+                    /// [Name].
+                    ///
+                    /// # Errors
+                    /// [InvalidType](ShaderError::InvalidType) if the value is of another type,
+                    /// [Missing](ShaderError::Missing) if it is unset.
+                    pub fn [<as_ $name:snake _image>](&self) -> Result<&Image<$type>, ShaderError> {
+                        match self {
+                            SocketValue::[<I  $name>](Some(image)) => Ok(image),
+                            SocketValue::[<I  $name>](None) => {
+                                Err(ShaderError::Missing(Side::Input, Name::default()))
+                            }
+                            other => Err(ShaderError::InvalidType {
+                                name: Name::default(),
+                                got: SocketType::from(other),
+                                expected: SocketType::[<I  $name>],
+                            }),
+                        }
+                    }
+                )+
             }
 
             #[allow(unused)]
-            #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+            #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
             /// Possible socket types.
             pub enum SocketType {
                 #[default]
@@ -128,6 +179,9 @@ macro_rules! socket_conversions {
     ($($src:ident => $($dst:ident by $method:path)|+),+ $(,)?) => {paste!{
         impl SocketValue {
             /// Attempt conversion between two socket values.
+            ///
+            /// Images convert to the image types their pixels' type converts to, pixel by pixel,
+            /// unset values staying unset.
             pub fn try_convert(self, target: SocketType) -> Result<Self, ()> {
                 let err = Err(());
 
@@ -157,6 +211,62 @@ macro_rules! socket_conversions {
     }};
 }
 
+/// Maximum number of pixels sampled when hashing an [Image].
+const IMAGE_HASH_SAMPLES: usize = 1024;
+
+/// Content hashing for socket value payloads, see [SocketValue::content_hash].
+trait ContentHash {
+    fn content_hash<H: Hasher>(&self, state: &mut H);
+}
+
+impl ContentHash for bool {
+    fn content_hash<H: Hasher>(&self, state: &mut H) {
+        self.hash(state);
+    }
+}
+
+impl ContentHash for f32 {
+    fn content_hash<H: Hasher>(&self, state: &mut H) {
+        self.to_bits().hash(state);
+    }
+}
+
+impl<const DIM: usize> ContentHash for Vector<DIM, f32> {
+    fn content_hash<H: Hasher>(&self, state: &mut H) {
+        self.inner.iter().for_each(|v| v.content_hash(state));
+    }
+}
+
+impl ContentHash for Color {
+    fn content_hash<H: Hasher>(&self, state: &mut H) {
+        [self.r, self.g, self.b]
+            .iter()
+            .for_each(|v| v.content_hash(state));
+    }
+}
+
+impl ContentHash for Mat4 {
+    fn content_hash<H: Hasher>(&self, state: &mut H) {
+        self.inner
+            .iter()
+            .flatten()
+            .for_each(|v| v.content_hash(state));
+    }
+}
+
+impl<T: ContentHash> ContentHash for Image<T> {
+    fn content_hash<H: Hasher>(&self, state: &mut H) {
+        self.width.hash(state);
+        self.height.hash(state);
+
+        let step = (self.pixels.len() / IMAGE_HASH_SAMPLES).max(1);
+        self.pixels
+            .iter()
+            .step_by(step)
+            .for_each(|pixel| pixel.content_hash(state));
+    }
+}
+
 socket_value! {
     /// Single value
     Value: f32 = 0.,
@@ -166,45 +276,26 @@ socket_value! {
     Vec3: Vector<3, f32> = Vector::default(),
     /// 3-channel color
     Color: Color = Color::default(),
+    /// 4x4 transformation matrix
+    Matrix: Mat4 = Mat4::identity(),
+    /// Boolean condition
+    Bool: bool = false,
+}
+
+/// Any value other than `0.` is true.
+fn value_to_bool(value: f32) -> bool {
+    value != 0.
 }
 
 socket_conversions! {
-    Value => Vec2 by Into::into | Vec3 by Into::into | Color by Into::into,
+    Value => Vec2 by Into::into | Vec3 by Into::into | Color by Into::into | Bool by value_to_bool,
     Vec2 => Value by Into::into,
-    Vec3 => Value by Into::into | Color by Into::into,
+    Vec3 => Value by Into::into | Color by Into::into | Matrix by Into::into,
     Color => Value by Into::into | Vec3 by Into::into,
+    Matrix => Vec3 by Into::into,
+    Bool => Value by f32::from,
 }
 
-// impl SocketValue {
-//     /// Attempt conversion between two socket values.
-//     pub fn try_convert(self, target: SocketType) -> Result<Self, ()> {
-//         let err = Err(());
-//
-//         Ok(match self {
-//             SocketValue::Value(opt) => match target {
-//                 SocketType::Vec2 => SocketValue::Vec2(opt.map(From::from)),
-//                 SocketType::Vec3 => SocketValue::Vec3(opt.map(From::from)),
-//                 SocketType::Color => SocketValue::Color(opt.map(From::from)),
-//                 _ => err?,
-//             },
-//             SocketValue::IValue(opt) => match target {
-//                 SocketType::IVec2 => SocketValue::IVec2(opt.map(Image::convert_image)),
-//                 SocketType::IVec3 => SocketValue::IVec3(opt.map(Image::convert_image)),
-//                 SocketType::IColor => SocketValue::IColor(opt.map(Image::convert_image)),
-//                 _ => err?,
-//             },
-//
-//             SocketValue::Vec3(opt) => todo!(),
-//             SocketValue::IVec3(opt) => todo!(),
-//
-//             SocketValue::Color(opt) => todo!(),
-//             SocketValue::IColor(opt) => todo!(),
-//
-//             _ => err?,
-//         })
-//     }
-// }
-
 #[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
 /// Wrapper around [String].
 pub struct NodeId(String);
@@ -243,7 +334,7 @@ impl ToString for Name {
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 /// Reference to a [Graph] or [Node] socket.
 pub enum SocketRef {
     /// Node [NodeId] and output socket [Name]
@@ -323,12 +414,14 @@ pub enum Error {
     /// An unlinked and unset graph output is likely unintended.
     UnlinkeUnsetdGraphOutput(Name),
 
-    #[error("Detected a cycle while validating the path {during:?}; cycle is from a `{}` socket to a `{}` socket, reaching node `{}`",
-        source_socket.to_string(), target_socket.to_string(), detected.to_string())]
+    #[error("Detected a cycle {} -> {}; cycle is from a `{}` socket to a `{}` socket, reaching node `{}`",
+        cycle.iter().map(|id| id.to_string()).collect::<Vec<String>>().join(" -> "),
+        detected.to_string(), source_socket.to_string(), target_socket.to_string(), detected.to_string())]
     /// Detected a cycle on the node with the given [NodeId].
     Cycle {
-        /// Current path.
-        during: Vec<NodeId>,
+        /// Nodes forming the loop, starting with `detected`, each taking an input from the next
+        /// one and the last one from the first.
+        cycle: Vec<NodeId>,
         /// Name of the socket the detected node was reached from.
         source_socket: Name,
         /// Name of the socket the current node was reached through.
@@ -341,9 +434,83 @@ pub enum Error {
     /// [Shader] returned with an error.
     Shader(super::shader::Error),
 
+    #[error("Node `{}` did not set its output(s) {}", node.to_string(),
+        outputs.iter().map(|v| format!("`{}`", v.to_string())).collect::<Vec<String>>().join(", "))]
+    /// A node's [Shader] returned successfully without writing every declared output.
+    UnsetOutputs {
+        /// Node whose shader was run.
+        node: NodeId,
+        /// Outputs left without a value, sorted by name.
+        outputs: Vec<Name>,
+    },
+
     #[error("Referencing missing {0:?} socket {}", .1.to_string())]
     /// Trying to get/set a non-existent socket.
     Missing(Side, Name),
+
+    #[error("Unknown graph output `{}`, available outputs are: {}",
+        name.to_string(), available.iter().map(|v| v.to_string()).collect::<Vec<String>>().join(", "))]
+    /// Referencing a graph output that does not exist.
+    UnknownOutput {
+        /// Requested output.
+        name: Name,
+        /// Outputs actually defined by the graph.
+        available: Vec<Name>,
+    },
+
+    #[error("Graph output(s) {} left unset by their linked socket",
+        .0.iter().map(|v| format!("`{}`", v.to_string())).collect::<Vec<String>>().join(", "))]
+    /// Linked graph outputs still unset after a [run](Graph::run), with the
+    /// [Error](OutputPolicy::Error) policy.
+    UnsetGraphOutputs(Vec<Name>),
+
+    #[error("Node `{}` does not exist", .0.to_string())]
+    /// Referencing a node that is not part of the graph.
+    MissingNode(NodeId),
+
+    #[error("Cannot rename to `{0}`, the name is already taken")]
+    /// Renaming a graph socket or node to the name of another one.
+    NameTaken(String),
+
+    #[error("UV channel {channel} out of range, vertices have {available}")]
+    /// Reading a [Material](crate::material::Material)'s images through a UV channel vertices do
+    /// not have.
+    UvChannelOutOfRange {
+        /// Requested channel.
+        channel: usize,
+        /// Number of UV channels per vertex.
+        available: usize,
+    },
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+/// What [Graph::run] does with linked graph outputs whose upstream socket produced no value.
+pub enum OutputPolicy {
+    #[default]
+    /// Set them to their type's default value.
+    Default,
+    /// Fail with [Error::UnsetGraphOutputs].
+    Error,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// Likely wiring mistake reported by [Graph::lint].
+pub enum Lint {
+    /// Graph input neither linked to a graph output nor used by a node contributing to one.
+    UnusedInput(Name),
+    /// Node not on any path to a graph output.
+    DeadNode(NodeId),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// Values and wiring of a [Graph] at some point, without its nodes' [Shader]s and computed
+/// values, see [Graph::snapshot].
+pub struct GraphSnapshot<State> {
+    inputs: HashMap<Name, SocketValue>,
+    outputs: HashMap<Name, (Option<SocketRef>, SocketValue)>,
+    links: HashMap<NodeId, HashMap<Name, (Option<SocketRef>, SocketType)>>,
+    output_policy: OutputPolicy,
+    state: PhantomData<State>,
 }
 
 impl From<super::shader::Error> for Error {
@@ -364,6 +531,9 @@ pub struct Graph<State> {
     /// Mapping of [NodeIds](NodeId) to [Nodes](Node).
     pub nodes: HashMap<NodeId, Node<State>>,
 
+    /// Handling of linked outputs left unset after a [run](Graph::run).
+    pub(crate) output_policy: OutputPolicy,
+
     /// Current state
     pub state: PhantomData<State>,
 }
@@ -397,20 +567,419 @@ pub struct Graph<State> {
 /// };
 /// ```
 macro_rules! graph {
-    { $($field:ident $(: $($name:literal : $value:expr),+)? $(,)?),+ } => {
-        $crate::shader::graph::Graph {
-            $($field: [$($(($name.into(), $value)),+)?].into_iter().collect()),+,
-            state: ::std::marker::PhantomData::<$crate::shader::graph::Unvalidated>,
+    { $($field:ident $(: $($name:literal : $value:expr),+)? $(,)?),+ } => {{
+        let mut graph = $crate::shader::graph::Graph::<
+            $crate::shader::graph::Unvalidated,
+        >::default();
+        $(graph.$field = [$($(($name.into(), $value)),+)?].into_iter().collect();)+
+        graph
+    }};
+}
+
+impl<State> Graph<State> {
+    /// Get the handling of linked outputs left unset after a [run](Graph::run).
+    pub fn output_policy(&self) -> OutputPolicy {
+        self.output_policy
+    }
+
+    /// Set the handling of linked outputs left unset after a [run](Graph::run).
+    pub fn set_output_policy(&mut self, policy: OutputPolicy) -> &mut Self {
+        self.output_policy = policy;
+        self
+    }
+
+    /// Get the graph's type signature, the output types being those of the stored values.
+    pub fn signature(&self) -> Signature {
+        Signature::new(
+            self.inputs
+                .iter()
+                .map(|(name, value)| (name.clone(), SocketType::from(value))),
+            self.outputs
+                .iter()
+                .map(|(name, (_socket_ref, value))| (name.clone(), SocketType::from(value))),
+        )
+    }
+
+    /// Report unused graph inputs and nodes not contributing to any graph output.
+    pub fn lint(&self) -> Vec<Lint> {
+        let reachable = self.reachable_nodes();
+
+        let used_inputs = self
+            .nodes
+            .iter()
+            .filter(|(id, _node)| reachable.contains(id))
+            .flat_map(|(_id, node)| node.inputs().values().map(|(socket_ref, _type)| socket_ref))
+            .chain(self.outputs.values().map(|(socket_ref, _value)| socket_ref))
+            .filter_map(|socket_ref| match socket_ref {
+                Some(SocketRef::Graph(name)) => Some(name),
+                _ => None,
+            })
+            .collect::<HashSet<&Name>>();
+
+        let mut unused_inputs = self
+            .inputs
+            .keys()
+            .filter(|&name| !used_inputs.contains(name))
+            .cloned()
+            .collect::<Vec<Name>>();
+        unused_inputs.sort_by_key(|name| name.to_string());
+
+        let mut dead_nodes = self
+            .nodes
+            .keys()
+            .filter(|&id| !reachable.contains(id))
+            .cloned()
+            .collect::<Vec<NodeId>>();
+        dead_nodes.sort_by_key(|id| id.to_string());
+
+        unused_inputs
+            .into_iter()
+            .map(Lint::UnusedInput)
+            .chain(dead_nodes.into_iter().map(Lint::DeadNode))
+            .collect()
+    }
+
+    /// Get the current value of the graph input or node output referenced by `socket_ref`.
+    ///
+    /// Returns [None] if the referenced node or socket does not exist.
+    pub fn resolve(&self, socket_ref: &SocketRef) -> Option<&SocketValue> {
+        match socket_ref {
+            SocketRef::Node(id, name) => self.nodes.get(id)?.output(name),
+            SocketRef::Graph(name) => self.inputs.get(name),
         }
-    };
+    }
+
+    /// Remove all nodes not contributing to any graph output, leaving the outputs unchanged.
+    pub fn prune(&mut self) -> &mut Self {
+        let reachable = self.reachable_nodes();
+        self.nodes.retain(|id, _node| reachable.contains(id));
+        self
+    }
+
+    /// Check whether both graphs share the same topology and socket types, regardless of how
+    /// their nodes are named.
+    ///
+    /// Socket values are ignored, as are [Shader]s like with [GraphNode]'s equality. Nodes are
+    /// matched starting from the graph outputs, nodes not contributing to any output being
+    /// matched by trial and error.
+    pub fn structurally_eq(&self, other: &Self) -> bool {
+        let output_types = |graph: &Self| {
+            graph
+                .outputs
+                .iter()
+                .map(|(name, (_ref, value))| (name.clone(), SocketType::from(value)))
+                .collect::<HashMap<_, _>>()
+        };
+        let input_types = |graph: &Self| {
+            graph
+                .inputs
+                .iter()
+                .map(|(name, value)| (name.clone(), SocketType::from(value)))
+                .collect::<HashMap<_, _>>()
+        };
+
+        if self.nodes.len() != other.nodes.len()
+            || input_types(self) != input_types(other)
+            || output_types(self) != output_types(other)
+        {
+            return false;
+        }
+
+        let mut mapping = NodeMapping::default();
+        let outputs_match = self.outputs.iter().all(|(name, (socket_ref, _value))| {
+            let (other_ref, _value) = &other.outputs[name];
+            self.refs_match(other, socket_ref, other_ref, &mut mapping)
+        });
+
+        outputs_match && self.match_remaining_nodes(other, mapping)
+    }
+
+    /// Save the graph's input and output values along with the wiring of its nodes, e.g. to
+    /// implement undo in an editor.
+    pub fn snapshot(&self) -> GraphSnapshot<State> {
+        GraphSnapshot {
+            inputs: self.inputs.clone(),
+            outputs: self.outputs.clone(),
+            links: self
+                .nodes
+                .iter()
+                .map(|(id, node)| (id.clone(), node.inputs().clone()))
+                .collect(),
+            output_policy: self.output_policy,
+            state: PhantomData,
+        }
+    }
+
+    /// Bring the graph back to a [snapshot](Self::snapshot) of itself, dropping the nodes added
+    /// since. Computed values are [reset](Self::reset) as they may be stale.
+    ///
+    /// Fails without modifying the graph if a node of the snapshot was removed since, as its
+    /// [Shader] cannot be restored.
+    pub fn restore(&mut self, snapshot: GraphSnapshot<State>) -> Result<&mut Self, Error> {
+        if let Some(id) = snapshot
+            .links
+            .keys()
+            .find(|id| !self.nodes.contains_key(id))
+        {
+            return Err(Error::MissingNode(id.clone()));
+        }
+
+        let GraphSnapshot {
+            inputs,
+            outputs,
+            mut links,
+            output_policy,
+            state: _state,
+        } = snapshot;
+
+        self.nodes.retain(|id, _node| links.contains_key(id));
+        for (id, node) in self.nodes.iter_mut() {
+            *node.inputs_mut() = links.remove(id).unwrap();
+        }
+
+        self.inputs = inputs;
+        self.outputs = outputs;
+        self.output_policy = output_policy;
+        self.reset();
+
+        Ok(self)
+    }
+
+    /// Clear all computed values so that the next [run](Self::run) recomputes every node.
+    ///
+    /// Graph outputs that are not linked to any socket keep their value, as do nodes without
+    /// inputs since they hold constants.
+    pub fn reset(&mut self) {
+        for node in self.nodes.values_mut() {
+            match node {
+                Node::Graph(node) if node.inputs.is_empty() => (),
+                Node::Graph(node) => node
+                    .outputs
+                    .values_mut()
+                    .for_each(|value| *value = SocketType::from(&*value).into()),
+                Node::Imported(node) => node.inner.reset(),
+            }
+        }
+
+        for (socket_ref, value) in self.outputs.values_mut() {
+            if socket_ref.is_some() {
+                *value = SocketType::from(&*value).into();
+            }
+        }
+    }
+
+    /// Rename a graph input, updating the links pointing to it.
+    pub fn rename_input(&mut self, old: &Name, new: Name) -> Result<&mut Self, Error> {
+        if old == &new {
+            return Ok(self);
+        }
+        if self.inputs.contains_key(&new) {
+            return Err(Error::NameTaken(new.to_string()));
+        }
+
+        let value = self
+            .inputs
+            .remove(old)
+            .ok_or_else(|| Error::Missing(Side::Input, old.clone()))?;
+        self.inputs.insert(new.clone(), value);
+
+        self.relink(|socket_ref| match socket_ref {
+            SocketRef::Graph(name) if name == old => *name = new.clone(),
+            _ => (),
+        });
+
+        Ok(self)
+    }
+
+    /// Rename a graph output, keeping its link and value.
+    pub fn rename_output(&mut self, old: &Name, new: Name) -> Result<&mut Self, Error> {
+        if old == &new {
+            return Ok(self);
+        }
+        if self.outputs.contains_key(&new) {
+            return Err(Error::NameTaken(new.to_string()));
+        }
+
+        let output = self
+            .outputs
+            .remove(old)
+            .ok_or_else(|| Error::Missing(Side::Output, old.clone()))?;
+        self.outputs.insert(new, output);
+
+        Ok(self)
+    }
+
+    /// Rename a node, updating the links pointing to its outputs.
+    pub fn rename_node(&mut self, old: &NodeId, new: NodeId) -> Result<&mut Self, Error> {
+        if old == &new {
+            return Ok(self);
+        }
+        if self.nodes.contains_key(&new) {
+            return Err(Error::NameTaken(new.to_string()));
+        }
+
+        let node = self
+            .nodes
+            .remove(old)
+            .ok_or_else(|| Error::MissingNode(old.clone()))?;
+        self.nodes.insert(new.clone(), node);
+
+        self.relink(|socket_ref| match socket_ref {
+            SocketRef::Node(id, _socket) if id == old => *id = new.clone(),
+            _ => (),
+        });
+
+        Ok(self)
+    }
+
+    /// Apply `update` to every link of the graph, i.e. node inputs and graph outputs.
+    fn relink(&mut self, update: impl Fn(&mut SocketRef)) {
+        let node_links = self.nodes.values_mut().flat_map(|node| {
+            node.inputs_mut()
+                .values_mut()
+                .map(|(socket_ref, _type)| socket_ref)
+        });
+        let output_links = self
+            .outputs
+            .values_mut()
+            .map(|(socket_ref, _value)| socket_ref);
+
+        node_links.chain(output_links).flatten().for_each(update);
+    }
+
+    /// Match every node left out of the `mapping` with one of `other`'s, backtracking on
+    /// failure.
+    fn match_remaining_nodes(&self, other: &Self, mapping: NodeMapping) -> bool {
+        let Some(id) = self
+            .nodes
+            .keys()
+            .find(|id| !mapping.forward.contains_key(id))
+        else {
+            return true;
+        };
+
+        other
+            .nodes
+            .keys()
+            .filter(|other_id| !mapping.backward.contains_key(other_id))
+            .any(|other_id| {
+                let mut attempt = mapping.clone();
+                self.nodes_match(other, id, other_id, &mut attempt)
+                    && self.match_remaining_nodes(other, attempt)
+            })
+    }
+
+    /// Check whether two socket references point to matching sockets, see
+    /// [structurally_eq](Self::structurally_eq).
+    fn refs_match(
+        &self,
+        other: &Self,
+        lhs: &Option<SocketRef>,
+        rhs: &Option<SocketRef>,
+        mapping: &mut NodeMapping,
+    ) -> bool {
+        match (lhs, rhs) {
+            (None, None) => true,
+            (Some(SocketRef::Graph(lhs)), Some(SocketRef::Graph(rhs))) => lhs == rhs,
+            (Some(SocketRef::Node(lhs, lhs_socket)), Some(SocketRef::Node(rhs, rhs_socket))) => {
+                lhs_socket == rhs_socket && self.nodes_match(other, lhs, rhs, mapping)
+            }
+            _ => false,
+        }
+    }
+
+    /// Check whether two nodes and their dependencies match, recording them in the `mapping`.
+    fn nodes_match(
+        &self,
+        other: &Self,
+        lhs: &NodeId,
+        rhs: &NodeId,
+        mapping: &mut NodeMapping,
+    ) -> bool {
+        if let Some(mapped) = mapping.forward.get(lhs) {
+            return mapped == rhs;
+        }
+        if mapping.backward.contains_key(rhs) {
+            return false;
+        }
+
+        let (Some(lhs_node), Some(rhs_node)) = (self.nodes.get(lhs), other.nodes.get(rhs)) else {
+            return false;
+        };
+
+        // Mapped beforehand so that cycles in unvalidated graphs terminate
+        mapping.forward.insert(lhs.clone(), rhs.clone());
+        mapping.backward.insert(rhs.clone(), lhs.clone());
+
+        let same_shape = match (lhs_node, rhs_node) {
+            (Node::Graph(_), Node::Graph(_)) => true,
+            (Node::Imported(lhs), Node::Imported(rhs)) => {
+                lhs.name == rhs.name && lhs.inner.structurally_eq(&rhs.inner)
+            }
+            _ => false,
+        };
+        let output_types = |node: &Node<State>| {
+            node.outputs()
+                .into_iter()
+                .map(|(name, value)| (name.clone(), SocketType::from(value)))
+                .collect::<HashMap<_, _>>()
+        };
+
+        same_shape
+            && output_types(lhs_node) == output_types(rhs_node)
+            && lhs_node.inputs().len() == rhs_node.inputs().len()
+            && lhs_node
+                .inputs()
+                .iter()
+                .all(|(name, (socket_ref, socket_type))| {
+                    rhs_node
+                        .inputs()
+                        .get(name)
+                        .is_some_and(|(other_ref, other_type)| {
+                            socket_type == other_type
+                                && self.refs_match(other, socket_ref, other_ref, mapping)
+                        })
+                })
+    }
+
+    /// Get the [NodeId]s of all nodes on a path to a graph output.
+    fn reachable_nodes(&self) -> HashSet<NodeId> {
+        let mut reachable = HashSet::new();
+        let mut next = self
+            .outputs
+            .values()
+            .filter_map(|(socket_ref, _value)| match socket_ref {
+                Some(SocketRef::Node(id, _socket)) => Some(id.clone()),
+                _ => None,
+            })
+            .collect::<Vec<NodeId>>();
+
+        while let Some(id) = next.pop() {
+            let Some(node) = self.nodes.get(&id) else {
+                continue;
+            };
+
+            if !reachable.insert(id) {
+                continue;
+            }
+
+            next.extend(node.inputs().values().filter_map(
+                |(socket_ref, _type)| match socket_ref {
+                    Some(SocketRef::Node(id, _socket)) => Some(id.clone()),
+                    _ => None,
+                },
+            ));
+        }
+
+        reachable
+    }
 }
 
 impl Graph<Unvalidated> {
     /// Check the [unvalidated](Unvalidated) [Graph] for cycles.
     pub fn validate(self) -> Result<Graph<Validated>, Error> {
         let mut path: Vec<NodeId> = Vec::new();
-        let mut visited: Vec<NodeId> = Vec::new();
-        let mut next: VecDeque<NodeId> = VecDeque::new();
+        let mut done: HashSet<NodeId> = HashSet::new();
 
         // Graph outputs
         for (output, (socket_ref, value)) in self.outputs.iter() {
@@ -426,64 +995,21 @@ impl Graph<Unvalidated> {
             // Check that it is connected to a node.
             let SocketRef::Node(node_id, _socket) = socket_ref else {continue};
 
-            // Ignore nodes connected to previously-handled graph outputs.
-            if visited.contains(node_id) {
-                continue;
-            }
-
-            next.push_back(node_id.clone());
-
-            // Loop through nodes recursively (using the push_front trick).
-            while let Some(current_node_id) = next.pop_front() {
-                // Check that the current node exists.
-                let Some(node) = self.nodes.get(&current_node_id) else {continue};
-
-                visited.push(current_node_id.clone());
-                path.push(current_node_id.clone());
-
-                // Used to check if the recursion should end.
-                let mut pushed_some = false;
-
-                // Node inputs
-                for (input, (socket_ref, _value)) in node.inputs() {
-                    let Some(socket_ref) = socket_ref else {continue};
-                    let SocketRef::Node(node_id, socket) = socket_ref else {continue};
-
-                    // Check for cycles, i.e. if the node was already encountered in the path.
-                    if path.contains(node_id) {
-                        return Err(Error::Cycle {
-                            detected: node_id.clone(),
-                            target_socket: socket.clone(),
-                            source_socket: input.clone(),
-                            during: path,
-                        });
-                    }
-
-                    // Ignore nodes visited from DFS starting from other graph outputs.
-                    if visited.contains(node_id) {
-                        continue;
-                    }
-
-                    next.push_front(node_id.clone());
-                    pushed_some = true;
-                }
-
-                if !pushed_some {
-                    path.pop();
-                }
-            }
+            self.check_cycles(node_id, &mut path, &mut done)?;
         }
 
         let Self {
             inputs,
             outputs,
             nodes,
+            output_policy,
             state: _state,
         } = self;
 
         Ok(Graph {
             inputs,
             outputs,
+            output_policy,
             nodes: nodes
                 .into_iter()
                 .map(|(k, v)| Ok((k, v.validate()?)))
@@ -491,130 +1017,517 @@ impl Graph<Unvalidated> {
             state: PhantomData::<Validated>,
         })
     }
-}
-
-impl Graph<Validated> {
-    /// Run graph by computing connected shader nodes recursively.
-    /// The final results are contained in the graph's `outputs` hashmap.
-    pub fn run(&mut self) -> Result<(), Error> {
-        // Dirtily cloning the entire outputs hashmap but it works
-        self.outputs = self
-            .outputs
-            .clone()
-            .into_iter()
-            .filter(|(_name, (_ref, value))| value.is_none())
-            .map(|output| {
-                let (name, (socket_ref, mut value)) = output;
 
-                // Unconnected output
-                if socket_ref.is_none() {
-                    value.set_default();
-                    return Ok((name, (socket_ref, value)));
-                }
+    /// Link the `to_input` input of the `to_node` node to `from`, either a graph input or another
+    /// node's output, replacing any previous link.
+    ///
+    /// # Errors
+    /// [Missing](Error::Missing) if `from` is not an existing graph input or node output, or if
+    /// the target node does not exist or has no `to_input` input.
+    pub fn connect(
+        &mut self,
+        from: SocketRef,
+        to_node: &NodeId,
+        to_input: &Name,
+    ) -> Result<&mut Self, Error> {
+        match &from {
+            SocketRef::Graph(name) if !self.inputs.contains_key(name) => {
+                return Err(Error::Missing(Side::Input, name.clone()));
+            }
+            SocketRef::Node(id, name)
+                if self
+                    .nodes
+                    .get(id)
+                    .and_then(|node| node.output(name))
+                    .is_none() =>
+            {
+                return Err(Error::Missing(Side::Output, name.clone()));
+            }
+            _ => (),
+        }
 
-                // Get rid of Option
-                let socket_ref = socket_ref.unwrap();
-                match &socket_ref {
-                    SocketRef::Node(node_id, name) => {
-                        // Recurse into node to run it
-                        self.run_node(node_id)?;
-                        // Get output value of node connected to graph output
-                        value = (*self
-                            .nodes
-                            .get(node_id)
-                            .unwrap()
-                            .outputs()
-                            .get(&name)
-                            .unwrap_or_else(|| panic!("Output `{}` not found for node `{}`.", name.0, node_id.0)))
-                        .clone();
-                    }
-                    SocketRef::Graph(name) => value = self.inputs.get(name).unwrap().clone(),
-                };
+        self.nodes
+            .get_mut(to_node)
+            .ok_or_else(|| Error::Missing(Side::Input, to_input.clone()))?
+            .set_input(to_input, Some(from))?;
 
-                Ok((name, (Some(socket_ref), value)))
-            })
-            .collect::<Result<_, Error>>()?;
+        Ok(self)
+    }
 
-        Ok(())
+    /// Unlink the `input` input of the `node` node.
+    ///
+    /// # Errors
+    /// Same as [connect](Self::connect) for the target node and input.
+    pub fn disconnect(&mut self, node: &NodeId, input: &Name) -> Result<&mut Self, Error> {
+        self.nodes
+            .get_mut(node)
+            .ok_or_else(|| Error::Missing(Side::Input, input.clone()))?
+            .set_input(input, None)?;
+
+        Ok(self)
     }
 
-    /// Run node by computing its inputs recursively, then computing the contained shader
-    fn run_node(&mut self, node_id: &NodeId) -> Result<(), Error> {
-        // Skip node if outputs are already computed.
-        if self
-            .nodes
-            .get(node_id)
-            .unwrap()
-            .outputs()
-            .iter()
-            .all(|(&_k, &v)| !v.is_none())
-        {
+    /// Depth-first search through the node's dependencies, `path` holding the nodes currently
+    /// being explored and `done` the ones whose dependencies are known to be acyclic.
+    fn check_cycles(
+        &self,
+        node_id: &NodeId,
+        path: &mut Vec<NodeId>,
+        done: &mut HashSet<NodeId>,
+    ) -> Result<(), Error> {
+        if done.contains(node_id) {
             return Ok(());
         }
 
-        let cur = self.nodes.get(node_id).unwrap().clone();
-
-        match cur {
-            Node::Graph(cur_inner) => {
-                let mut inputs = HashMap::new();
-
-                for (name, (socket_ref, r#type)) in cur_inner.inputs.into_iter() {
-                    if let Some(socket_ref) = socket_ref {
-                        inputs.insert(
-                            name,
-                            match socket_ref {
-                                SocketRef::Node(id, field) => {
-                                    self.run_node(&id)?;
-                                    (*self.nodes.get(&id).unwrap().outputs().get(&field).unwrap())
-                                        .clone()
-                                }
-                                SocketRef::Graph(field) => self.inputs.get(&field).unwrap().clone(),
-                            },
-                        );
-                    } else {
-                        inputs.insert(name, r#type.into());
-                    }
-                }
+        // Check that the current node exists.
+        let Some(node) = self.nodes.get(node_id) else {
+            return Ok(());
+        };
 
-                let Some(Node::Graph(node)) = self.nodes.get_mut(node_id) else {unreachable!()};
-                node.shader.call(&inputs, &mut node.outputs)?;
-            }
-            Node::Imported(cur_inner) => {
-                for (name, (socket_ref, r#_type)) in cur_inner.inputs.into_iter() {
-                    if let Some(socket_ref) = socket_ref {
-                        let value = match socket_ref.clone() {
-                            SocketRef::Node(id, field) => {
-                                self.run_node(&id)?;
-                                (*self.nodes.get(&id).unwrap().outputs().get(&field).unwrap())
-                                    .clone()
-                            }
-                            SocketRef::Graph(field) => self.inputs.get(&field).unwrap().clone(),
-                        };
-
-                        let Some(Node::Imported(node)) = self.nodes.get_mut(node_id) else {unreachable!()};
-                        node.inner.inputs.insert(name, value);
-                    } else {
-                        let Some(Node::Imported(node)) = self.nodes.get_mut(node_id) else {unreachable!()};
-                        node.inner.inputs.get_mut(&name).unwrap().set_default();
-                    }
-                }
+        path.push(node_id.clone());
 
-                let Some(Node::Imported(node)) = self.nodes.get_mut(node_id) else {unreachable!()};
-                node.inner.run()?;
+        // Node inputs
+        for (input, (socket_ref, _value)) in node.inputs() {
+            let Some(SocketRef::Node(dependency, socket)) = socket_ref else {
+                continue;
+            };
+
+            // Check for cycles, i.e. if the node was already encountered in the path.
+            if let Some(start) = path.iter().position(|id| id == dependency) {
+                return Err(Error::Cycle {
+                    detected: dependency.clone(),
+                    target_socket: socket.clone(),
+                    source_socket: input.clone(),
+                    cycle: path.split_off(start),
+                });
             }
+
+            self.check_cycles(dependency, path, done)?;
         }
 
+        path.pop();
+        done.insert(node_id.clone());
+
         Ok(())
     }
 }
 
-#[derive(Clone, Default)]
-/// A "raw" node with its own [Shader].
-pub struct GraphNode {
-    /// Node inputs.
-    pub inputs: HashMap<Name, (Option<SocketRef>, SocketType)>,
-    /// Node outputs.
-    pub outputs: HashMap<Name, SocketValue>,
+impl Graph<Validated> {
+    /// Run graph by computing connected shader nodes in topological order, i.e. dependencies
+    /// first. The final results are contained in the graph's `outputs` hashmap.
+    ///
+    /// Node output values read by a single node input or graph output are moved there rather
+    /// than copied, leaving the node to be recomputed if it is ever needed again.
+    pub fn run(&mut self) -> Result<(), Error> {
+        let pending = self.pending_nodes();
+        let consumers = self.consumers(&pending);
+
+        for id in self.topological_order() {
+            if pending.contains(&id) {
+                self.run_node(&id, &consumers)?;
+            }
+        }
+
+        // Only names are cloned, values are moved in place
+        let names = self.outputs.keys().cloned().collect::<Vec<Name>>();
+
+        for name in names {
+            let (socket_ref, value) = &self.outputs[&name];
+
+            // Preset or already computed output
+            if !value.is_none() {
+                continue;
+            }
+
+            let Some(socket_ref) = socket_ref.clone() else {
+                // Unconnected output
+                self.outputs.get_mut(&name).unwrap().1.set_default();
+                continue;
+            };
+
+            // Get value of the socket connected to graph output
+            let value = self.consume(&socket_ref, &consumers);
+            self.outputs.get_mut(&name).unwrap().1 = value;
+        }
+
+        // Linked outputs whose upstream socket produced nothing
+        let mut unset = Vec::new();
+        for (name, (_socket_ref, value)) in self
+            .outputs
+            .iter_mut()
+            .filter(|(_, (socket_ref, _))| socket_ref.is_some())
+        {
+            match self.output_policy {
+                OutputPolicy::Default if value.is_none() => value.set_default(),
+                OutputPolicy::Error if value.is_none() => unset.push(name.clone()),
+                _ => (),
+            }
+        }
+
+        if !unset.is_empty() {
+            unset.sort_by_key(|name| name.to_string());
+            return Err(Error::UnsetGraphOutputs(unset));
+        }
+
+        Ok(())
+    }
+
+    /// Same as [run](Self::run), but stops evaluating nodes in topological order once `budget` is
+    /// exhausted. Outputs whose nodes were not evaluated keep their previous value.
+    ///
+    /// Returns whether every node could be evaluated, calling it again resumes where it stopped.
+    pub fn run_budgeted(&mut self, budget: Duration) -> Result<bool, Error> {
+        let start = Instant::now();
+        let mut complete = true;
+
+        for id in self.topological_order() {
+            if start.elapsed() >= budget {
+                complete = false;
+                break;
+            }
+
+            // Values are copied so that stopping does not lose any computed node
+            self.run_node(&id, &HashMap::new())?;
+        }
+
+        let computed = self
+            .outputs
+            .iter()
+            .filter_map(|(name, (socket_ref, _value))| {
+                let value = self.resolve(socket_ref.as_ref()?)?;
+                (!value.is_none()).then(|| (name.clone(), value.clone()))
+            })
+            .collect::<Vec<(Name, SocketValue)>>();
+
+        for (name, value) in computed {
+            self.outputs.get_mut(&name).unwrap().1 = value;
+        }
+
+        // Unconnected outputs
+        for (_socket_ref, value) in self
+            .outputs
+            .values_mut()
+            .filter(|(socket_ref, value)| socket_ref.is_none() && value.is_none())
+        {
+            value.set_default();
+        }
+
+        Ok(complete)
+    }
+
+    /// Get the [NodeId]s of all nodes on a path to a graph output, dependencies first.
+    ///
+    /// Kahn's algorithm, which orders every node since [validation](Graph::validate) already
+    /// ruled out cycles.
+    fn topological_order(&self) -> Vec<NodeId> {
+        let reachable = self.reachable_nodes();
+
+        // Number of links to dependencies not ordered yet, and reverse links
+        let mut pending = HashMap::<&NodeId, usize>::new();
+        let mut dependents = HashMap::<&NodeId, Vec<&NodeId>>::new();
+        for id in reachable.iter() {
+            let dependencies = self.nodes[id]
+                .inputs()
+                .values()
+                .filter_map(|(socket_ref, _type)| match socket_ref {
+                    Some(SocketRef::Node(dependency, _socket)) => reachable.get(dependency),
+                    _ => None,
+                })
+                .collect::<Vec<&NodeId>>();
+
+            pending.insert(id, dependencies.len());
+            for dependency in dependencies {
+                dependents.entry(dependency).or_default().push(id);
+            }
+        }
+
+        let mut ready = pending
+            .iter()
+            .filter(|(_id, &count)| count == 0)
+            .map(|(&id, _count)| id)
+            .collect::<VecDeque<&NodeId>>();
+        let mut order = Vec::with_capacity(reachable.len());
+
+        while let Some(id) = ready.pop_front() {
+            for &dependent in dependents.get(id).into_iter().flatten() {
+                let count = pending.get_mut(dependent).unwrap();
+                *count -= 1;
+                if *count == 0 {
+                    ready.push_back(dependent);
+                }
+            }
+
+            order.push(id.clone());
+        }
+
+        debug_assert_eq!(order.len(), reachable.len(), "Validated graphs are acyclic");
+
+        order
+    }
+
+    /// Whether every output of the node already holds a value.
+    fn is_computed(&self, node_id: &NodeId) -> bool {
+        self.nodes
+            .get(node_id)
+            .unwrap()
+            .outputs()
+            .iter()
+            .all(|(&_k, &v)| !v.is_none())
+    }
+
+    /// Nodes left to compute for the linked graph outputs without a value, i.e. those they
+    /// depend on through nodes whose outputs are not all computed.
+    fn pending_nodes(&self) -> HashSet<NodeId> {
+        let mut pending = HashSet::new();
+        let mut stack = self
+            .outputs
+            .values()
+            .filter(|(_socket_ref, value)| value.is_none())
+            .filter_map(|(socket_ref, _value)| socket_ref.clone())
+            .collect::<Vec<SocketRef>>();
+
+        while let Some(socket_ref) = stack.pop() {
+            let SocketRef::Node(id, _name) = socket_ref else {
+                continue;
+            };
+            if self.is_computed(&id) || !pending.insert(id.clone()) {
+                continue;
+            }
+
+            stack.extend(
+                self.nodes[&id]
+                    .inputs()
+                    .values()
+                    .filter_map(|(socket_ref, _type)| socket_ref.clone()),
+            );
+        }
+
+        pending
+    }
+
+    /// Count how many times each socket is about to be read, by the inputs of the `pending`
+    /// nodes and by the linked graph outputs without a value.
+    fn consumers(&self, pending: &HashSet<NodeId>) -> HashMap<SocketRef, usize> {
+        let inputs = pending
+            .iter()
+            .flat_map(|id| self.nodes[id].inputs().values())
+            .filter_map(|(socket_ref, _type)| socket_ref.as_ref());
+        let outputs = self
+            .outputs
+            .values()
+            .filter(|(_socket_ref, value)| value.is_none())
+            .filter_map(|(socket_ref, _value)| socket_ref.as_ref());
+
+        let mut consumers = HashMap::new();
+        for socket_ref in inputs.chain(outputs) {
+            *consumers.entry(socket_ref.clone()).or_default() += 1;
+        }
+
+        consumers
+    }
+
+    /// Get the value of a socket for one of its `consumers`.
+    ///
+    /// Outputs of nodes with inputs are moved out when read only once, as the node can be
+    /// recomputed. Graph inputs and the outputs of other nodes, which hold constants, are copied.
+    fn consume(
+        &mut self,
+        socket_ref: &SocketRef,
+        consumers: &HashMap<SocketRef, usize>,
+    ) -> SocketValue {
+        if let SocketRef::Node(id, name) = socket_ref {
+            if consumers.get(socket_ref) == Some(&1) {
+                if let Some(Node::Graph(node)) = self.nodes.get_mut(id) {
+                    if let Some(value) = node.outputs.get_mut(name) {
+                        if !node.inputs.is_empty() {
+                            let empty = SocketType::from(&*value).into();
+                            return std::mem::replace(value, empty);
+                        }
+                    }
+                }
+            }
+        }
+
+        self.resolve(socket_ref)
+            .unwrap_or_else(|| panic!("Socket {socket_ref:?} not found."))
+            .clone()
+    }
+
+    /// Run node on the values of its inputs, whose nodes must have already been run.
+    fn run_node(
+        &mut self,
+        node_id: &NodeId,
+        consumers: &HashMap<SocketRef, usize>,
+    ) -> Result<(), Error> {
+        // Skip node if outputs are already computed.
+        if self.is_computed(node_id) {
+            return Ok(());
+        }
+
+        let inputs = self.input_values(node_id, consumers);
+        Self::evaluate(node_id, self.nodes.get_mut(node_id).unwrap(), inputs)
+    }
+
+    /// Current values of the node's inputs, [None] standing for unlinked ones, see
+    /// [consume](Self::consume).
+    fn input_values(
+        &mut self,
+        node_id: &NodeId,
+        consumers: &HashMap<SocketRef, usize>,
+    ) -> HashMap<Name, Option<SocketValue>> {
+        let links = self.nodes[node_id]
+            .inputs()
+            .iter()
+            .map(|(name, (socket_ref, _type))| (name.clone(), socket_ref.clone()))
+            .collect::<Vec<_>>();
+
+        links
+            .into_iter()
+            .map(|(name, socket_ref)| {
+                let value = socket_ref.map(|socket_ref| self.consume(&socket_ref, consumers));
+                (name, value)
+            })
+            .collect()
+    }
+
+    /// Run a node's shader or inner graph on already computed `inputs`, [None] standing for
+    /// unlinked ones.
+    fn evaluate(
+        node_id: &NodeId,
+        node: &mut Node<Validated>,
+        inputs: HashMap<Name, Option<SocketValue>>,
+    ) -> Result<(), Error> {
+        match node {
+            Node::Graph(node) => {
+                let inputs = inputs
+                    .into_iter()
+                    .map(|(name, value)| {
+                        let value = value.unwrap_or_else(|| node.inputs[&name].1.into());
+                        (name, value)
+                    })
+                    .collect();
+
+                node.shader.call(&inputs, &mut node.outputs)?;
+
+                // Catch forgotten outputs here rather than when they are later resolved
+                let mut unset = node
+                    .outputs
+                    .iter()
+                    .filter(|(_name, value)| value.is_none())
+                    .map(|(name, _value)| name.clone())
+                    .collect::<Vec<Name>>();
+
+                if !unset.is_empty() {
+                    unset.sort_by_key(|name| name.to_string());
+                    return Err(Error::UnsetOutputs {
+                        node: node_id.clone(),
+                        outputs: unset,
+                    });
+                }
+            }
+            Node::Imported(node) => {
+                for (name, value) in inputs.into_iter() {
+                    match value {
+                        Some(value) => {
+                            node.inner.inputs.insert(name, value);
+                        }
+                        None => node.inner.inputs.get_mut(&name).unwrap().set_default(),
+                    }
+                }
+
+                node.inner.run()?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl Graph<Validated> {
+    /// Same as [run](Self::run), but evaluates nodes level by level, running the nodes of a level
+    /// concurrently since none of them depends on another.
+    pub fn run_parallel(&mut self) -> Result<(), Error> {
+        use rayon::prelude::*;
+
+        let pending = self.pending_nodes();
+        let consumers = self.consumers(&pending);
+
+        for level in self.topological_levels() {
+            let mut jobs = Vec::new();
+
+            for id in level {
+                if !pending.contains(&id) || self.is_computed(&id) {
+                    continue;
+                }
+
+                // Every dependency belongs to a previous level and was already computed
+                let inputs = self.input_values(&id, &consumers);
+
+                let node = self.nodes.remove(&id).unwrap();
+                jobs.push((id, node, inputs));
+            }
+
+            let results = jobs
+                .par_iter_mut()
+                .map(|(id, node, inputs)| Self::evaluate(id, node, std::mem::take(inputs)))
+                .collect::<Vec<Result<(), Error>>>();
+
+            for (id, node, _inputs) in jobs {
+                self.nodes.insert(id, node);
+            }
+
+            results.into_iter().collect::<Result<(), Error>>()?;
+        }
+
+        // Only gathers the outputs now that every node is computed
+        self.run()
+    }
+
+    /// Group the nodes of [topological_order](Self::topological_order) by dependency depth, each
+    /// level only depending on the previous ones.
+    fn topological_levels(&self) -> Vec<Vec<NodeId>> {
+        let mut depths = HashMap::<NodeId, usize>::new();
+        let mut levels = Vec::<Vec<NodeId>>::new();
+
+        for id in self.topological_order() {
+            let depth = self.nodes[&id]
+                .inputs()
+                .values()
+                .filter_map(|(socket_ref, _type)| match socket_ref {
+                    Some(SocketRef::Node(dependency, _socket)) => {
+                        depths.get(dependency).map(|depth| depth + 1)
+                    }
+                    _ => None,
+                })
+                .max()
+                .unwrap_or(0);
+
+            depths.insert(id.clone(), depth);
+            if levels.len() <= depth {
+                levels.resize_with(depth + 1, Vec::new);
+            }
+            levels[depth].push(id);
+        }
+
+        levels
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+/// Bijection between two graphs' [NodeId]s, see [Graph::structurally_eq].
+struct NodeMapping {
+    forward: HashMap<NodeId, NodeId>,
+    backward: HashMap<NodeId, NodeId>,
+}
+
+#[derive(Clone, Default)]
+/// A "raw" node with its own [Shader].
+pub struct GraphNode {
+    /// Node inputs.
+    pub inputs: HashMap<Name, (Option<SocketRef>, SocketType)>,
+    /// Node outputs.
+    pub outputs: HashMap<Name, SocketValue>,
 
     /// Function to be run, taking the inputs and modifying the output values.
     pub shader: Shader,
@@ -746,14 +1659,41 @@ impl Node<Unvalidated> {
 }
 
 impl<State> Node<State> {
-    fn inputs(&self) -> &HashMap<Name, (Option<SocketRef>, SocketType)> {
+    /// Get the node's inputs, i.e. their optional link and [SocketType].
+    ///
+    /// Use [set_input](Node::set_input) to modify them.
+    /// # Example
+    /// ```
+    /// use eray::{node, ssref, shader::graph::{Node, Unvalidated, SocketType}};
+    ///
+    /// let node: Node<Unvalidated> = node! {
+    ///     inputs:
+    ///         "lhs": (ssref!(graph "a"), SocketType::Value),
+    ///         "rhs": (None, SocketType::Value),
+    ///     outputs:
+    ///         "value": SocketType::Value.into()
+    /// };
+    ///
+    /// let mut names = node.inputs().keys().map(String::from).collect::<Vec<_>>();
+    /// names.sort();
+    /// assert_eq!(names, ["lhs", "rhs"]);
+    /// ```
+    pub fn inputs(&self) -> &HashMap<Name, (Option<SocketRef>, SocketType)> {
         match self {
             Node::Graph(node) => &node.inputs,
             Node::Imported(node) => &node.inputs,
         }
     }
 
-    fn outputs(&self) -> HashMap<&Name, &SocketValue> {
+    fn inputs_mut(&mut self) -> &mut HashMap<Name, (Option<SocketRef>, SocketType)> {
+        match self {
+            Node::Graph(node) => &mut node.inputs,
+            Node::Imported(node) => &mut node.inputs,
+        }
+    }
+
+    /// Get the node's outputs and their current values.
+    pub fn outputs(&self) -> HashMap<&Name, &SocketValue> {
         match self {
             Node::Graph(node) => node.outputs.iter().collect(),
             Node::Imported(node) => node
@@ -765,6 +1705,18 @@ impl<State> Node<State> {
         }
     }
 
+    /// Get the value of a single output.
+    fn output(&self, name: &Name) -> Option<&SocketValue> {
+        match self {
+            Node::Graph(node) => node.outputs.get(name),
+            Node::Imported(node) => node
+                .inner
+                .outputs
+                .get(name)
+                .map(|(_socket_ref, value)| value),
+        }
+    }
+
     /// Get the node's (and by extension the shader's) type signature.
     pub fn signature(&self) -> Signature {
         let input = self
@@ -938,6 +1890,24 @@ mod test {
     use super::*;
     use crate::{graph, node, ssref, get_sv};
 
+    /// Node copying its input value to its output.
+    fn identity() -> Node<Unvalidated> {
+        node! {
+            inputs:
+                "value": (None, SocketType::Value),
+            outputs:
+                "value": SocketType::Value.into();
+            |inputs, outputs| {
+                get_sv!( input | inputs  . "value" : Value > in_value);
+                get_sv!(output | outputs . "value" : Value > out_value);
+
+                *out_value = *in_value;
+
+                Ok(())
+            }
+        }
+    }
+
     fn setup_imports() -> HashMap<String, ImportedNode<Unvalidated>> {
         std::iter::once((
             "identity".to_owned(),
@@ -1023,7 +1993,7 @@ mod test {
                 detected: NodeId("a".to_owned()),
                 target_socket: "value".into(),
                 source_socket: "value".into(),
-                during: vec![NodeId("a".to_owned()), NodeId("b".to_owned())],
+                cycle: vec![NodeId("a".to_owned()), NodeId("b".to_owned())],
             };
 
             assert!(
@@ -1033,6 +2003,85 @@ mod test {
 
             assert_eq!(validation_result.unwrap_err(), expected);
         }
+
+        #[test]
+        fn three_node_cycle() {
+            let imported = setup_imports();
+
+            let validation_result = graph! {
+                inputs,
+                nodes:
+                    "a": node! {
+                        import "identity" from imported,
+                        inputs:
+                            "value": (ssref!(node "b" "value"), SocketType::IValue),
+                    },
+                    "b": node! {
+                        import "identity" from imported,
+                        inputs:
+                            "value": (ssref!(node "c" "value"), SocketType::IValue),
+                    },
+                    "c": node! {
+                        import "identity" from imported,
+                        inputs:
+                            "value": (ssref!(node "a" "value"), SocketType::IValue),
+                    },
+                outputs:
+                    "value": (ssref!(node "a" "value"), SocketType::IValue.into()),
+            }
+            .validate();
+
+            let Err(Error::Cycle {
+                cycle, detected, ..
+            }) = validation_result
+            else {
+                panic!("Expected a cycle, got `{validation_result:?}`")
+            };
+
+            assert_eq!(detected, NodeId::from("a"));
+            assert_eq!(
+                cycle,
+                vec![NodeId::from("a"), NodeId::from("b"), NodeId::from("c")]
+            );
+        }
+
+        #[test]
+        fn shared_dependency() {
+            let imported = setup_imports();
+
+            // Diamond-shaped graph where `b` is reached from both `a` and `c`
+            let validation_result = graph! {
+                inputs:
+                    "value": SocketType::IValue.into(),
+                nodes:
+                    "a": node! {
+                        inputs:
+                            "left": (ssref!(node "b" "value"), SocketType::Value),
+                            "right": (ssref!(node "c" "value"), SocketType::Value),
+                        outputs:
+                            "value": SocketType::Value.into()
+                    },
+                    "b": node! {
+                        import "identity" from imported,
+                        inputs:
+                            "value": (ssref!(graph "value"), SocketType::IValue),
+                    },
+                    "c": node! {
+                        import "identity" from imported,
+                        inputs:
+                            "value": (ssref!(node "b" "value"), SocketType::IValue),
+                    },
+                outputs:
+                    "value": (ssref!(node "a" "value"), SocketType::Value.into()),
+                    "other": (ssref!(node "c" "value"), SocketType::Value.into()),
+            }
+            .validate();
+
+            assert!(
+                validation_result.is_ok(),
+                "Expected a success, got `{validation_result:?}`"
+            );
+        }
     }
 
     #[test]
@@ -1092,6 +2141,7 @@ mod test {
                 ),
             ))
             .collect(),
+            output_policy: OutputPolicy::Default,
             state: PhantomData::<Unvalidated>,
         };
 
@@ -1117,4 +2167,975 @@ mod test {
 
         assert_eq!(manual, r#macro);
     }
+
+    #[test]
+    fn lint() {
+        let graph = graph! {
+            inputs:
+                "used": SocketType::Value.into(),
+                "unused": SocketType::Value.into(),
+                "dangling_only": SocketType::Value.into(),
+            nodes:
+                "identity": node! {
+                    inputs:
+                        "value": (ssref!(graph "used"), SocketType::Value),
+                    outputs:
+                        "value": SocketType::Value.into()
+                },
+                "dangling": node! {
+                    inputs:
+                        "value": (ssref!(graph "dangling_only"), SocketType::Value),
+                    outputs:
+                        "value": SocketType::Value.into()
+                },
+            outputs:
+                "value": (ssref!(node "identity" "value"), SocketType::Value.into()),
+        };
+
+        assert_eq!(
+            graph.lint(),
+            vec![
+                Lint::UnusedInput("dangling_only".into()),
+                Lint::UnusedInput("unused".into()),
+                Lint::DeadNode("dangling".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn prune() {
+        let mut graph = graph! {
+            inputs:
+                "value": SocketValue::Value(Some(2.)),
+            nodes:
+                "double": node! {
+                    inputs:
+                        "value": (ssref!(graph "value"), SocketType::Value),
+                    outputs:
+                        "value": SocketType::Value.into();
+                    |inputs, outputs| {
+                        get_sv!( input | inputs  . "value" : Value > in_value);
+                        get_sv!(output | outputs . "value" : Value > out_value);
+
+                        *out_value.get_or_insert(0.) = in_value.unwrap_or(0.) * 2.;
+
+                        Ok(())
+                    }
+                },
+                "expensive": node! {
+                    inputs:
+                        "value": (ssref!(graph "value"), SocketType::Value),
+                    outputs:
+                        "value": SocketType::IValue.into();
+                    |_inputs, outputs| {
+                        get_sv!(output | outputs . "value" : IValue > out_value);
+                        out_value.replace(Image::new(1024, 1024, 1.));
+                        Ok(())
+                    }
+                },
+            outputs:
+                "value": (ssref!(node "double" "value"), SocketType::Value.into()),
+        }
+        .validate()
+        .unwrap();
+
+        let mut pruned = graph.clone();
+        pruned.prune();
+
+        assert!(!pruned.nodes.contains_key(&"expensive".into()));
+        assert!(pruned.nodes.contains_key(&"double".into()));
+
+        graph.run().unwrap();
+        pruned.run().unwrap();
+
+        assert_eq!(graph.outputs, pruned.outputs);
+    }
+
+    #[test]
+    fn structurally_eq() {
+        let chain = |[first, second, dead]: [&str; 3], swapped: bool| {
+            let (lhs, rhs) = if swapped {
+                (ssref!(graph "b"), ssref!(node first => "value"))
+            } else {
+                (ssref!(node first => "value"), ssref!(graph "b"))
+            };
+
+            let mut graph = graph! {
+                inputs:
+                    "a": SocketValue::Value(Some(1.)),
+                    "b": SocketValue::Value(Some(2.)),
+                nodes,
+                outputs:
+                    "value": (ssref!(node second => "value"), SocketType::Value.into()),
+            };
+
+            graph.nodes.extend([
+                (
+                    first.into(),
+                    node! {
+                        inputs:
+                            "value": (ssref!(graph "a"), SocketType::Value),
+                        outputs:
+                            "value": SocketType::Value.into()
+                    },
+                ),
+                (
+                    second.into(),
+                    node! {
+                        inputs:
+                            "lhs": (lhs, SocketType::Value),
+                            "rhs": (rhs, SocketType::Value),
+                        outputs:
+                            "value": SocketType::Value.into()
+                    },
+                ),
+                (
+                    dead.into(),
+                    node! {
+                        inputs:
+                            "value": (ssref!(node second => "value"), SocketType::Value),
+                        outputs:
+                            "value": SocketType::Color.into()
+                    },
+                ),
+            ]);
+
+            graph
+        };
+
+        let graph = chain(["first", "second", "dead"], false);
+        let renamed = chain(["a", "b", "c"], false);
+
+        assert_ne!(graph, renamed);
+        assert!(graph.structurally_eq(&renamed));
+        assert!(renamed.structurally_eq(&graph));
+
+        assert!(!graph.structurally_eq(&chain(["a", "b", "c"], true)));
+    }
+
+    #[test]
+    fn resolve() {
+        let mut graph = graph! {
+            inputs:
+                "iFac": SocketValue::Value(Some(2.)),
+            nodes:
+                "identity": node! {
+                    inputs:
+                        "value": (ssref!(graph "iFac"), SocketType::Value),
+                    outputs:
+                        "value": SocketType::Value.into();
+                    |inputs, outputs| {
+                        get_sv!( input | inputs  . "value" : Value > in_value);
+                        get_sv!(output | outputs . "value" : Value > out_value);
+
+                        *out_value = *in_value;
+
+                        Ok(())
+                    }
+                },
+            outputs:
+                "oFac": (ssref!(node "identity" "value"), SocketValue::Value(None)),
+        }
+        .validate()
+        .unwrap();
+
+        let (graph_ref, node_ref) = (sref!(graph "iFac"), sref!(node "identity" "value"));
+
+        assert_eq!(
+            graph.resolve(&graph_ref),
+            Some(&SocketValue::Value(Some(2.)))
+        );
+        assert_eq!(graph.resolve(&node_ref), Some(&SocketValue::Value(None)));
+        assert_eq!(graph.resolve(&sref!(node "identity" "missing")), None);
+
+        graph.run().unwrap();
+
+        // The graph output being the only reader, the value was moved there
+        assert_eq!(
+            graph.outputs[&"oFac".into()].1,
+            SocketValue::Value(Some(2.))
+        );
+        assert_eq!(graph.resolve(&node_ref), Some(&SocketValue::Value(None)));
+    }
+
+    #[test]
+    fn content_hash() {
+        let value = SocketValue::Value(Some(1.5));
+        assert_eq!(value.content_hash(), value.clone().content_hash());
+        assert_ne!(
+            value.content_hash(),
+            SocketValue::Value(Some(2.)).content_hash()
+        );
+        assert_ne!(
+            value.content_hash(),
+            SocketValue::Value(None).content_hash()
+        );
+
+        let image = Image::new(4, 4, Color::new(0.2, 0.4, 0.6));
+        let mut changed = image.clone();
+        changed.set(1, 2, Color::new(1., 0., 0.));
+
+        let (image, changed) = (
+            SocketValue::IColor(Some(image)),
+            SocketValue::IColor(Some(changed)),
+        );
+
+        assert_eq!(image.content_hash(), image.clone().content_hash());
+        assert_ne!(image.content_hash(), changed.content_hash());
+    }
+
+    #[test]
+    fn matrix_conversions() {
+        let translation = Vector::new(1., 2., 3.);
+        let matrix = SocketValue::Vec3(Some(translation))
+            .try_convert(SocketType::Matrix)
+            .unwrap();
+        assert_eq!(matrix, SocketValue::Matrix(Some(Mat4::from(translation))));
+        assert_eq!(
+            matrix.try_convert(SocketType::Vec3),
+            Ok(SocketValue::Vec3(Some(translation)))
+        );
+
+        let image = SocketValue::IMatrix(Some(Image::new(2, 2, Mat4::from(translation))));
+        assert_eq!(
+            image.try_convert(SocketType::IVec3),
+            Ok(SocketValue::IVec3(Some(Image::new(2, 2, translation))))
+        );
+
+        let mut unset = SocketValue::Matrix(None);
+        assert_eq!(
+            unset.or_default(),
+            &SocketValue::Matrix(Some(Mat4::identity()))
+        );
+        assert!(SocketValue::Matrix(None)
+            .try_convert(SocketType::Color)
+            .is_err());
+    }
+
+    #[test]
+    fn bool_conversions() {
+        for (value, expected) in [(0., false), (-0., false), (1., true), (-0.5, true)] {
+            assert_eq!(
+                SocketValue::Value(Some(value)).try_convert(SocketType::Bool),
+                Ok(SocketValue::Bool(Some(expected)))
+            );
+        }
+        assert_eq!(
+            SocketValue::Bool(Some(true)).try_convert(SocketType::Value),
+            Ok(SocketValue::Value(Some(1.)))
+        );
+
+        let mask = Image {
+            width: 2,
+            height: 1,
+            pixels: vec![0., 2.],
+        };
+        assert_eq!(
+            SocketValue::IValue(Some(mask)).try_convert(SocketType::IBool),
+            Ok(SocketValue::IBool(Some(Image {
+                width: 2,
+                height: 1,
+                pixels: vec![false, true],
+            })))
+        );
+    }
+
+    #[test]
+    fn try_convert_images() {
+        // Image counterpart of a scalar value or type
+        let image = |value: SocketValue| match value {
+            SocketValue::Value(v) => SocketValue::IValue(v.map(|v| Image::new(2, 1, v))),
+            SocketValue::Vec2(v) => SocketValue::IVec2(v.map(|v| Image::new(2, 1, v))),
+            SocketValue::Vec3(v) => SocketValue::IVec3(v.map(|v| Image::new(2, 1, v))),
+            SocketValue::Color(v) => SocketValue::IColor(v.map(|v| Image::new(2, 1, v))),
+            other => panic!("Expected a scalar, got {other:?}"),
+        };
+        let image_type = |ty: SocketType| SocketType::from(image(ty.into()));
+
+        let scalars = [
+            SocketValue::Value(Some(0.5)),
+            SocketValue::Vec2(Some(Vector::from([0.25, 0.75]))),
+            SocketValue::Vec3(Some(Vector::new(0.1, 0.2, 0.3))),
+            SocketValue::Color(Some(Color::new(0.4, 0.5, 0.6))),
+        ];
+
+        let mut supported = 0;
+        for scalar in scalars.iter() {
+            for target in scalars.iter().map(SocketType::from) {
+                let converted = scalar.clone().try_convert(target);
+                let converted_image = image(scalar.clone()).try_convert(image_type(target));
+
+                assert_eq!(
+                    converted_image,
+                    converted.clone().map(image),
+                    "{scalar:?} to an image of {target:?}"
+                );
+
+                // Unset images stay unset
+                let unset = SocketValue::from(image_type(SocketType::from(scalar)))
+                    .try_convert(image_type(target));
+                assert_eq!(unset.is_ok(), converted.is_ok());
+                assert!(unset.map_or(true, |unset| unset.is_none()));
+
+                supported += converted.is_ok() as usize;
+            }
+        }
+        assert_eq!(supported, 8);
+
+        let converted = SocketValue::IVec3(Some(Image::new(1, 1, Vector::new(1., 0.5, 0.))))
+            .try_convert(SocketType::IColor);
+        assert_eq!(
+            converted,
+            Ok(SocketValue::IColor(Some(Image::new(
+                1,
+                1,
+                Color::new(1., 0.5, 0.)
+            ))))
+        );
+    }
+
+    #[test]
+    fn typed_image_accessors() {
+        let image = Image::new(2, 2, Color::new(0.2, 0.4, 0.6));
+        let value = SocketValue::IColor(Some(image.clone()));
+
+        assert_eq!(value.as_color_image(), Ok(&image));
+        assert_eq!(
+            value.as_value_image(),
+            Err(ShaderError::InvalidType {
+                name: Name::default(),
+                got: SocketType::IColor,
+                expected: SocketType::IValue,
+            })
+        );
+        assert!(matches!(
+            SocketValue::Vec3(Some(Vector::default())).as_vec3_image(),
+            Err(ShaderError::InvalidType {
+                got: SocketType::Vec3,
+                ..
+            })
+        ));
+        assert!(matches!(
+            SocketValue::IVec2(None).as_vec2_image(),
+            Err(ShaderError::Missing(Side::Input, _))
+        ));
+    }
+
+    #[test]
+    fn run_budgeted() {
+        let increment = || {
+            node! {
+                inputs:
+                    "value": (None, SocketType::Value),
+                outputs:
+                    "value": SocketType::Value.into();
+                |inputs, outputs| {
+                    get_sv!( input | inputs  . "value" : Value > in_value);
+                    get_sv!(output | outputs . "value" : Value > out_value);
+
+                    *out_value = Some(in_value.unwrap_or(0.) + 1.);
+
+                    Ok(())
+                }
+            }
+        };
+
+        let mut graph = graph! {
+            inputs:
+                "value": SocketValue::Value(Some(1.)),
+            nodes:
+                "first": increment(),
+                "second": increment(),
+            outputs:
+                "value": (ssref!(node "second" "value"), SocketValue::Value(None)),
+        };
+        graph
+            .nodes
+            .get_mut(&"first".into())
+            .unwrap()
+            .set_input(&"value".into(), ssref!(graph "value"))
+            .unwrap();
+        graph
+            .nodes
+            .get_mut(&"second".into())
+            .unwrap()
+            .set_input(&"value".into(), ssref!(node "first" "value"))
+            .unwrap();
+        let mut graph = graph.validate().unwrap();
+
+        // An exhausted budget does not let any node run
+        assert!(!graph.run_budgeted(Duration::ZERO).unwrap());
+        assert_eq!(
+            graph.resolve(&sref!(node "first" "value")),
+            Some(&SocketValue::Value(None))
+        );
+        assert_eq!(graph.outputs[&"value".into()].1, SocketValue::Value(None));
+
+        assert!(graph.run_budgeted(Duration::MAX).unwrap());
+        assert_eq!(
+            graph.outputs[&"value".into()].1,
+            SocketValue::Value(Some(3.))
+        );
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn run_parallel() {
+        use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+        // Branches wait for each other when enabled, only meeting if they run at the same time
+        static RENDEZVOUS: AtomicBool = AtomicBool::new(false);
+        static ARRIVED: AtomicUsize = AtomicUsize::new(0);
+        static MET: AtomicUsize = AtomicUsize::new(0);
+
+        let branch = || {
+            node! {
+                inputs:
+                    "value": (ssref!(graph "value"), SocketType::Value),
+                outputs:
+                    "value": SocketType::Value.into();
+                |inputs, outputs| {
+                    get_sv!( input | inputs  . "value" : Value > in_value);
+                    get_sv!(output | outputs . "value" : Value > out_value);
+
+                    if RENDEZVOUS.load(Ordering::SeqCst) {
+                        ARRIVED.fetch_add(1, Ordering::SeqCst);
+
+                        // Only bounded so that a regression fails instead of hanging
+                        let deadline = Instant::now() + Duration::from_secs(10);
+                        while ARRIVED.load(Ordering::SeqCst) < 2 && Instant::now() < deadline {
+                            std::thread::yield_now();
+                        }
+
+                        if ARRIVED.load(Ordering::SeqCst) == 2 {
+                            MET.fetch_add(1, Ordering::SeqCst);
+                        }
+                    }
+
+                    *out_value = Some(in_value.unwrap_or(0.) + 1.);
+
+                    Ok(())
+                }
+            }
+        };
+
+        let mut graph = graph! {
+            inputs:
+                "value": SocketValue::Value(Some(1.)),
+            nodes:
+                "left": branch(),
+                "right": branch(),
+                "sum": node! {
+                    inputs:
+                        "lhs": (ssref!(node "left" "value"), SocketType::Value),
+                        "rhs": (ssref!(node "right" "value"), SocketType::Value),
+                    outputs:
+                        "value": SocketType::Value.into();
+                    |inputs, outputs| {
+                        get_sv!( input | inputs  . "lhs" : Value > lhs);
+                        get_sv!( input | inputs  . "rhs" : Value > rhs);
+                        get_sv!(output | outputs . "value" : Value > out_value);
+
+                        *out_value = Some(lhs.unwrap_or(0.) + rhs.unwrap_or(0.));
+
+                        Ok(())
+                    }
+                },
+            outputs:
+                "value": (ssref!(node "sum" "value"), SocketValue::Value(None)),
+        }
+        .validate()
+        .unwrap();
+
+        let mut serial = graph.clone();
+        serial.run().unwrap();
+
+        // Both branches run at the same time given more than one worker thread
+        let concurrent = rayon::current_num_threads() > 1;
+        RENDEZVOUS.store(concurrent, Ordering::SeqCst);
+        graph.run_parallel().unwrap();
+
+        assert_eq!(
+            graph.outputs[&"value".into()].1,
+            SocketValue::Value(Some(4.))
+        );
+        assert_eq!(serial.outputs, graph.outputs);
+
+        if concurrent {
+            assert_eq!(
+                2,
+                MET.load(Ordering::SeqCst),
+                "Expected both branches to meet"
+            );
+        }
+    }
+
+    #[test]
+    fn snapshot_restore() {
+        let mut graph = graph! {
+            inputs:
+                "value": SocketValue::Value(Some(1.)),
+            nodes:
+                "first": identity(),
+                "second": identity(),
+            outputs:
+                "value": (ssref!(node "second" "value"), SocketValue::Value(None)),
+        };
+        graph
+            .nodes
+            .get_mut(&"second".into())
+            .unwrap()
+            .set_input(&"value".into(), ssref!(node "first" "value"))
+            .unwrap();
+
+        let original = graph.clone();
+        let snapshot = graph.snapshot();
+
+        // Rewire, add a node and change values
+        graph
+            .nodes
+            .get_mut(&"second".into())
+            .unwrap()
+            .set_input(&"value".into(), ssref!(graph "value"))
+            .unwrap();
+        graph.nodes.insert("third".into(), identity());
+        graph.outputs.get_mut(&"value".into()).unwrap().0 = ssref!(node "third" "value");
+        graph
+            .inputs
+            .insert("value".into(), SocketValue::Value(Some(2.)));
+        assert_ne!(original, graph);
+
+        graph.restore(snapshot.clone()).unwrap();
+        assert_eq!(original, graph);
+
+        // Values computed before restoring are not reused
+        let mut validated = original.clone();
+        validated
+            .nodes
+            .get_mut(&"first".into())
+            .unwrap()
+            .set_input(&"value".into(), ssref!(graph "value"))
+            .unwrap();
+        let mut validated = validated.validate().unwrap();
+        let validated_snapshot = validated.snapshot();
+        validated
+            .inputs
+            .insert("value".into(), SocketValue::Value(Some(2.)));
+        validated.run().unwrap();
+        assert_eq!(
+            validated.outputs[&"value".into()].1,
+            SocketValue::Value(Some(2.))
+        );
+
+        validated.restore(validated_snapshot).unwrap();
+        validated.run().unwrap();
+        assert_eq!(
+            validated.outputs[&"value".into()].1,
+            SocketValue::Value(Some(1.))
+        );
+
+        // Removed nodes cannot be brought back
+        graph.nodes.remove(&"first".into());
+        assert_eq!(
+            graph.restore(snapshot).unwrap_err(),
+            Error::MissingNode("first".into())
+        );
+        assert_eq!(1, graph.nodes.len());
+    }
+
+    #[test]
+    fn rename() {
+        let mut graph = graph! {
+            inputs:
+                "value": SocketValue::Value(Some(1.)),
+            nodes:
+                "first": identity(),
+                "second": identity(),
+            outputs:
+                "value": (ssref!(node "first" "value"), SocketValue::Value(None)),
+                "passthrough": (ssref!(graph "value"), SocketValue::Value(None)),
+        };
+        graph
+            .nodes
+            .get_mut(&"first".into())
+            .unwrap()
+            .set_input(&"value".into(), ssref!(graph "value"))
+            .unwrap();
+        graph
+            .nodes
+            .get_mut(&"second".into())
+            .unwrap()
+            .set_input(&"value".into(), ssref!(node "first" "value"))
+            .unwrap();
+
+        graph
+            .rename_node(&"first".into(), "source".into())
+            .unwrap()
+            .rename_input(&"value".into(), "input".into())
+            .unwrap()
+            .rename_output(&"value".into(), "output".into())
+            .unwrap();
+
+        let input = |graph: &Graph<Unvalidated>, id: &str| {
+            graph.nodes[&id.into()].inputs()[&"value".into()].0.clone()
+        };
+        assert_eq!(input(&graph, "second"), ssref!(node "source" "value"));
+        assert_eq!(input(&graph, "source"), ssref!(graph "input"));
+        assert_eq!(
+            graph.outputs[&"output".into()].0,
+            ssref!(node "source" "value")
+        );
+        assert_eq!(
+            graph.outputs[&"passthrough".into()].0,
+            ssref!(graph "input")
+        );
+        assert!(!graph.nodes.contains_key(&"first".into()));
+
+        assert_eq!(
+            graph
+                .rename_node(&"second".into(), "source".into())
+                .unwrap_err(),
+            Error::NameTaken("source".to_owned())
+        );
+        assert_eq!(
+            graph
+                .rename_output(&"value".into(), "other".into())
+                .unwrap_err(),
+            Error::Missing(Side::Output, "value".into())
+        );
+
+        let mut graph = graph.validate().unwrap();
+        graph.run().unwrap();
+        assert_eq!(
+            graph.outputs[&"output".into()].1,
+            SocketValue::Value(Some(1.))
+        );
+    }
+
+    #[test]
+    fn connect() {
+        let mut graph = graph! {
+            inputs:
+                "value": SocketValue::Value(Some(1.)),
+            nodes:
+                "first": identity(),
+                "second": identity(),
+            outputs:
+                "value": (ssref!(node "second" "value"), SocketValue::Value(None)),
+        };
+
+        graph
+            .connect(sref!(graph "value"), &"first".into(), &"value".into())
+            .unwrap()
+            .connect(
+                sref!(node "first" "value"),
+                &"second".into(),
+                &"value".into(),
+            )
+            .unwrap();
+
+        let input = |graph: &Graph<Unvalidated>, id: &str| {
+            graph.nodes[&id.into()].inputs()[&"value".into()].0.clone()
+        };
+        assert_eq!(input(&graph, "first"), ssref!(graph "value"));
+        assert_eq!(input(&graph, "second"), ssref!(node "first" "value"));
+
+        graph.disconnect(&"second".into(), &"value".into()).unwrap();
+        assert_eq!(input(&graph, "second"), None);
+
+        graph
+            .connect(
+                sref!(node "first" "value"),
+                &"second".into(),
+                &"value".into(),
+            )
+            .unwrap();
+        let mut validated = graph.clone().validate().unwrap();
+        validated.run().unwrap();
+        assert_eq!(
+            validated.outputs[&"value".into()].1,
+            SocketValue::Value(Some(1.))
+        );
+    }
+
+    #[test]
+    fn connect_missing() {
+        let mut graph = graph! {
+            inputs:
+                "value": SocketValue::Value(Some(1.)),
+            nodes:
+                "node": node! {
+                    inputs:
+                        "input": (None, SocketType::Value),
+                    outputs:
+                        "output": SocketType::Value.into();
+                    |_inputs, _outputs| Ok(())
+                },
+            outputs,
+        };
+
+        assert_eq!(
+            graph
+                .connect(sref!(graph "value"), &"missing".into(), &"input".into())
+                .unwrap_err(),
+            Error::Missing(Side::Input, "input".into())
+        );
+        assert_eq!(
+            graph
+                .disconnect(&"missing".into(), &"input".into())
+                .unwrap_err(),
+            Error::Missing(Side::Input, "input".into())
+        );
+        assert_eq!(
+            graph
+                .connect(
+                    sref!(node "missing" "output"),
+                    &"node".into(),
+                    &"input".into()
+                )
+                .unwrap_err(),
+            Error::Missing(Side::Output, "output".into())
+        );
+
+        // Only graph inputs and node outputs can be linked to node inputs
+        assert_eq!(
+            graph
+                .connect(sref!(graph "other"), &"node".into(), &"input".into())
+                .unwrap_err(),
+            Error::Missing(Side::Input, "other".into())
+        );
+        assert_eq!(
+            graph
+                .connect(sref!(node "node" "input"), &"node".into(), &"input".into())
+                .unwrap_err(),
+            Error::Missing(Side::Output, "input".into())
+        );
+        assert_eq!(
+            graph
+                .connect(sref!(graph "value"), &"node".into(), &"output".into())
+                .unwrap_err(),
+            Error::Missing(Side::Input, "output".into())
+        );
+        assert_eq!(
+            graph.nodes[&"node".into()].inputs()[&"input".into()].0,
+            None
+        );
+    }
+
+    #[test]
+    fn unset_outputs() {
+        let mut graph = graph! {
+            inputs,
+            nodes:
+                "forgetful": node! {
+                    inputs,
+                    outputs:
+                        "set": SocketType::Value.into(),
+                        "forgotten": SocketType::Color.into();
+                    |_inputs, outputs| {
+                        get_sv!(output | outputs . "set" : Value > set);
+                        *set = Some(1.);
+
+                        Ok(())
+                    }
+                },
+            outputs:
+                "set": (ssref!(node "forgetful" "set"), SocketValue::Value(None)),
+                "forgotten": (ssref!(node "forgetful" "forgotten"), SocketValue::Color(None)),
+        }
+        .validate()
+        .unwrap();
+
+        let err = graph.run().unwrap_err();
+        assert_eq!(
+            err,
+            Error::UnsetOutputs {
+                node: "forgetful".into(),
+                outputs: vec!["forgotten".into()],
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "Node `forgetful` did not set its output(s) `forgotten`"
+        );
+    }
+
+    #[test]
+    fn output_policy() {
+        // Upstream graph input left without a value
+        let graph = graph! {
+            inputs:
+                "value": SocketValue::Value(None),
+            nodes,
+            outputs:
+                "value": (ssref!(graph "value"), SocketValue::Value(None)),
+                "unlinked": (None, SocketValue::Value(Some(1.))),
+        }
+        .validate()
+        .unwrap();
+
+        let mut defaulted = graph.clone();
+        defaulted.run().unwrap();
+
+        let mut expected = SocketValue::Value(None);
+        expected.set_default();
+        assert_eq!(defaulted.outputs[&"value".into()].1, expected);
+
+        let mut strict = graph;
+        strict.set_output_policy(OutputPolicy::Error);
+        // Only the linked output is reported
+        assert_eq!(
+            strict.run(),
+            Err(Error::UnsetGraphOutputs(vec!["value".into()]))
+        );
+    }
+
+    #[test]
+    fn run_deep_chain() {
+        const DEPTH: usize = 500;
+
+        let mut graph = graph! {
+            inputs:
+                "value": SocketValue::Value(Some(0.)),
+            nodes,
+            outputs:
+                "value": (
+                    ssref!(node format!("n{}", DEPTH - 1).as_str() => "value"),
+                    SocketValue::Value(None)
+                ),
+        };
+
+        for index in 0..DEPTH {
+            let mut node = node! {
+                inputs:
+                    "value": (None, SocketType::Value),
+                outputs:
+                    "value": SocketType::Value.into();
+                |inputs, outputs| {
+                    get_sv!( input | inputs  . "value" : Value > in_value);
+                    get_sv!(output | outputs . "value" : Value > out_value);
+
+                    *out_value = Some(in_value.unwrap() + 1.);
+
+                    Ok(())
+                }
+            };
+
+            let source = match index {
+                0 => ssref!(graph "value"),
+                _ => ssref!(node format!("n{}", index - 1).as_str() => "value"),
+            };
+            node.set_input(&"value".into(), source).unwrap();
+            graph
+                .nodes
+                .insert(format!("n{index}").as_str().into(), node);
+        }
+
+        let mut graph = graph.validate().unwrap();
+        assert_eq!(graph.topological_order().len(), DEPTH);
+
+        graph.run().unwrap();
+        assert_eq!(
+            graph.outputs[&"value".into()].1,
+            SocketValue::Value(Some(DEPTH as f32))
+        );
+    }
+
+    #[test]
+    fn run_large_output() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        // Addresses of the pixel buffers produced by `fill` and `tint`, and read by `tint`
+        static FILLED: AtomicUsize = AtomicUsize::new(0);
+        static READ: AtomicUsize = AtomicUsize::new(0);
+        static TINTED: AtomicUsize = AtomicUsize::new(0);
+
+        let mut graph = graph! {
+            inputs:
+                "size": SocketValue::Value(Some(1024.)),
+            nodes:
+                "fill": node! {
+                    inputs:
+                        "size": (ssref!(graph "size"), SocketType::Value),
+                    outputs:
+                        "color": SocketType::IColor.into();
+                    |inputs, outputs| {
+                        get_sv!( input | inputs  . "size" : Value > size);
+                        get_sv!(output | outputs . "color" : IColor > out);
+
+                        let size = size.unwrap() as u32;
+                        let image = Image::new(size, size, Color::new(0.25, 0.5, 0.75));
+                        FILLED.store(image.pixels.as_ptr() as usize, Ordering::SeqCst);
+                        *out = Some(image);
+
+                        Ok(())
+                    }
+                },
+                "tint": node! {
+                    inputs:
+                        "color": (ssref!(node "fill" "color"), SocketType::IColor),
+                    outputs:
+                        "color": SocketType::IColor.into();
+                    |inputs, outputs| {
+                        get_sv!( input | inputs  . "color" : IColor > color);
+                        get_sv!(output | outputs . "color" : IColor > out);
+
+                        let color = color.as_ref().unwrap();
+                        READ.store(color.pixels.as_ptr() as usize, Ordering::SeqCst);
+                        let image = color.map(|&pixel| pixel * 2.);
+                        TINTED.store(image.pixels.as_ptr() as usize, Ordering::SeqCst);
+                        *out = Some(image);
+
+                        Ok(())
+                    }
+                },
+            outputs:
+                "color": (ssref!(node "tint" "color"), SocketType::IColor.into()),
+        }
+        .validate()
+        .unwrap();
+
+        for _ in 0..2 {
+            graph.reset();
+            graph.run().unwrap();
+
+            let Some((_, SocketValue::IColor(Some(image)))) = graph.outputs.get(&"color".into())
+            else {
+                panic!("Missing color output")
+            };
+            assert_eq!((image.width, image.height), (1024, 1024));
+            assert!(image
+                .pixels
+                .iter()
+                .all(|&pixel| pixel == Color::new(0.5, 1., 1.5)));
+
+            // Images are moved from node to node and into the graph output, never copied
+            let read = READ.load(Ordering::SeqCst);
+            assert_eq!(FILLED.load(Ordering::SeqCst), read);
+            assert_eq!(
+                TINTED.load(Ordering::SeqCst),
+                image.pixels.as_ptr() as usize
+            );
+        }
+    }
+
+    #[test]
+    fn run_keeps_set_outputs() {
+        let mut graph = graph! {
+            inputs,
+            nodes,
+            outputs:
+                "preset": (None, SocketValue::Value(Some(1.))),
+        }
+        .validate()
+        .unwrap();
+
+        graph.run().unwrap();
+        graph.run().unwrap();
+
+        assert_eq!(
+            graph.outputs.get(&"preset".into()),
+            Some(&(None, SocketValue::Value(Some(1.))))
+        );
+    }
 }
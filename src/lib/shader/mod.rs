@@ -1,7 +1,9 @@
 //! Shader graph implementation
 
+pub mod eval;
 pub mod graph;
 pub mod parsing;
+pub mod procedural;
 pub mod shader;
 
 use graph::*;
@@ -13,3 +15,26 @@ pub struct Signature {
     input: HashMap<Name, SocketType>,
     output: HashMap<Name, SocketType>,
 }
+
+impl Signature {
+    /// Create a [Signature] from its input and output sockets.
+    pub fn new(
+        input: impl IntoIterator<Item = (Name, SocketType)>,
+        output: impl IntoIterator<Item = (Name, SocketType)>,
+    ) -> Self {
+        Self {
+            input: input.into_iter().collect(),
+            output: output.into_iter().collect(),
+        }
+    }
+
+    /// Get the input sockets' types.
+    pub fn input(&self) -> &HashMap<Name, SocketType> {
+        &self.input
+    }
+
+    /// Get the output sockets' types.
+    pub fn output(&self) -> &HashMap<Name, SocketType> {
+        &self.output
+    }
+}
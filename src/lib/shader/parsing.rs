@@ -3,7 +3,8 @@
 
 use super::{
     graph::{
-        Graph, ImportedNode, Name, Node, NodeId, SocketRef, SocketType, SocketValue, Unvalidated,
+        Graph, GraphNode, ImportedNode, Name, Node, NodeId, SocketRef, SocketType, SocketValue,
+        Unvalidated,
     },
     shader::Side,
     Signature,
@@ -56,13 +57,57 @@ pub type PResult<T> = Result<T, self::Error>;
 #[error("Encountered an error while parsing at {line:?}: {kind}")]
 /// Parsing error.
 pub struct Error {
-    kind: ErrorKind,
+    // Boxed to keep results small, Pest errors and signatures being large
+    kind: Box<ErrorKind>,
     line: LineColLocation,
 }
 
 impl Error {
     fn new(kind: ErrorKind, line: LineColLocation) -> Self {
-        Self { kind, line }
+        Self {
+            kind: Box::new(kind),
+            line,
+        }
+    }
+
+    /// Render the error along with the offending line of the parsed `source`, pointing carets
+    /// under the erroneous span like rustc does.
+    ///
+    /// Spans covering multiple lines only point to their start.
+    pub fn render(&self, source: &str) -> String {
+        let message = match self.kind.as_ref() {
+            ErrorKind::Parsing(err) => err.variant.message().into_owned(),
+            kind => kind.to_string(),
+        };
+
+        let ((line, col), width) = match self.line {
+            LineColLocation::Pos(pos) => (pos, 1),
+            LineColLocation::Span(start, end) if start.0 == end.0 => {
+                (start, end.1.saturating_sub(start.1).max(1))
+            }
+            LineColLocation::Span(start, _) => (start, 1),
+        };
+
+        let Some(code) = source.lines().nth(line.saturating_sub(1)) else {
+            return format!("error: {message}\n --> {line}:{col}");
+        };
+
+        // Keep tabs so that the carets line up with the source line
+        let padding = code
+            .chars()
+            .take(col.saturating_sub(1))
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect::<String>();
+        let gutter = " ".repeat(line.to_string().len());
+
+        format!(
+            "error: {message}\n\
+             {gutter}--> {line}:{col}\n\
+             {gutter} |\n\
+             {line} | {code}\n\
+             {gutter} | {padding}{}",
+            "^".repeat(width)
+        )
     }
 }
 
@@ -119,6 +164,10 @@ pub enum CodeError {
     #[error("Trying to link two inputs or outputs together")]
     /// Trying to link two inputs or two outputs.
     SideMismatch,
+
+    #[error("Unsupported syntax: {0}")]
+    /// Valid syntax the parser cannot handle yet.
+    Unsupported(String),
 }
 
 #[derive(Debug, Clone, thiserror::Error)]
@@ -147,6 +196,8 @@ pub enum Section {
     Unknown,
     /// Graph type [Signature].
     Signature,
+    /// Named constant declarations.
+    Constants,
     /// Loaded [Node] importing.
     Imports,
     /// [Node] declarations.
@@ -199,8 +250,10 @@ fn parse_program(
     let mut inner = program.into_inner();
 
     let signature = parse_signature(inner.next().unwrap())?;
+    let constants = parse_constants(inner.next().unwrap())?;
     let imports = parse_imports(inner.next().unwrap(), loaded)?;
     let mut nodes = parse_nodes(inner.next().unwrap(), loaded, &imports)?;
+    nodes.extend(constants);
     let out_links = parse_links(inner.next().unwrap(), &signature, &mut nodes)?;
 
     let mut graph = graph::Graph {
@@ -215,6 +268,7 @@ fn parse_program(
             .map(|(name, socket_type)| (name, (None, socket_type.into())))
             .collect(),
         nodes,
+        output_policy: Default::default(),
         state: std::marker::PhantomData,
     };
 
@@ -265,6 +319,116 @@ fn get_loaded(
         .clone())
 }
 
+/// Name of the output socket of the nodes holding `@CONST` values.
+const CONSTANT_SOCKET: &str = "value";
+
+/// Id of the node holding the value of the `@CONST.name` constant.
+fn constant_id(name: &Name) -> NodeId {
+    NodeId::from(format!("@CONST.{}", name.to_string()).as_str())
+}
+
+/// Prefix of the ids of the nodes holding inline literal values, see [literal_id].
+const LITERAL_PREFIX: &str = "const_";
+
+/// Id of the next node holding an inline literal value, `const_<counter>` with the first
+/// `counter` not already used by one of the `nodes`.
+///
+/// Ids only depend on the order of the literals in the links section and on the declared node
+/// names, so that parsing the same code always yields the same graph.
+fn literal_id(nodes: &HashMap<NodeId, Node<Unvalidated>>, counter: &mut usize) -> NodeId {
+    loop {
+        let id = NodeId::from(format!("{LITERAL_PREFIX}{counter}").as_str());
+        *counter += 1;
+
+        if !nodes.contains_key(&id) {
+            return id;
+        }
+    }
+}
+
+/// Input-less node outputting `value` through its [CONSTANT_SOCKET].
+fn constant_node(value: SocketValue) -> Node<Unvalidated> {
+    Node::Graph(GraphNode {
+        outputs: std::iter::once((CONSTANT_SOCKET.into(), value)).collect(),
+        ..Default::default()
+    })
+}
+
+fn parse_constants(constants: Pair<Rule>) -> PResult<HashMap<NodeId, Node<Unvalidated>>> {
+    let mut res = HashMap::new();
+
+    for constant in constants.into_inner() {
+        let span = constant.as_span();
+        let (name, value) = parse_constant(constant)?;
+
+        if res
+            .insert(constant_id(&name), constant_node(value))
+            .is_some()
+        {
+            return Err(Error::new(
+                ErrorKind::Code {
+                    r#type: CodeError::Redefinition(format!("@CONST.{}", name.to_string())),
+                    section: Section::Constants,
+                },
+                lcl_from_bounds(span.split()),
+            ));
+        }
+    }
+
+    Ok(res)
+}
+
+/// # Example
+/// ```eray
+/// @CONST.offset: Vec3 = 1, 0.5, 0
+/// ```
+fn parse_constant(constant: Pair<Rule>) -> PResult<(Name, SocketValue)> {
+    let span = constant.as_span();
+    let mut inner = constant.into_inner();
+
+    let name = Name::from(inner.next().unwrap().as_str());
+    let ty = SocketType::from_str(inner.next().unwrap().as_str()).unwrap();
+    let value = parse_literal(inner.next().unwrap(), ty).map_err(|literal_type| {
+        Error::new(
+            ErrorKind::Code {
+                r#type: CodeError::SocketType(ty, literal_type),
+                section: Section::Constants,
+            },
+            lcl_from_bounds(span.split()),
+        )
+    })?;
+
+    Ok((name, value))
+}
+
+/// Parse a literal, converting it to `ty` if needed. Fails with the literal's own type if it
+/// cannot be converted.
+/// # Example
+/// ```eray
+/// 1, 0.5, 0
+/// ```
+fn parse_literal(literal: Pair<Rule>, ty: SocketType) -> Result<SocketValue, SocketType> {
+    let literal = literal.into_inner().next().unwrap();
+    let numbers = literal
+        .clone()
+        .into_inner()
+        .map(|number| number.as_str().replace('_', "").parse::<f32>().unwrap())
+        .collect::<Vec<f32>>();
+
+    let value = match_rule! {
+        literal:
+            value => SocketValue::Value(Some(numbers[0])),
+            vector => SocketValue::Vec3(Some(Vector::new(numbers[0], numbers[1], numbers[2]))),
+    };
+
+    let literal_type = SocketType::from(&value);
+    if literal_type == ty {
+        return Ok(value);
+    }
+
+    value.try_convert(ty).map_err(|_| literal_type)
+}
+
 fn parse_imports(
     imports: Pair<Rule>,
     loaded: &mut HashMap<Name, Vec<ImportedNode<Unvalidated>>>,
@@ -367,10 +531,12 @@ fn parse_links(
     graph_signature: &Signature,
     nodes: &mut HashMap<NodeId, Node<Unvalidated>>,
 ) -> PResult<Vec<(Name, SocketRef)>> {
+    let mut literals = 0;
+
     links
         .into_inner()
         .flat_map(|link| {
-            parse_link(link.clone(), graph_signature, nodes)
+            parse_link(link.clone(), graph_signature, nodes, &mut literals)
                 .transpose()
                 .map(|res| (link, res))
         })
@@ -403,6 +569,7 @@ fn parse_link(
     link: Pair<Rule>,
     graph_signature: &Signature,
     nodes: &mut HashMap<NodeId, Node<Unvalidated>>,
+    literals: &mut usize,
     // ) -> PResult<Vec<Link>> {
 ) -> PResult<Option<(Name, SocketRef)>> {
     let mut inner = link.clone().into_inner();
@@ -411,7 +578,7 @@ fn parse_link(
 
     let (lhs_link, lhs_type) = match_rule! {
         lhs:
-            // expr => parse_expr(lhs, graph_signature, nodes),
+            expr => parse_expr(lhs, nodes, literals),
             field => parse_field(lhs, graph_signature, nodes, &Side::Input),
     }?;
 
@@ -420,6 +587,16 @@ fn parse_link(
             field => parse_field(rhs, graph_signature, nodes, &Side::Output),
     }?;
 
+    if lhs_type != rhs_type {
+        return Err(Error::new(
+            ErrorKind::Code {
+                r#type: CodeError::SocketType(lhs_type, rhs_type),
+                section: Section::Links,
+            },
+            lcl_from_bounds(link.as_span().split()),
+        ));
+    }
+
     match rhs_link {
         LinkSide::NodeSocket(id, name) => nodes
             .get_mut(&id)
@@ -441,108 +618,75 @@ fn parse_link(
                     LinkSide::GraphSocket(name) => ssref!(graph name),
                 },
             )
-            .map_err(|err| {
+            .map_err(|_err| {
                 Error::new(
                     ErrorKind::Code {
                         r#type: CodeError::Undefined {
-                            got: match lhs_link {
-                                LinkSide::NodeSocket(id, name) => {
-                                    format!("{}.{}", id.to_string(), name.to_string())
-                                }
-                                LinkSide::GraphSocket(name) => name.to_string(),
-                            },
-                            guess: todo!(),
-                            variant: todo!(),
+                            got: format!("{}.{}", id.to_string(), name.to_string()),
+                            guess: None,
+                            variant: UndefinedError::Undefined,
                         },
-                        section: todo!(),
+                        section: Section::Links,
                     },
                     lcl_from_bounds(link.as_span().split()),
                 )
             })?,
-        LinkSide::GraphSocket(name) => {
+        // Graph outputs are set by the caller, either from a node or straight from a graph input
+        LinkSide::GraphSocket(output) => {
             return Ok(match lhs_link.clone() {
                 LinkSide::NodeSocket(id, name) => ssref!(node id => name),
-                LinkSide::GraphSocket(name) => ssref!(graph name),
+                LinkSide::GraphSocket(input) => ssref!(graph input),
             }
-            .map(|socket_ref| (name, socket_ref)))
+            .map(|socket_ref| (output, socket_ref)))
         }
     };
 
     Ok(None)
 }
 
-/// # Example
-/// ```eray
-/// expr:  Color(1, 1, 1)
-/// field: Color(@IN.vec3)
-/// ```
-// fn parse_expr(
-//     expr: Pair<Rule>,
-//     graph_signature: &Signature,
-//     nodes: &mut HashMap<NodeId, Node<Unvalidated>>,
-// ) -> PResult<(LinkSide, SocketType)> {
-//     let mut inner = expr.clone().into_inner();
-//
-//     // Converted value type target
-//     let ty = SocketType::from_str(inner.next().unwrap().as_str()).unwrap();
-//
-//     let lhs = inner.next().unwrap();
-//     let rhs = inner.next().unwrap();
-//     match_rule! {
-//         lhs:
-//             field => {
-//                 let (source_link, source_socket_type) = parse_field(lhs, graph_signature, nodes, &Side::Input)?;
-//                 let (destination_link, destination_socket_type) = parse_field(rhs, graph_signature, nodes, &Side::Output)?;
-//
-//
-//
-//                 todo!();
-//             },
-//             literal => {
-//                 let value = parse_literal(lhs, graph_signature, nodes)?;
-//                 todo!();
-//             },
-//     };
-//
-//     todo!()
-// }
-
-/// Returns:
-///     - [SocketValue]: the parsed literal value of the correct type
+/// Materialize an inline literal as a constant node named by [literal_id], `literals` counting
+/// the ones already encountered.
 /// # Example
 /// ```eray
 /// Color(1, 1, 1)
 /// ```
-// fn parse_literal(
-//     literal: Pair<Rule>,
-//     graph_signature: &Signature,
-//     nodes: &mut HashMap<NodeId, Node<Unvalidated>>,
-// ) -> PResult<SocketValue> {
-//     let mut inner = literal.clone().into_inner().next().unwrap();
-//
-//     let value = match_rule! {
-//         inner:
-//             value => SocketValue::Value(Some(inner.into_inner().next().unwrap().as_str().parse::<f32>().unwrap())),
-//             vector => {
-//                 let values = inner
-//                     .into_inner()
-//                     .map(|number| number.as_str().parse::<f32>())
-//                     .collect::<Result<Vec<f32>, _>>()
-//                     .unwrap();
-//
-//                 let vector = Vector::new(values[0], values[1], values[2]);
-//
-//                 SocketValue::Vec3(Some(vector))
-//             },
-//     };
-//
-//     // let duplicate_count = nodes
-//     //     .keys()
-//     //     .filter(|&key| String::from(key).as_str().starts_with("constant"))
-//     //     .count();
-//
-//     Ok(value)
-// }
+fn parse_expr(
+    expr: Pair<Rule>,
+    nodes: &mut HashMap<NodeId, Node<Unvalidated>>,
+    literals: &mut usize,
+) -> PResult<(LinkSide, SocketType)> {
+    let span = expr.as_span();
+    let mut inner = expr.into_inner();
+
+    // Converted value type target
+    let ty = SocketType::from_str(inner.next().unwrap().as_str()).unwrap();
+
+    let source = inner.next().unwrap();
+    let error = |r#type| {
+        Error::new(
+            ErrorKind::Code {
+                r#type,
+                section: Section::Links,
+            },
+            lcl_from_bounds(span.split()),
+        )
+    };
+
+    if source.as_rule() != Rule::literal || inner.next().is_some() {
+        return Err(error(CodeError::Unsupported(format!(
+            "`{}`, only literals can be used in expressions",
+            span.as_str()
+        ))));
+    }
+
+    let value = parse_literal(source, ty)
+        .map_err(|literal_type| error(CodeError::SocketType(ty, literal_type)))?;
+
+    let id = literal_id(nodes, literals);
+    nodes.insert(id.clone(), constant_node(value));
+
+    Ok((LinkSide::NodeSocket(id, CONSTANT_SOCKET.into()), ty))
+}
 
 /// Returns:
 ///     - [LinkSide]: contains information about the linked socket
@@ -560,11 +704,14 @@ fn parse_field(
     let mut inner = field.clone().into_inner();
 
     let source = inner.next().unwrap();
+    let socket = Name::from(inner.next().unwrap().as_str());
+
     let source = match_rule! {
         source:
-            id => Ok(Some(NodeId::from(source.as_str()))),
+            id => Ok(Some((NodeId::from(source.as_str()), socket.clone()))),
             meta => match (side, source.as_str()) {
                 (Side::Input, "@IN") | (Side::Output, "@OUT") => Ok(None),
+                (Side::Input, "@CONST") => Ok(Some((constant_id(&socket), CONSTANT_SOCKET.into()))),
                 _ => Err(Error::new(
                     ErrorKind::Code {
                         r#type: CodeError::SideMismatch,
@@ -575,8 +722,6 @@ fn parse_field(
             },
     }?;
 
-    let socket = Name::from(inner.next().unwrap().as_str());
-
     let error = |name: String| {
         Error::new(
             ErrorKind::Code {
@@ -592,7 +737,7 @@ fn parse_field(
     };
 
     Ok(match source {
-        Some(node_id) => {
+        Some((node_id, socket)) => {
             let r#type = nodes
                 .get(&node_id)
                 .and_then(|node| match side {
@@ -718,6 +863,37 @@ mod test {
         assert!(parse_shader(code, &mut HashMap::new()).is_ok());
     }
 
+    #[test]
+    fn negative_numbers() {
+        let code = "@CONST.offset: Vec3 = -1, 0.5, -0.25";
+        let constant = SParser::parse(Rule::constant, code)
+            .unwrap()
+            .next()
+            .unwrap();
+
+        assert_eq!(
+            parse_constant(constant).unwrap(),
+            (
+                Name::from("offset"),
+                SocketValue::Vec3(Some(Vector::new(-1., 0.5, -0.25)))
+            )
+        );
+    }
+
+    #[test]
+    fn render_error() {
+        let code = "|a: Value| -> (b: Value);\n\t@IN.a -> @OUT b;";
+
+        let err = parse_shader(code, &mut HashMap::new()).unwrap_err();
+        let rendered = err.render(code);
+
+        // `@OUT b` is missing a dot, so the whole field fails to parse
+        let lines = rendered.lines().collect::<Vec<_>>();
+        assert_eq!(lines[1], " --> 2:11", "Unexpected rendering:\n{rendered}");
+        assert_eq!(lines[3], "2 | \t@IN.a -> @OUT b;");
+        assert_eq!(lines[4], "  | \t         ^");
+    }
+
     #[test]
     fn full_parse() {
         let code = std::fs::read_to_string("nodes/test.eray")
@@ -790,4 +966,219 @@ mod test {
         let res = parse_shader(code.as_str(), &mut loaded);
         assert!(res.is_ok(), "{res:?}");
     }
+
+    #[test]
+    fn constants() {
+        let code = std::fs::read_to_string("nodes/constants.eray")
+            .expect("Missing `nodes/constants.eray` test shader");
+
+        let mut loaded = std::iter::once((
+            Name::from("add"),
+            vec![ImportedNode::from((
+                "add",
+                graph! {
+                    inputs:
+                        "lhs": SocketType::Value.into(),
+                        "rhs": SocketType::Value.into(),
+                    nodes:
+                        "inner": node! {
+                            inputs:
+                                "lhs": (ssref!(graph "lhs"), SocketType::Value),
+                                "rhs": (ssref!(graph "rhs"), SocketType::Value),
+                            outputs:
+                                "value": SocketType::Value.into(),
+                        },
+                    outputs:
+                        "value": (ssref!(node "inner" "value"), SocketType::Value.into())
+                },
+            ))],
+        ))
+        .collect();
+
+        let graph = parse_shader(code.as_str(), &mut loaded).unwrap();
+
+        let Some(Node::Graph(constant)) = graph.nodes.get(&"@CONST.offset".into()) else {
+            panic!("Missing constant node in {:?}", graph.nodes.keys());
+        };
+        assert_eq!(
+            constant.outputs.get(&"value".into()),
+            Some(&SocketValue::Value(Some(0.5)))
+        );
+
+        for id in ["A", "B"] {
+            let Some(Node::Imported(node)) = graph.nodes.get(&id.into()) else {
+                panic!("Missing node {id}");
+            };
+
+            assert_eq!(
+                node.inputs.get(&"rhs".into()),
+                Some(&(ssref!(node "@CONST.offset" "value"), SocketType::Value))
+            );
+        }
+    }
+
+    #[test]
+    fn literal_ids() {
+        let code = "
+            | x: Value | -> ( value: Value );
+
+            add = add: |lhs: Value, rhs: Value| -> value: Value;
+
+            const_0 = add;
+            B = add;
+
+            @IN.x -> const_0.lhs;
+            Value(0.5) -> const_0.rhs;
+
+            const_0.value -> B.lhs;
+            Value(0.5) -> B.rhs;
+
+            B.value -> @OUT.value;
+        ";
+
+        let graph = parse_shader(code, &mut add_import()).unwrap();
+
+        // The user's `const_0` node is left alone
+        let literal = |id: &str| {
+            let Some(Node::Imported(node)) = graph.nodes.get(&id.into()) else {
+                panic!("Missing node {id}");
+            };
+            let Some(SocketRef::Node(literal, _socket)) = node.inputs[&"rhs".into()].0.clone()
+            else {
+                panic!("Unlinked {id}.rhs")
+            };
+            literal
+        };
+        let (first, second) = (literal("const_0"), literal("B"));
+
+        assert_eq!(first, NodeId::from("const_1"));
+        assert_eq!(second, NodeId::from("const_2"));
+
+        for id in [first, second] {
+            let Some(Node::Graph(node)) = graph.nodes.get(&id) else {
+                panic!("Missing literal node {id:?}");
+            };
+            assert_eq!(node.outputs[&"value".into()], SocketValue::Value(Some(0.5)));
+        }
+
+        // Stable across parses
+        assert_eq!(graph, parse_shader(code, &mut add_import()).unwrap());
+    }
+
+    fn add_import() -> HashMap<Name, Vec<ImportedNode<Unvalidated>>> {
+        std::iter::once((
+            Name::from("add"),
+            vec![ImportedNode::from((
+                "add",
+                graph! {
+                    inputs:
+                        "lhs": SocketType::Value.into(),
+                        "rhs": SocketType::Value.into(),
+                    nodes:
+                        "inner": node! {
+                            inputs:
+                                "lhs": (ssref!(graph "lhs"), SocketType::Value),
+                                "rhs": (ssref!(graph "rhs"), SocketType::Value),
+                            outputs:
+                                "value": SocketType::Value.into(),
+                        },
+                    outputs:
+                        "value": (ssref!(node "inner" "value"), SocketType::Value.into())
+                },
+            ))],
+        ))
+        .collect()
+    }
+
+    #[test]
+    fn link_type_mismatch() {
+        let code = "
+            | x: Value, color: Color | -> ( value: Value );
+
+            add = add: |lhs: Value, rhs: Value| -> value: Value;
+
+            A = add;
+
+            @IN.x -> A.lhs;
+            @IN.color -> A.rhs;
+            A.value -> @OUT.value;
+        ";
+
+        let res = parse_shader(code, &mut add_import());
+
+        assert!(
+            matches!(
+                res.as_ref().map_err(|err| err.kind.as_ref()),
+                Err(ErrorKind::Code {
+                    r#type: CodeError::SocketType(SocketType::Color, SocketType::Value),
+                    section: Section::Links,
+                })
+            ),
+            "Expected a socket type error, got {res:?}"
+        );
+    }
+
+    #[test]
+    fn flexible_whitespace() {
+        let dense = "|x:Value,|->(value:Value,);@CONST.c:Value=0.5;@CONST.v:Vec3=1,2,3,;\
+            add=add:|lhs:Value,rhs:Value|->value:Value;A=add;B=add;\
+            @IN.x->A.lhs;@IN.x->A.rhs;A.value->B.lhs;@CONST.c->B.rhs;B.value->@OUT.value;\
+            #! Trailing comment without a newline";
+
+        let spaced = "
+            |
+                x : Value ,
+            |
+                ->
+            (
+                value
+                    : Value
+            ) ;
+
+            @CONST . c : Value = 0.5 ;
+            @CONST . v : Vec3 = 1 , 2 , 3 ;
+
+            add = add : | lhs : Value , rhs : Value | -> value : Value ;
+
+            A = add ;   B = add ;
+
+            @IN . x -> A . lhs ;  @IN . x -> A . rhs ;
+            A . value
+                -> B . lhs ;
+            @CONST . c -> B . rhs ;
+            B . value -> @OUT . value ;
+        ";
+
+        let dense = parse_shader(dense, &mut add_import()).unwrap();
+        let spaced = parse_shader(spaced, &mut add_import()).unwrap();
+
+        assert_eq!(dense, spaced);
+        assert_eq!(dense.nodes.len(), 4);
+    }
+
+    #[test]
+    fn passthrough() {
+        let code = "
+            | x: Value | -> ( y: Value );
+
+            @IN.x -> @OUT.y;
+        ";
+
+        let mut graph = parse_shader(code, &mut HashMap::new()).unwrap();
+        assert_eq!(
+            graph.outputs.get(&"y".into()),
+            Some(&(ssref!(graph "x"), SocketType::Value.into()))
+        );
+
+        graph
+            .inputs
+            .insert("x".into(), SocketValue::Value(Some(4.2)));
+        let mut graph = graph.validate().unwrap();
+        graph.run().unwrap();
+
+        assert_eq!(
+            graph.outputs.get(&"y".into()).map(|(_ref, value)| value),
+            Some(&SocketValue::Value(Some(4.2)))
+        );
+    }
 }
@@ -0,0 +1,259 @@
+//! Authoring of custom nodes outside of the [Graph]-building macros, through the
+//! [ProceduralNode] trait.
+
+use super::{
+    graph::{
+        Graph, GraphNode, ImportedNode, Name, Node, NodeId, SocketRef, SocketType, SocketValue,
+        Unvalidated,
+    },
+    shader::{Error, Shader, Side},
+    Signature,
+};
+
+use std::{collections::HashMap, sync::Arc};
+
+/// Id of the single node inside the [Graph] wrapping a [ProceduralNode].
+const INNER_NODE: &str = "inner";
+
+/// Custom node defined by its [Signature] and an evaluation function.
+///
+/// # Example
+/// ```
+/// use eray::shader::{
+///     graph::{Name, SocketType, SocketValue},
+///     procedural::ProceduralNode,
+///     shader::Error,
+///     Signature,
+/// };
+/// use std::collections::HashMap;
+///
+/// struct Double;
+///
+/// impl ProceduralNode for Double {
+///     fn signature(&self) -> Signature {
+///         Signature::new(
+///             [("value".into(), SocketType::Value)],
+///             [("value".into(), SocketType::Value)],
+///         )
+///     }
+///
+///     fn evaluate(
+///         &self,
+///         inputs: &HashMap<Name, SocketValue>,
+///     ) -> Result<HashMap<Name, SocketValue>, Error> {
+///         let Some(SocketValue::Value(value)) = inputs.get(&"value".into()) else {
+///             return Err(Error::Unknown(Some("Missing value".to_owned())));
+///         };
+///
+///         Ok([("value".into(), SocketValue::Value(value.map(|v| v * 2.)))].into())
+///     }
+/// }
+///
+/// let node = Double.into_node();
+/// ```
+pub trait ProceduralNode: Send + Sync {
+    /// Input and output sockets of the node.
+    fn signature(&self) -> Signature;
+
+    /// Compute the node's output values from its input values.
+    ///
+    /// Returned outputs must be part of the [signature](ProceduralNode::signature) with a
+    /// matching type, missing outputs are left untouched.
+    fn evaluate(
+        &self,
+        inputs: &HashMap<Name, SocketValue>,
+    ) -> Result<HashMap<Name, SocketValue>, Error>;
+
+    /// Wrap the node into a [GraphNode] with unlinked inputs.
+    fn into_graph_node(self) -> GraphNode
+    where
+        Self: Sized + 'static,
+    {
+        graph_node(Arc::new(self))
+    }
+
+    /// Wrap the node into a [Node] with unlinked inputs.
+    fn into_node(self) -> Node<Unvalidated>
+    where
+        Self: Sized + 'static,
+    {
+        Node::Graph(self.into_graph_node())
+    }
+}
+
+/// Wrap a shared [ProceduralNode] into a [GraphNode] calling it as its [Shader].
+pub fn graph_node(node: Arc<dyn ProceduralNode>) -> GraphNode {
+    let Signature { input, output } = node.signature();
+
+    GraphNode {
+        inputs: input
+            .into_iter()
+            .map(|(name, socket_type)| (name, (None, socket_type)))
+            .collect(),
+        outputs: output
+            .into_iter()
+            .map(|(name, socket_type)| (name, socket_type.into()))
+            .collect(),
+        shader: Shader::from_fn(move |inputs, outputs| {
+            for (name, value) in node.evaluate(inputs)? {
+                let output = outputs
+                    .get_mut(&name)
+                    .ok_or_else(|| Error::Missing(Side::Output, name.clone()))?;
+
+                let (got, expected) = (SocketType::from(&value), SocketType::from(&*output));
+                if got != expected {
+                    return Err(Error::InvalidType {
+                        name,
+                        got,
+                        expected,
+                    });
+                }
+
+                *output = value;
+            }
+
+            Ok(())
+        }),
+    }
+}
+
+/// Wrap a shared [ProceduralNode] into a [Graph] forwarding its inputs and outputs, as expected
+/// by [ImportedNode]s.
+pub fn graph(node: Arc<dyn ProceduralNode>) -> Graph<Unvalidated> {
+    let mut inner = graph_node(node);
+
+    for (name, (socket_ref, _socket_type)) in inner.inputs.iter_mut() {
+        *socket_ref = Some(SocketRef::Graph(name.clone()));
+    }
+
+    Graph {
+        inputs: inner
+            .inputs
+            .iter()
+            .map(|(name, (_socket_ref, socket_type))| (name.clone(), (*socket_type).into()))
+            .collect(),
+        outputs: inner
+            .outputs
+            .iter()
+            .map(|(name, value)| {
+                let socket_ref = SocketRef::Node(NodeId::from(INNER_NODE), name.clone());
+                (name.clone(), (Some(socket_ref), value.clone()))
+            })
+            .collect(),
+        nodes: std::iter::once((NodeId::from(INNER_NODE), Node::Graph(inner))).collect(),
+        output_policy: Default::default(),
+        state: std::marker::PhantomData,
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+/// Collection of loaded nodes available for importing while
+/// [parsing](super::parsing::parse_shader), with overloads sharing the same [Name].
+pub struct Registry {
+    loaded: HashMap<Name, Vec<ImportedNode<Unvalidated>>>,
+}
+
+impl Registry {
+    /// Create an empty [Registry].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a [ProceduralNode] under `name`, as an overload if the name is already taken.
+    pub fn register(&mut self, name: &str, node: impl ProceduralNode + 'static) -> &mut Self {
+        self.register_imported(ImportedNode::from((name, graph(Arc::new(node)))))
+    }
+
+    /// Register an already built [ImportedNode], e.g. from a node library.
+    pub fn register_imported(&mut self, node: ImportedNode<Unvalidated>) -> &mut Self {
+        self.loaded
+            .entry(node.name().clone())
+            .or_default()
+            .push(node);
+        self
+    }
+
+    /// Get the loaded nodes map passed to [parse_shader](super::parsing::parse_shader).
+    pub fn loaded(&mut self) -> &mut HashMap<Name, Vec<ImportedNode<Unvalidated>>> {
+        &mut self.loaded
+    }
+}
+
+impl Extend<ImportedNode<Unvalidated>> for Registry {
+    fn extend<T: IntoIterator<Item = ImportedNode<Unvalidated>>>(&mut self, iter: T) {
+        iter.into_iter().for_each(|node| {
+            self.register_imported(node);
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::shader::parsing::parse_shader;
+
+    /// Adds its two inputs.
+    struct Sum;
+
+    impl ProceduralNode for Sum {
+        fn signature(&self) -> Signature {
+            Signature::new(
+                [
+                    ("lhs".into(), SocketType::Value),
+                    ("rhs".into(), SocketType::Value),
+                ],
+                [("value".into(), SocketType::Value)],
+            )
+        }
+
+        fn evaluate(
+            &self,
+            inputs: &HashMap<Name, SocketValue>,
+        ) -> Result<HashMap<Name, SocketValue>, Error> {
+            let value = |name: &str| match inputs.get(&name.into()) {
+                Some(SocketValue::Value(value)) => Ok(value.unwrap_or_default()),
+                _ => Err(Error::Missing(Side::Input, name.into())),
+            };
+
+            Ok([(
+                "value".into(),
+                SocketValue::Value(Some(value("lhs")? + value("rhs")?)),
+            )]
+            .into())
+        }
+    }
+
+    #[test]
+    fn custom_node() {
+        let code = "
+            | a: Value, b: Value | -> ( sum: Value );
+
+            sum = sum: |lhs: Value, rhs: Value| -> value: Value;
+
+            S = sum;
+
+            @IN.a -> S.lhs;
+            @IN.b -> S.rhs;
+            S.value -> @OUT.sum;
+        ";
+
+        let mut registry = Registry::new();
+        registry.register("sum", Sum);
+
+        let mut graph = parse_shader(code, registry.loaded()).unwrap();
+        graph
+            .inputs
+            .insert("a".into(), SocketValue::Value(Some(1.5)));
+        graph
+            .inputs
+            .insert("b".into(), SocketValue::Value(Some(2.)));
+
+        let mut graph = graph.validate().unwrap();
+        graph.run().unwrap();
+
+        assert_eq!(
+            graph.outputs.get(&"sum".into()).map(|(_ref, value)| value),
+            Some(&SocketValue::Value(Some(3.5)))
+        );
+    }
+}
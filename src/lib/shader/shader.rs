@@ -75,6 +75,13 @@ impl Shader {
         }
     }
 
+    /// Creates a [Shader] from any clonable closure, e.g. one capturing its own state.
+    pub fn from_fn(func: impl CloneFn + 'static) -> Self {
+        Self {
+            func: Box::new(func),
+        }
+    }
+
     /// Execute the contained function.
     pub fn call(
         &self,
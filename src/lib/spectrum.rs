@@ -0,0 +1,234 @@
+//! Sampled spectral power distribution, convertible from and to [Color] through the CIE 1931
+//! color matching functions.
+
+use std::ops::{Add, Mul};
+
+use crate::color::Color;
+
+/// Number of wavelength samples in a [Spectrum].
+pub const SAMPLES: usize = 32;
+/// Shortest sampled wavelength, in nanometers.
+pub const LAMBDA_MIN: f32 = 380.;
+/// Longest sampled wavelength, in nanometers.
+pub const LAMBDA_MAX: f32 = 720.;
+
+const BIN_WIDTH: f32 = (LAMBDA_MAX - LAMBDA_MIN) / SAMPLES as f32;
+
+/// CIE XYZ to linear sRGB (D65) matrix.
+const XYZ_TO_RGB: [[f32; 3]; 3] = [
+    [3.2406, -1.5372, -0.4986],
+    [-0.9689, 1.8758, 0.0415],
+    [0.0557, -0.2040, 1.0570],
+];
+
+lazy_static::lazy_static! {
+    /// Spectra converting to pure red, green and blue, used to upsample a [Color].
+    static ref RGB_BASIS: [Spectrum; 3] = rgb_basis();
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// Spectral power distribution sampled at [SAMPLES] evenly spaced wavelengths between
+/// [LAMBDA_MIN] and [LAMBDA_MAX].
+pub struct Spectrum {
+    /// Power at each sampled wavelength.
+    pub samples: [f32; SAMPLES],
+}
+
+impl Default for Spectrum {
+    fn default() -> Self {
+        Self::constant(0.)
+    }
+}
+
+impl Spectrum {
+    /// Creates a [Spectrum] with the same power at every wavelength.
+    pub fn constant(value: f32) -> Self {
+        Self {
+            samples: [value; SAMPLES],
+        }
+    }
+
+    /// Creates a [Spectrum] by evaluating a function of the wavelength (in nanometers).
+    pub fn from_fn(f: impl Fn(f32) -> f32) -> Self {
+        let mut samples = [0.; SAMPLES];
+        for (index, sample) in samples.iter_mut().enumerate() {
+            *sample = f(Self::wavelength(index));
+        }
+        Self { samples }
+    }
+
+    /// Wavelength (in nanometers) at the center of the sample at `index`.
+    pub fn wavelength(index: usize) -> f32 {
+        LAMBDA_MIN + (index as f32 + 0.5) * BIN_WIDTH
+    }
+
+    /// Power at the given wavelength (in nanometers), using the closest sample.
+    pub fn at(&self, wavelength: f32) -> f32 {
+        let index = ((wavelength - LAMBDA_MIN) / BIN_WIDTH).floor();
+        self.samples[(index.max(0.) as usize).min(SAMPLES - 1)]
+    }
+
+    /// Converts to CIE XYZ, normalized so that a constant spectrum of 1 has a luminance of 1.
+    pub fn to_xyz(&self) -> [f32; 3] {
+        let mut xyz = [0.; 3];
+        let mut norm = 0.;
+
+        for (index, sample) in self.samples.iter().enumerate() {
+            let matching = cie_1931(Self::wavelength(index));
+            for (value, weight) in xyz.iter_mut().zip(matching) {
+                *value += sample * weight;
+            }
+            norm += matching[1];
+        }
+
+        xyz.map(|value| value / norm)
+    }
+}
+
+impl From<Spectrum> for Color {
+    fn from(value: Spectrum) -> Self {
+        let xyz = value.to_xyz();
+        let [r, g, b] = XYZ_TO_RGB.map(|row| row.iter().zip(xyz).map(|(m, v)| m * v).sum());
+        Color::new(r, g, b)
+    }
+}
+
+/// Upsamples a [Color] as a linear combination of three fixed spectra.
+///
+/// The conversion round-trips exactly through [Color], but saturated colors may produce
+/// negative samples.
+impl From<Color> for Spectrum {
+    fn from(value: Color) -> Self {
+        let [r, g, b] = &*RGB_BASIS;
+        *r * value.r + *g * value.g + *b * value.b
+    }
+}
+
+impl Add for Spectrum {
+    type Output = Self;
+
+    fn add(mut self, rhs: Self) -> Self::Output {
+        self.samples
+            .iter_mut()
+            .zip(rhs.samples)
+            .for_each(|(lhs, rhs)| *lhs += rhs);
+        self
+    }
+}
+
+impl Mul for Spectrum {
+    type Output = Self;
+
+    fn mul(mut self, rhs: Self) -> Self::Output {
+        self.samples
+            .iter_mut()
+            .zip(rhs.samples)
+            .for_each(|(lhs, rhs)| *lhs *= rhs);
+        self
+    }
+}
+
+impl Mul<f32> for Spectrum {
+    type Output = Self;
+
+    fn mul(mut self, rhs: f32) -> Self::Output {
+        self.samples.iter_mut().for_each(|sample| *sample *= rhs);
+        self
+    }
+}
+
+/// Piecewise gaussian with different widths on each side of the mean.
+fn lobe(x: f32, mean: f32, left: f32, right: f32) -> f32 {
+    let sigma = if x < mean { left } else { right };
+    (-0.5 * ((x - mean) / sigma).powi(2)).exp()
+}
+
+/// CIE 1931 2° color matching functions, using the multi-lobe fit from Wyman et al. (2013).
+fn cie_1931(wavelength: f32) -> [f32; 3] {
+    let x = 1.056 * lobe(wavelength, 599.8, 37.9, 31.0)
+        + 0.362 * lobe(wavelength, 442.0, 16.0, 26.7)
+        - 0.065 * lobe(wavelength, 501.1, 20.4, 26.2);
+    let y =
+        0.821 * lobe(wavelength, 568.8, 46.9, 40.5) + 0.286 * lobe(wavelength, 530.9, 16.3, 31.1);
+    let z =
+        1.217 * lobe(wavelength, 437.0, 11.8, 36.0) + 0.681 * lobe(wavelength, 459.0, 26.0, 13.8);
+    [x, y, z]
+}
+
+fn rgb_basis() -> [Spectrum; 3] {
+    // Long, medium and short wavelength boxes
+    let bands = [
+        Spectrum::from_fn(|wavelength| (wavelength >= 590.) as u8 as f32),
+        Spectrum::from_fn(|wavelength| (490. ..590.).contains(&wavelength) as u8 as f32),
+        Spectrum::from_fn(|wavelength| (wavelength < 490.) as u8 as f32),
+    ];
+
+    // Columns are the colors of each band
+    let colors = bands.map(Color::from);
+    let m = [
+        [colors[0].r, colors[1].r, colors[2].r],
+        [colors[0].g, colors[1].g, colors[2].g],
+        [colors[0].b, colors[1].b, colors[2].b],
+    ];
+    let inv = invert(m);
+
+    [0, 1, 2].map(|channel| {
+        bands
+            .iter()
+            .enumerate()
+            .fold(Spectrum::default(), |acc, (band, spectrum)| {
+                acc + *spectrum * inv[band][channel]
+            })
+    })
+}
+
+fn invert(m: [[f32; 3]; 3]) -> [[f32; 3]; 3] {
+    let cofactor = |row: usize, col: usize| {
+        let (r0, r1) = ((row + 1) % 3, (row + 2) % 3);
+        let (c0, c1) = ((col + 1) % 3, (col + 2) % 3);
+        m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0]
+    };
+
+    let det = (0..3).map(|col| m[0][col] * cofactor(0, col)).sum::<f32>();
+
+    let mut inv = [[0.; 3]; 3];
+    for (row, values) in inv.iter_mut().enumerate() {
+        for (col, value) in values.iter_mut().enumerate() {
+            *value = cofactor(col, row) / det;
+        }
+    }
+    inv
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_close(expected: Color, actual: Color) {
+        assert!(
+            expected.approx_eq(&actual, 1e-3),
+            "Expected {expected:?}, got {actual:?}"
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        // Smooth reddish SPD
+        let spd = Spectrum::from_fn(|wavelength| 0.2 + 0.8 * lobe(wavelength, 620., 40., 40.));
+        let color = Color::from(spd);
+
+        assert!(
+            color.r > color.g && color.r > color.b,
+            "Expected a reddish color, got {color:?}"
+        );
+        assert_close(color, Color::from(Spectrum::from(color)));
+
+        // A flat spectrum has unit luminance
+        let y = Spectrum::constant(1.).to_xyz()[1];
+        assert!((y - 1.).abs() < 1e-5, "Expected unit luminance, got {y}");
+
+        for color in [Color::new(1., 1., 1.), Color::new(0.2, 0.5, 0.9)] {
+            assert_close(color, Color::from(Spectrum::from(color)));
+        }
+    }
+}
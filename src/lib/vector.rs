@@ -5,6 +5,7 @@ use super::{color::Color, DefaultType, DEFAULT_DIM};
 use std::ops::{Add, AddAssign, Div, DivAssign, Index, IndexMut, Mul, MulAssign, Sub, SubAssign};
 
 use paste::paste;
+use rand::Rng;
 
 #[derive(PartialEq, Clone, Copy, Debug)]
 /// DIM-dimensional vector of TYPE values.
@@ -29,6 +30,18 @@ impl<const DIM: usize, TYPE> Index<usize> for Vector<DIM, TYPE> {
     }
 }
 
+impl<const DIM: usize, TYPE> Vector<DIM, TYPE> {
+    /// Get a reference to the value at `index`, or `None` if it is out of bounds.
+    pub fn get(&self, index: usize) -> Option<&TYPE> {
+        self.inner.get(index)
+    }
+
+    /// Get a mutable reference to the value at `index`, or `None` if it is out of bounds.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut TYPE> {
+        self.inner.get_mut(index)
+    }
+}
+
 impl<const DIM: usize, TYPE> IndexMut<usize> for Vector<DIM, TYPE> {
     fn index_mut(&mut self, index: usize) -> &mut Self::Output {
         &mut self.inner[index]
@@ -52,11 +65,12 @@ macro_rules! into_primitive_array {
                 }
             }
 
+            /// Missing values are filled with the default one, extra values are ignored.
             impl<const DIM: usize> From<&[$target]> for Vector<DIM, $target> {
                 fn from(value: &[$target]) -> Self {
                     let mut res = [<$target as Default>::default(); DIM];
-                    for i in 0..DIM {
-                        res[i] = value[i];
+                    for (dst, src) in res.iter_mut().zip(value) {
+                        *dst = *src;
                     }
 
                     Self {
@@ -206,6 +220,71 @@ impl<TYPE: Copy + Mul<Output = TYPE> + Sub<TYPE, Output = TYPE>> Vector<3, TYPE>
     }
 }
 
+impl<const DIM: usize, TYPE: Copy + PartialOrd> Vector<DIM, TYPE> {
+    /// Clamps all values to the [min, max] range.
+    pub fn clamp(&self, min: TYPE, max: TYPE) -> Self {
+        self.clamp_vec(&Vector { inner: [min; DIM] }, &Vector { inner: [max; DIM] })
+    }
+
+    /// Clamps each value to the range given by the matching `min` and `max` components.
+    pub fn clamp_vec(&self, min: &Self, max: &Self) -> Self {
+        let mut res = *self;
+
+        for ((v, min), max) in res.inner.iter_mut().zip(min.inner).zip(max.inner) {
+            if *v < min {
+                *v = min;
+            } else if *v > max {
+                *v = max;
+            }
+        }
+
+        res
+    }
+}
+
+impl<const DIM: usize, TYPE: Copy + Into<f32>> Vector<DIM, TYPE> {
+    /// Checks that every value is within `eps` of the matching one in `other`.
+    pub fn approx_eq(&self, other: &Self, eps: f32) -> bool {
+        self.inner
+            .iter()
+            .zip(other.inner.iter())
+            .all(|(&lhs, &rhs)| (lhs.into() - rhs.into()).abs() <= eps)
+    }
+}
+
+impl Vector<3, f32> {
+    /// Build two unit vectors forming an orthonormal basis with the unit vector `normal`,
+    /// returned as `(tangent, bitangent)`.
+    pub fn build_basis(normal: &Self) -> (Self, Self) {
+        // Branchless construction from Duff et al., "Building an Orthonormal Basis, Revisited"
+        let sign = 1f32.copysign(normal[2]);
+        let a = -1. / (sign + normal[2]);
+        let b = normal[0] * normal[1] * a;
+
+        (
+            Vector::new(
+                1. + sign * normal[0] * normal[0] * a,
+                sign * b,
+                -sign * normal[0],
+            ),
+            Vector::new(b, sign + normal[1] * normal[1] * a, -normal[1]),
+        )
+    }
+
+    /// Sample a unit direction in the hemisphere around the unit vector `normal`, with a density
+    /// proportional to the cosine of its angle to the normal.
+    pub fn sample_cosine_hemisphere(normal: &Self, rng: &mut impl Rng) -> Self {
+        // Uniform disk sample projected up onto the hemisphere (Malley's method)
+        let radius = rng.gen::<f32>().sqrt();
+        let theta = std::f32::consts::TAU * rng.gen::<f32>();
+        let (x, y) = (radius * theta.cos(), radius * theta.sin());
+        let z = (1. - x * x - y * y).max(0.).sqrt();
+
+        let (tangent, bitangent) = Self::build_basis(normal);
+        (tangent * x + bitangent * y + *normal * z).normalize()
+    }
+}
+
 impl From<Color> for Vector<3, f32> {
     fn from(Color { r, g, b }: Color) -> Self {
         Self { inner: [r, g, b] }
@@ -232,8 +311,10 @@ impl<const DIM: usize, TYPE: Default + Copy> Vector<DIM, TYPE> {
     pub fn resize<const NEW_DIM: usize>(value: Vector<DIM, TYPE>) -> Vector<NEW_DIM, TYPE> {
         let mut v = Vector::<NEW_DIM, TYPE>::default();
 
-        for i in 0..DIM.min(NEW_DIM) {
-            v[i] = value[i];
+        for i in 0..NEW_DIM {
+            if let (Some(dst), Some(src)) = (v.get_mut(i), value.get(i)) {
+                *dst = *src;
+            }
         }
 
         v
@@ -297,4 +378,81 @@ mod test {
             "Invalid angle {got}, expected {expected}"
         );
     }
+
+    #[test]
+    fn clamp() {
+        let vec = Vector::new(-0.5, 0.25, 1.5);
+
+        assert_eq!(Vector::new(0., 0.25, 1.), vec.clamp(0., 1.));
+        assert_eq!(
+            Vector::new(-0.5, 0.5, 1.),
+            vec.clamp_vec(&Vector::new(-1., 0.5, 0.), &Vector::new(0., 1., 1.))
+        );
+    }
+
+    #[test]
+    fn checked_get() {
+        let mut vec = Vector::new(1., 2., 3.);
+
+        assert_eq!(Some(&3.), vec.get(2));
+        assert_eq!(None, vec.get(5));
+        assert_eq!(None, vec.get_mut(3));
+
+        let short = Vector::<3, f32>::from(&[1., 2.][..]);
+        assert_eq!(Vector::new(1., 2., 0.), short);
+        assert_eq!(Vector::from([1., 2.]), Vector::resize::<2>(vec));
+    }
+
+    #[test]
+    fn approx_eq() {
+        let (first, second) = get_vecs();
+
+        assert!(first.approx_eq(&(first + Vector::new(1e-6, -1e-6, 0.)), 1e-5));
+        assert!(first.approx_eq(&first, 0.));
+        assert!(!first.approx_eq(&second, 1e-5));
+        assert!(!first.approx_eq(&(first + Vector::new(0., 0., 1e-3)), 1e-5));
+    }
+
+    #[test]
+    fn basis() {
+        for normal in [
+            Vector::new(0., 0., 1.),
+            Vector::new(0., 0., -1.),
+            Vector::new(1., 2., -3.).normalize(),
+        ] {
+            let (tangent, bitangent) = Vector::build_basis(&normal);
+
+            for v in [tangent, bitangent] {
+                assert_float_eq!(1., v.len(), abs <= 1e-5);
+                assert_float_eq!(0., v.dot_product(&normal), abs <= 1e-5);
+            }
+            assert_float_eq!(0., tangent.dot_product(&bitangent), abs <= 1e-5);
+        }
+    }
+
+    #[test]
+    fn cosine_hemisphere() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let normal = Vector::new(1., -1., 0.5).normalize();
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let count = 10_000;
+        let mut sum = 0.;
+        for _ in 0..count {
+            let direction = Vector::sample_cosine_hemisphere(&normal, &mut rng);
+
+            assert_float_eq!(1., direction.len(), abs <= 1e-5);
+            let cos = direction.dot_product(&normal);
+            assert!(
+                cos >= 0.,
+                "Sampled direction {direction:?} is below the hemisphere"
+            );
+            sum += cos;
+        }
+
+        // A cosine-weighted distribution has an expected cosine of 2/3, against 1/2 for a
+        // uniform one
+        assert_float_eq!(2. / 3., sum / count as f32, abs <= 0.01);
+    }
 }
@@ -0,0 +1,315 @@
+//! Minimal OpenGL viewer displaying [Object]s in a window, only available with the `viewer`
+//! feature.
+
+use crate::{object::Object, primitives::Triangle, Built};
+
+use std::{ffi::CString, sync::mpsc::Receiver};
+
+use glfw::{Context, Glfw, Window, WindowEvent};
+
+#[derive(Debug, thiserror::Error)]
+/// Possible errors returned by the [Viewer].
+pub enum Error {
+    #[error(transparent)]
+    /// GLFW could not be initialized.
+    Init(#[from] glfw::InitError),
+
+    #[error("Failed to create a window with an OpenGL context")]
+    /// Window or OpenGL context creation failed, e.g. without any display.
+    Window,
+
+    #[error("Failed to compile or link the viewer's shaders:\n{0}")]
+    /// Invalid GLSL code or linking error, along with the driver's log.
+    Shader(String),
+
+    #[error("Object conversion failed: {0}")]
+    /// Object could not be converted into a triangle list.
+    Object(&'static str),
+}
+
+const VERTEX_SHADER: &str = r#"
+#version 330 core
+
+layout (location = 0) in vec3 position;
+layout (location = 1) in vec3 normal;
+
+uniform float scale;
+
+out vec3 v_normal;
+
+void main() {
+    v_normal = normal;
+    // Orthographic view looking down -Z, like the raytracer's default camera
+    gl_Position = vec4(position.xy * scale, -position.z * scale, 1.0);
+}
+"#;
+
+const FRAGMENT_SHADER: &str = r#"
+#version 330 core
+
+in vec3 v_normal;
+
+out vec4 color;
+
+void main() {
+    float light = max(dot(normalize(v_normal), normalize(vec3(1.0, 1.0, 1.0))), 0.0);
+    color = vec4(vec3(0.2 + 0.8 * light), 1.0);
+}
+"#;
+
+/// Object uploaded to the GPU.
+struct Mesh {
+    vao: u32,
+    vbos: Vec<u32>,
+    vertex_count: i32,
+}
+
+/// Window displaying [Object]s with a basic shader, see [run](Viewer::run).
+///
+/// # Example
+/// ```no_run
+/// use eray::{prelude::*, viewer::Viewer};
+/// use std::path::Path;
+///
+/// let cube = Object::load_obj(Path::new("./objects/cube.obj")).unwrap();
+///
+/// let mut viewer = Viewer::new(800, 800, "eray").unwrap();
+/// viewer.add_object(&cube.build().unwrap()).unwrap();
+/// viewer.run();
+/// ```
+pub struct Viewer {
+    glfw: Glfw,
+    window: Window,
+    events: Receiver<(f64, WindowEvent)>,
+    program: u32,
+    meshes: Vec<Mesh>,
+    extent: f32,
+}
+
+impl Viewer {
+    /// Open a window with an OpenGL 3.3 core context.
+    pub fn new(width: u32, height: u32, title: &str) -> Result<Self, Error> {
+        Self::create(width, height, title, true)
+    }
+
+    /// Same as [new](Self::new) without showing the window, e.g. for tests.
+    pub fn hidden(width: u32, height: u32) -> Result<Self, Error> {
+        Self::create(width, height, "eray", false)
+    }
+
+    fn create(width: u32, height: u32, title: &str, visible: bool) -> Result<Self, Error> {
+        let mut glfw = glfw::init(glfw::LOG_ERRORS)?;
+
+        glfw.window_hint(glfw::WindowHint::ContextVersion(3, 3));
+        glfw.window_hint(glfw::WindowHint::OpenGlProfile(
+            glfw::OpenGlProfileHint::Core,
+        ));
+        glfw.window_hint(glfw::WindowHint::OpenGlForwardCompat(true));
+        glfw.window_hint(glfw::WindowHint::Visible(visible));
+
+        let (mut window, events) = glfw
+            .create_window(width, height, title, glfw::WindowMode::Windowed)
+            .ok_or(Error::Window)?;
+
+        window.make_current();
+        window.set_key_polling(true);
+        window.set_framebuffer_size_polling(true);
+
+        gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
+
+        let program = unsafe {
+            gl::Enable(gl::DEPTH_TEST);
+
+            link_program(&[
+                compile_shader(gl::VERTEX_SHADER, VERTEX_SHADER)?,
+                compile_shader(gl::FRAGMENT_SHADER, FRAGMENT_SHADER)?,
+            ])?
+        };
+
+        Ok(Self {
+            glfw,
+            window,
+            events,
+            program,
+            meshes: Vec::new(),
+            extent: 0.,
+        })
+    }
+
+    /// Upload an [Object]'s faces to the GPU through [Object::to_opengl].
+    pub fn add_object(&mut self, object: &Object<Built>) -> Result<&mut Self, Error> {
+        // Faces are flattened into a plain triangle list as no index buffer is used
+        let mut soup = Object::default();
+        soup.vertices(object.faces.iter().flat_map(|triangle: &Triangle| {
+            [
+                triangle.a.position,
+                triangle.b.position,
+                triangle.c.position,
+            ]
+        }))
+        .normals(object.faces.iter().flat_map(|triangle: &Triangle| {
+            [triangle.a.normal, triangle.b.normal, triangle.c.normal]
+        }));
+
+        let (_, gl_object) = soup.build().map_err(Error::Object)?.to_opengl();
+
+        self.extent = object
+            .bounding_box
+            .bounds()
+            .iter()
+            .flat_map(|corner| [corner[0], corner[1], corner[2]])
+            .fold(self.extent, |extent, value| extent.max(value.abs()));
+
+        self.meshes.push(Mesh {
+            vao: gl_object.vao,
+            vbos: std::iter::once(gl_object.vertices_vbo)
+                .chain(gl_object.normals_vbo)
+                .collect(),
+            vertex_count: (object.faces.len() * 3) as i32,
+        });
+
+        Ok(self)
+    }
+
+    /// Draw a single frame of every uploaded object.
+    pub fn draw(&mut self) {
+        let scale = if self.extent > 0. {
+            0.9 / self.extent
+        } else {
+            1.
+        };
+        let scale_name = CString::new("scale").unwrap();
+
+        unsafe {
+            gl::ClearColor(0.1, 0.1, 0.2, 1.);
+            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+
+            gl::UseProgram(self.program);
+            gl::Uniform1f(
+                gl::GetUniformLocation(self.program, scale_name.as_ptr()),
+                scale,
+            );
+
+            for mesh in self.meshes.iter() {
+                gl::BindVertexArray(mesh.vao);
+                gl::DrawArrays(gl::TRIANGLES, 0, mesh.vertex_count);
+            }
+
+            gl::BindVertexArray(0);
+        }
+
+        self.window.swap_buffers();
+    }
+
+    /// Draw frames until the window is closed or `Escape` is pressed.
+    pub fn run(&mut self) {
+        while !self.window.should_close() {
+            self.draw();
+
+            self.glfw.poll_events();
+            for (_, event) in glfw::flush_messages(&self.events) {
+                match event {
+                    WindowEvent::Key(glfw::Key::Escape, _, glfw::Action::Press, _) => {
+                        self.window.set_should_close(true)
+                    }
+                    WindowEvent::FramebufferSize(width, height) => unsafe {
+                        gl::Viewport(0, 0, width, height)
+                    },
+                    _ => (),
+                }
+            }
+        }
+    }
+}
+
+impl Drop for Viewer {
+    fn drop(&mut self) {
+        unsafe {
+            for mesh in self.meshes.iter() {
+                gl::DeleteBuffers(mesh.vbos.len() as i32, mesh.vbos.as_ptr());
+                gl::DeleteVertexArrays(1, &mesh.vao);
+            }
+            gl::DeleteProgram(self.program);
+        }
+    }
+}
+
+/// Compile a shader of the given `kind` from its GLSL `source`.
+unsafe fn compile_shader(kind: gl::types::GLenum, source: &str) -> Result<u32, Error> {
+    let shader = gl::CreateShader(kind);
+    let source = CString::new(source).map_err(|err| Error::Shader(err.to_string()))?;
+
+    gl::ShaderSource(shader, 1, &source.as_ptr(), std::ptr::null());
+    gl::CompileShader(shader);
+
+    let mut success = 0;
+    gl::GetShaderiv(shader, gl::COMPILE_STATUS, &mut success);
+    if success == 0 {
+        let mut len = 0;
+        gl::GetShaderiv(shader, gl::INFO_LOG_LENGTH, &mut len);
+
+        let mut log = vec![0u8; len.max(1) as usize];
+        gl::GetShaderInfoLog(shader, len, std::ptr::null_mut(), log.as_mut_ptr().cast());
+        gl::DeleteShader(shader);
+
+        return Err(Error::Shader(
+            String::from_utf8_lossy(&log)
+                .trim_end_matches('\0')
+                .to_owned(),
+        ));
+    }
+
+    Ok(shader)
+}
+
+/// Link compiled shaders into a program, deleting them along the way.
+unsafe fn link_program(shaders: &[u32]) -> Result<u32, Error> {
+    let program = gl::CreateProgram();
+
+    shaders
+        .iter()
+        .for_each(|&shader| gl::AttachShader(program, shader));
+    gl::LinkProgram(program);
+    shaders.iter().for_each(|&shader| gl::DeleteShader(shader));
+
+    let mut success = 0;
+    gl::GetProgramiv(program, gl::LINK_STATUS, &mut success);
+    if success == 0 {
+        let mut len = 0;
+        gl::GetProgramiv(program, gl::INFO_LOG_LENGTH, &mut len);
+
+        let mut log = vec![0u8; len.max(1) as usize];
+        gl::GetProgramInfoLog(program, len, std::ptr::null_mut(), log.as_mut_ptr().cast());
+        gl::DeleteProgram(program);
+
+        return Err(Error::Shader(
+            String::from_utf8_lossy(&log)
+                .trim_end_matches('\0')
+                .to_owned(),
+        ));
+    }
+
+    Ok(program)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    #[ignore = "needs a display, run with `cargo test --features viewer -- --ignored`"]
+    fn upload() {
+        let cube = Object::load_obj(Path::new("./objects/cube.obj"))
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let mut viewer = Viewer::hidden(64, 64).unwrap();
+        viewer.add_object(&cube).unwrap();
+        viewer.draw();
+
+        assert!(viewer.meshes[0].vbos.iter().all(|&vbo| vbo != 0));
+        assert_eq!(gl::NO_ERROR, unsafe { gl::GetError() });
+    }
+}
@@ -39,9 +39,9 @@ fn main() -> std::io::Result<()> {
         // Mixing
         .set_input(&"factor".into(), SocketValue::Value(Some(0.5)))
         .unwrap();
-    cube.material.update().unwrap();
+    cube.material.update(0.).unwrap();
 
-    let mut engine = Engine::new((1024, 1024), 0, 0);
+    let mut engine = Engine::new((1024, 1024), 0, 0).unwrap();
     engine
         .scene()
         .set_camera(Camera {
@@ -50,19 +50,11 @@ fn main() -> std::io::Result<()> {
             width: 1024,
             ..Default::default()
         })
-        .add_light(Light {
-            transform: Transform::default().apply_translation(Vector::new(0., 2., 0.)),
-            variant: LightVariant::Ambient,
-            color: Color::new(1., 1., 1.),
-            brightness: 0.2,
-        })
-        .add_light(Light {
-            transform: Transform::default().apply_translation(Vector::new(1., 1., 2.)),
-            variant: LightVariant::Point,
-            color: Color::new(1., 1., 1.),
-            brightness: 1.,
-        })
-        .add_object(cube.build().unwrap());
+        .add_light(Light::ambient().brightness(0.2))
+        .add_light(Light::point(Vector::new(1., 1., 2.)))
+        .add_object(cube.build().unwrap())
+        .validate()
+        .unwrap();
 
     #[cfg(not(debug_assertions))]
     {
@@ -78,7 +70,7 @@ fn main() -> std::io::Result<()> {
 }
 
 fn material() -> Result<Material, eray::shader::graph::Error> {
-    Ok(Material::from((
+    Material::try_from((
         eray::shader::graph::graph! {
             inputs:
                 // Mandatory
@@ -140,5 +132,5 @@ fn material() -> Result<Material, eray::shader::graph::Error> {
             StandardMaterialOutput::Color => "color".into(),
             StandardMaterialOutput::Diffuse => "diffuse".into(),
         },
-    )))
+    ))
 }
@@ -0,0 +1,151 @@
+//! Adjust the brightness and contrast of a [Color image](SocketType::IColor), pivoting around
+//! mid-grey.
+//!
+//! Mandatory inputs:
+//! - color: IColor
+//!
+//! Optional inputs:
+//! - brightness: Value, offset added to every channel, default is `DEFAULT_BRIGHTNESS`
+//! - contrast: Value, scale of the distance to mid-grey, default is `DEFAULT_CONTRAST`
+//!
+//! Output:
+//! - color: IColor, clamped to the 0..=1 range
+
+use crate::handle_missing_socket_values;
+
+use super::{GraphResult, NodeResult};
+
+use eray::{
+    get_sv, node,
+    prelude::*,
+    shader::{
+        self,
+        graph::{SocketType, SocketValue},
+    },
+    ssref,
+};
+
+pub const DEFAULT_BRIGHTNESS: f32 = 0.;
+pub const DEFAULT_CONTRAST: f32 = 1.;
+
+/// Apply `brightness` and `contrast` to a single channel.
+fn adjust(value: f32, brightness: f32, contrast: f32) -> f32 {
+    ((value - 0.5) * contrast + 0.5 + brightness).clamp(0., 1.)
+}
+
+/// Get a wrapping [Graph](eray::shader::graph::Graph) containing the node.
+pub fn graph() -> GraphResult {
+    Ok(shader::graph::graph! {
+        inputs:
+            // Mandatory
+            "color": SocketType::IColor.into(),
+
+            // Optional
+            "brightness": SocketValue::Value(Some(DEFAULT_BRIGHTNESS)),
+            "contrast": SocketValue::Value(Some(DEFAULT_CONTRAST)),
+        nodes:
+            "bright_contrast": {
+                let mut node = node()?;
+                node.set_input(&"color".into(), ssref!(graph "color"))?
+                    .set_input(&"brightness".into(), ssref!(graph "brightness"))?
+                    .set_input(&"contrast".into(), ssref!(graph "contrast"))?;
+                node
+            },
+        outputs:
+            "color": (ssref!(node "bright_contrast" "color"), SocketType::IColor.into()),
+    })
+}
+
+/// Get the [node](eray::shader::graph::Node::Graph) by itself.
+pub fn node() -> NodeResult {
+    Ok(node! {
+        inputs:
+            "color": (None, SocketType::IColor),
+
+            "brightness": (None, SocketType::Value),
+            "contrast": (None, SocketType::Value),
+        outputs:
+            "color": SocketType::IColor.into();
+        |inputs, outputs| {
+            get_sv!( input | inputs  . "color": IColor > color);
+
+            get_sv!( input | inputs  . "brightness": Value > brightness);
+            get_sv!( input | inputs  . "contrast": Value > contrast);
+
+            get_sv!(output | outputs . "color": IColor > out);
+
+            handle_missing_socket_values![color];
+            let brightness = brightness.unwrap_or(DEFAULT_BRIGHTNESS);
+            let contrast = contrast.unwrap_or(DEFAULT_CONTRAST);
+
+            out.replace(color.map(|pixel| Color::new(
+                adjust(pixel.r, brightness, contrast),
+                adjust(pixel.g, brightness, contrast),
+                adjust(pixel.b, brightness, contrast),
+            )));
+
+            Ok(())
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::shaderlib::utils::run_graph;
+
+    fn gradient() -> Image<Color> {
+        Image {
+            width: 256,
+            height: 1,
+            pixels: (0..=255u32)
+                .map(|v| [v, 255 - v, (v * 7) % 256].map(|v| v as f32 / 255.))
+                .map(|[r, g, b]| Color::new(r, g, b))
+                .collect(),
+        }
+    }
+
+    fn run(color: Image<Color>, brightness: f32, contrast: f32) -> Image<Color> {
+        let inputs = [
+            ("color", SocketValue::IColor(Some(color))),
+            ("brightness", SocketValue::Value(Some(brightness))),
+            ("contrast", SocketValue::Value(Some(contrast))),
+        ];
+
+        let SocketValue::IColor(Some(image)) = run_graph(graph().unwrap(), inputs, "color") else {
+            panic!("Missing bright_contrast output")
+        };
+
+        image
+    }
+
+    #[test]
+    fn identity() {
+        let input = gradient();
+        let res = run(input.clone(), 0., 1.);
+
+        for (got, expected) in res.pixels.iter().zip(input.pixels.iter()) {
+            assert!(
+                got.approx_eq(expected, 1e-6),
+                "Expected {expected:?}, got {got:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn contrast_separates_from_mid_grey() {
+        let input = gradient();
+        let res = run(input.clone(), 0., 2.);
+
+        for (got, original) in res.pixels.iter().zip(input.pixels.iter()) {
+            let distance = |c: &Color| [c.r, c.g, c.b].map(|v| (v - 0.5).abs());
+
+            for (got, original) in distance(got).into_iter().zip(distance(original)) {
+                assert!(got >= original, "Expected {got} to be at least {original}");
+            }
+        }
+
+        let (dark, bright) = (res.pixels[64].r, res.pixels[192].r);
+        assert!(bright - dark > input.pixels[192].r - input.pixels[64].r);
+    }
+}
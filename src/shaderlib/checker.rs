@@ -0,0 +1,159 @@
+//! Checkerboard pattern alternating between 0 and 1.
+//!
+//! Mandatory inputs:
+//! - width: Value, width of the output image
+//! - height: Value, height of the output image
+//!
+//! Optional inputs:
+//! - cells: Value, number of cells along each axis, rounded up to an even count so that the
+//!   pattern tiles, default is `DEFAULT_CELLS`
+//!
+//! Output:
+//! - value: IValue
+
+use crate::handle_missing_socket_values;
+
+use super::{GraphResult, NodeResult};
+
+use eray::{
+    get_sv, node,
+    prelude::*,
+    shader::{
+        self,
+        graph::{SocketType, SocketValue},
+    },
+    ssref,
+};
+
+pub const DEFAULT_CELLS: f32 = 8.;
+
+/// Get a wrapping [Graph](eray::shader::graph::Graph) containing the node.
+pub fn graph() -> GraphResult {
+    Ok(shader::graph::graph! {
+        inputs:
+            // Mandatory
+            "width": SocketType::Value.into(),
+            "height": SocketType::Value.into(),
+
+            // Optional
+            "cells": SocketValue::Value(Some(DEFAULT_CELLS)),
+        nodes:
+            "checker": {
+                let mut node = node()?;
+                node.set_input(&"width".into(), ssref!(graph "width"))?
+                    .set_input(&"height".into(), ssref!(graph "height"))?
+                    .set_input(&"cells".into(), ssref!(graph "cells"))?;
+                node
+            },
+        outputs:
+            "value": (ssref!(node "checker" "value"), SocketType::IValue.into()),
+    })
+}
+
+/// Get the [node](eray::shader::graph::Node::Graph) by itself.
+pub fn node() -> NodeResult {
+    Ok(node! {
+        inputs:
+            "width": (None, SocketType::Value),
+            "height": (None, SocketType::Value),
+
+            "cells": (None, SocketType::Value),
+        outputs:
+            "value": SocketType::IValue.into();
+        |inputs, outputs| {
+            get_sv!( input | inputs  . "width": Value > width);
+            get_sv!( input | inputs  . "height": Value > height);
+
+            get_sv!( input | inputs  . "cells": Value > cells);
+
+            get_sv!(output | outputs . "value": IValue > out);
+
+            handle_missing_socket_values![width, height];
+            let cells = ((cells.unwrap_or(DEFAULT_CELLS) / 2.).ceil() * 2.).max(2.);
+
+            let (width, height) = (*width as u32, *height as u32);
+            let mut res = Image::new(width, height, 0.);
+
+            for (x, y, pixel) in res.enumerate_pixels_mut() {
+                let cell_x = (x as f32 * cells / width as f32) as u32;
+                let cell_y = (y as f32 * cells / height as f32) as u32;
+                *pixel = ((cell_x + cell_y) % 2) as f32;
+            }
+
+            out.replace(res);
+
+            Ok(())
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::shaderlib::utils::{assert_tiles_seamlessly, run_graph};
+
+    fn run(cells: f32) -> Image<f32> {
+        let inputs = [("width", 16.), ("height", 8.), ("cells", cells)]
+            .map(|(name, value)| (name, SocketValue::Value(Some(value))));
+
+        let SocketValue::IValue(Some(image)) = run_graph(graph().unwrap(), inputs, "value") else {
+            panic!("Missing checker output")
+        };
+
+        image
+    }
+
+    #[test]
+    fn alternates() {
+        let image = run(4.);
+
+        // 4 by 2 pixel cells
+        assert_eq!(image.mod_get(0, 0), 0.);
+        assert_eq!(image.mod_get(3, 1), 0.);
+        assert_eq!(image.mod_get(4, 0), 1.);
+        assert_eq!(image.mod_get(0, 2), 1.);
+        assert_eq!(image.mod_get(4, 2), 0.);
+    }
+
+    #[test]
+    fn tiles_seamlessly() {
+        for cells in [2., 3., 4.] {
+            let image = run(cells);
+            assert_tiles_seamlessly(&image.clone().into(), 0.);
+
+            // Odd counts would put two same-valued cells side by side across the seam
+            assert_ne!(image.mod_get(0, 0), image.mod_get(15, 0));
+        }
+    }
+
+    #[test]
+    fn eval_eray() {
+        use eray::shader::eval::eval_eray_to_image;
+        use std::{collections::HashMap, path::Path};
+
+        let mut loaded = HashMap::<_, Vec<_>>::new();
+        for node in crate::shaderlib::SHADERLIB.iter() {
+            loaded
+                .entry(node.name().clone())
+                .or_default()
+                .push(node.clone());
+        }
+
+        let inputs = [("width", 4.), ("height", 4.), ("cells", 2.)]
+            .map(|(name, value)| (name.into(), SocketValue::Value(Some(value))));
+        let image = eval_eray_to_image(
+            Path::new("./nodes/checker.eray"),
+            inputs.into(),
+            8,
+            8,
+            &mut loaded,
+        )
+        .unwrap();
+
+        // Upscaled 4 by 4 checker of 2 by 2 texel cells
+        for (x, y, &pixel) in image.enumerate_pixels() {
+            let expected = ((x / 4 + y / 4) % 2) as f32;
+            assert_eq!(Color::from(expected), pixel, "at ({x}, {y})");
+        }
+    }
+}
@@ -0,0 +1,115 @@
+//! Map a [Value image](SocketType::IValue) to colors by linearly interpolating between two
+//! colors, e.g. to tint noise.
+//!
+//! Mandatory inputs:
+//! - value: IValue, also gives the output dimensions
+//!
+//! Optional inputs:
+//! - low: Color, color at a value of 0 and below, default is black
+//! - high: Color, color at a value of 1 and above, default is white
+//!
+//! Output:
+//! - color: IColor
+
+use crate::handle_missing_socket_values;
+
+use super::{GraphResult, NodeResult};
+
+use eray::{
+    get_sv, node,
+    prelude::*,
+    shader::{
+        self,
+        graph::{SocketType, SocketValue},
+    },
+    ssref,
+};
+
+/// Get a wrapping [Graph](eray::shader::graph::Graph) containing the node.
+pub fn graph() -> GraphResult {
+    Ok(shader::graph::graph! {
+        inputs:
+            // Mandatory
+            "value": SocketType::IValue.into(),
+
+            // Optional
+            "low": SocketValue::Color(Some(Color::new(0., 0., 0.))),
+            "high": SocketValue::Color(Some(Color::new(1., 1., 1.))),
+        nodes:
+            "ramp": {
+                let mut node = node()?;
+                node.set_input(&"value".into(), ssref!(graph "value"))?
+                    .set_input(&"low".into(), ssref!(graph "low"))?
+                    .set_input(&"high".into(), ssref!(graph "high"))?;
+                node
+            },
+        outputs:
+            "color": (ssref!(node "ramp" "color"), SocketType::IColor.into()),
+    })
+}
+
+/// Get the [node](eray::shader::graph::Node::Graph) by itself.
+pub fn node() -> NodeResult {
+    Ok(node! {
+        inputs:
+            "value": (None, SocketType::IValue),
+
+            "low": (None, SocketType::Color),
+            "high": (None, SocketType::Color),
+        outputs:
+            "color": SocketType::IColor.into();
+        |inputs, outputs| {
+            get_sv!( input | inputs  . "value": IValue > value);
+
+            get_sv!( input | inputs  . "low": Color > low);
+            get_sv!( input | inputs  . "high": Color > high);
+
+            get_sv!(output | outputs . "color": IColor > out);
+
+            handle_missing_socket_values![value];
+            let low = low.unwrap_or(Color::new(0., 0., 0.));
+            let high = high.unwrap_or(Color::new(1., 1., 1.));
+
+            out.replace(value.map(|&value| {
+                let t = value.clamp(0., 1.);
+                low * (1. - t) + high * t
+            }));
+
+            Ok(())
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::shaderlib::utils::run_graph;
+
+    #[test]
+    fn interpolates() {
+        let value = Image {
+            width: 4,
+            height: 1,
+            pixels: vec![-1., 0., 0.5, 2.],
+        };
+        let inputs = [
+            ("value", SocketValue::IValue(Some(value))),
+            ("low", SocketValue::Color(Some(Color::new(1., 0., 0.)))),
+            ("high", SocketValue::Color(Some(Color::new(0., 0., 1.)))),
+        ];
+
+        let SocketValue::IColor(Some(image)) = run_graph(graph().unwrap(), inputs, "color") else {
+            panic!("Missing color_ramp output")
+        };
+
+        assert_eq!(
+            image.pixels,
+            vec![
+                Color::new(1., 0., 0.),
+                Color::new(1., 0., 0.),
+                Color::new(0.5, 0., 0.5),
+                Color::new(0., 0., 1.),
+            ]
+        );
+    }
+}
@@ -0,0 +1,124 @@
+//! Pack two [Value images](SocketType::IValue) into a [Vec2 image](SocketType::IVec2), see
+//! [separate_vec2](super::separate_vec2) for the reverse operation.
+//!
+//! Mandatory inputs:
+//! - x: IValue, also gives the output dimensions
+//! - y: IValue, tiled over the output
+//!
+//! Output:
+//! - vec: IVec2
+
+use crate::handle_missing_socket_values;
+
+use super::{GraphResult, NodeResult};
+
+use eray::{
+    get_sv, node,
+    prelude::*,
+    shader::{self, graph::SocketType},
+    ssref,
+};
+
+/// Get a wrapping [Graph](eray::shader::graph::Graph) containing the node.
+pub fn graph() -> GraphResult {
+    Ok(shader::graph::graph! {
+        inputs:
+            // Mandatory
+            "x": SocketType::IValue.into(),
+            "y": SocketType::IValue.into(),
+        nodes:
+            "combine": {
+                let mut node = node()?;
+                node.set_input(&"x".into(), ssref!(graph "x"))?
+                    .set_input(&"y".into(), ssref!(graph "y"))?;
+                node
+            },
+        outputs:
+            "vec": (ssref!(node "combine" "vec"), SocketType::IVec2.into()),
+    })
+}
+
+/// Get the [node](eray::shader::graph::Node::Graph) by itself.
+pub fn node() -> NodeResult {
+    Ok(node! {
+        inputs:
+            "x": (None, SocketType::IValue),
+            "y": (None, SocketType::IValue),
+        outputs:
+            "vec": SocketType::IVec2.into();
+        |inputs, outputs| {
+            get_sv!( input | inputs  . "x": IValue > x);
+            get_sv!( input | inputs  . "y": IValue > y);
+
+            get_sv!(output | outputs . "vec": IVec2 > out);
+
+            handle_missing_socket_values![x, y];
+
+            let mut res = Image::new(x.width, x.height, Vector::<2, f32>::default());
+            for row in 0..res.height {
+                for col in 0..res.width {
+                    res.set(col, row, Vector::from([x.mod_get(col, row), y.mod_get(col, row)]));
+                }
+            }
+
+            out.replace(res);
+
+            Ok(())
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use eray::shader::graph::SocketValue;
+
+    use super::{super::separate_vec2, *};
+
+    #[test]
+    fn round_trip() {
+        let x = Image {
+            width: 2,
+            height: 2,
+            pixels: vec![0., 0.25, 0.5, 0.75],
+        };
+        let y = Image {
+            width: 2,
+            height: 2,
+            pixels: vec![1., 0.9, 0.8, 0.7],
+        };
+
+        let mut graph = shader::graph::graph! {
+            inputs:
+                "x": SocketValue::IValue(Some(x.clone())),
+                "y": SocketValue::IValue(Some(y.clone())),
+            nodes:
+                "combine": {
+                    let mut node = node().unwrap();
+                    node.set_input(&"x".into(), ssref!(graph "x")).unwrap()
+                        .set_input(&"y".into(), ssref!(graph "y")).unwrap();
+                    node
+                },
+                "separate": {
+                    let mut node = separate_vec2::node().unwrap();
+                    node.set_input(&"vec".into(), ssref!(node "combine" "vec")).unwrap();
+                    node
+                },
+            outputs:
+                "x": (ssref!(node "separate" "x"), SocketType::IValue.into()),
+                "y": (ssref!(node "separate" "y"), SocketType::IValue.into()),
+        }
+        .validate()
+        .unwrap();
+        graph.run().unwrap();
+
+        let Some((_, SocketValue::IValue(Some(out_x)))) = graph.outputs.remove(&"x".into()) else {
+            panic!("Missing x output")
+        };
+        let Some((_, SocketValue::IValue(Some(out_y)))) = graph.outputs.remove(&"y".into()) else {
+            panic!("Missing y output")
+        };
+
+        assert_eq!(x, out_x);
+        assert_eq!(y, out_y);
+    }
+}
@@ -0,0 +1,208 @@
+//! Fractal Brownian motion, i.e. several octaves of Perlin noise summed together.
+//!
+//! Mandatory inputs:
+//! - width: Value, width of the output image
+//! - height: Value, height of the output image
+//!
+//! Optional inputs:
+//! - scale: Value, size in pixels of the base octave's noise cells, default is `DEFAULT_SCALE`
+//! - octaves: Value, number of summed octaves, default is `DEFAULT_OCTAVES`
+//! - lacunarity: Value, frequency multiplier between octaves, default is `DEFAULT_LACUNARITY`
+//! - gain: Value, amplitude multiplier between octaves, default is `DEFAULT_GAIN`
+//!
+//! Cell sizes are adjusted to fit a whole number of cells in the image so that it tiles.
+//!
+//! Output:
+//! - value: IValue, normalized to the 0..=1 range
+
+use crate::handle_missing_socket_values;
+
+use super::{perlin, GraphResult, NodeResult};
+
+use eray::{
+    get_sv, node,
+    prelude::*,
+    shader::{
+        self,
+        graph::{SocketType, SocketValue},
+    },
+    ssref,
+};
+
+pub const DEFAULT_SCALE: f32 = 32.;
+pub const DEFAULT_OCTAVES: f32 = 4.;
+pub const DEFAULT_LACUNARITY: f32 = 2.;
+pub const DEFAULT_GAIN: f32 = 0.5;
+
+/// Get a wrapping [Graph](eray::shader::graph::Graph) containing the node.
+pub fn graph() -> GraphResult {
+    Ok(shader::graph::graph! {
+        inputs:
+            // Mandatory
+            "width": SocketType::Value.into(),
+            "height": SocketType::Value.into(),
+
+            // Optional
+            "scale": SocketValue::Value(Some(DEFAULT_SCALE)),
+            "octaves": SocketValue::Value(Some(DEFAULT_OCTAVES)),
+            "lacunarity": SocketValue::Value(Some(DEFAULT_LACUNARITY)),
+            "gain": SocketValue::Value(Some(DEFAULT_GAIN)),
+        nodes:
+            "fbm": {
+                let mut node = node()?;
+                node.set_input(&"width".into(), ssref!(graph "width"))?
+                    .set_input(&"height".into(), ssref!(graph "height"))?
+                    .set_input(&"scale".into(), ssref!(graph "scale"))?
+                    .set_input(&"octaves".into(), ssref!(graph "octaves"))?
+                    .set_input(&"lacunarity".into(), ssref!(graph "lacunarity"))?
+                    .set_input(&"gain".into(), ssref!(graph "gain"))?;
+                node
+            },
+        outputs:
+            "value": (ssref!(node "fbm" "value"), SocketType::IValue.into()),
+    })
+}
+
+/// Get the [node](eray::shader::graph::Node::Graph) by itself.
+pub fn node() -> NodeResult {
+    Ok(node! {
+        inputs:
+            "width": (None, SocketType::Value),
+            "height": (None, SocketType::Value),
+
+            "scale": (None, SocketType::Value),
+            "octaves": (None, SocketType::Value),
+            "lacunarity": (None, SocketType::Value),
+            "gain": (None, SocketType::Value),
+        outputs:
+            "value": SocketType::IValue.into();
+        |inputs, outputs| {
+            get_sv!( input | inputs  . "width": Value > width);
+            get_sv!( input | inputs  . "height": Value > height);
+
+            get_sv!( input | inputs  . "scale": Value > scale);
+            get_sv!( input | inputs  . "octaves": Value > octaves);
+            get_sv!( input | inputs  . "lacunarity": Value > lacunarity);
+            get_sv!( input | inputs  . "gain": Value > gain);
+
+            get_sv!(output | outputs . "value": IValue > out);
+
+            handle_missing_socket_values![width, height];
+            let scale = scale.unwrap_or(DEFAULT_SCALE);
+            let octaves = octaves.unwrap_or(DEFAULT_OCTAVES).max(1.) as usize;
+            let lacunarity = lacunarity.unwrap_or(DEFAULT_LACUNARITY);
+            let gain = gain.unwrap_or(DEFAULT_GAIN);
+
+            let (width, height) = (*width as u32, *height as u32);
+            let mut res = Image::new(width, height, 0.);
+
+            // Number of noise cells along each axis for every octave
+            let cells = (0..octaves)
+                .scan(1. / scale, |frequency, _| {
+                    let fit = |size: u32| (size as f32 * *frequency).round().max(1.) as u32;
+                    let cells = (fit(width), fit(height));
+                    *frequency *= lacunarity;
+                    Some(cells)
+                })
+                .collect::<Vec<(u32, u32)>>();
+
+            for (x, y, pixel) in res.enumerate_pixels_mut() {
+                let mut amplitude = 1.;
+                let (mut sum, mut total) = (0., 0.);
+
+                for &(x_cells, y_cells) in cells.iter() {
+                    let (u, v) = (
+                        x as f32 * x_cells as f32 / width as f32,
+                        y as f32 * y_cells as f32 / height as f32,
+                    );
+                    sum += amplitude * perlin::sample(u, v, (x_cells, y_cells));
+                    total += amplitude;
+
+                    amplitude *= gain;
+                }
+
+                *pixel = (sum / total) * 0.5 + 0.5;
+            }
+
+            out.replace(res);
+
+            Ok(())
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::shaderlib::utils::{assert_tiles_seamlessly, run_graph};
+
+    fn render(octaves: f32) -> Image<f32> {
+        let inputs = [
+            ("width", 64.),
+            ("height", 64.),
+            ("scale", 16.),
+            ("octaves", octaves),
+        ]
+        .map(|(name, value)| (name, SocketValue::Value(Some(value))));
+
+        let SocketValue::IValue(Some(image)) = run_graph(graph().unwrap(), inputs, "value") else {
+            panic!("Missing fbm output")
+        };
+
+        image
+    }
+
+    /// Mean squared difference between horizontally adjacent pixels.
+    fn small_scale_variance(image: &Image<f32>) -> f32 {
+        let diffs = image
+            .pixels
+            .chunks(image.width as usize)
+            .flat_map(|row| row.windows(2).map(|pair| (pair[1] - pair[0]).powi(2)))
+            .collect::<Vec<f32>>();
+
+        diffs.iter().sum::<f32>() / diffs.len() as f32
+    }
+
+    fn correlation(lhs: &Image<f32>, rhs: &Image<f32>) -> f32 {
+        let mean =
+            |image: &Image<f32>| image.pixels.iter().sum::<f32>() / image.pixels.len() as f32;
+        let (lmean, rmean) = (mean(lhs), mean(rhs));
+
+        let (mut cov, mut lvar, mut rvar) = (0., 0., 0.);
+        for (l, r) in lhs.pixels.iter().zip(rhs.pixels.iter()) {
+            cov += (l - lmean) * (r - rmean);
+            lvar += (l - lmean).powi(2);
+            rvar += (r - rmean).powi(2);
+        }
+
+        cov / (lvar * rvar).sqrt()
+    }
+
+    #[test]
+    fn tiles_seamlessly() {
+        let inputs = [("width", 64.), ("height", 48.), ("scale", 20.)]
+            .map(|(name, value)| (name, SocketValue::Value(Some(value))));
+
+        let SocketValue::IValue(Some(image)) = run_graph(graph().unwrap(), inputs, "value") else {
+            panic!("Missing fbm output")
+        };
+
+        assert_tiles_seamlessly(&image.into(), 1e-5);
+    }
+
+    #[test]
+    fn octaves_add_detail() {
+        let (base, detailed) = (render(1.), render(4.));
+
+        assert!(
+            small_scale_variance(&detailed) > small_scale_variance(&base),
+            "Expected more octaves to add high-frequency detail"
+        );
+
+        let correlation = correlation(&base, &detailed);
+        assert!(
+            correlation > 0.7,
+            "Expected the base frequency to remain, correlation is {correlation}"
+        );
+    }
+}
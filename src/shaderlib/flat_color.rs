@@ -28,12 +28,12 @@ use eray::{
 use map_macro::hash_map;
 
 pub fn material() -> MaterialResult {
-    Ok(Material::from((
+    Material::try_from((
         graph()?.validate()?,
         hash_map! {
             StandardMaterialOutput::Color => "color".into()
         },
-    )))
+    ))
 }
 
 pub fn graph() -> GraphResult {
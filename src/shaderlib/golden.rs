@@ -0,0 +1,153 @@
+//! Golden-image regression tests, baking shaderlib nodes to small fixed images compared against
+//! committed references in `src/shaderlib/golden/`.
+//!
+//! Run the tests with the `ERAY_BLESS` environment variable set to regenerate the references
+//! after an intentional change.
+
+use std::path::PathBuf;
+
+use eray::{prelude::*, shader::graph::SocketValue};
+
+use super::{utils::run_graph, GraphResult};
+
+/// Width and height of the baked images.
+const SIZE: u32 = 16;
+/// Highest accepted mean squared error, leaving room for the references' 8-bit quantization.
+const MAX_MSE: f64 = 1e-4;
+
+fn reference_path(name: &str) -> PathBuf {
+    [env!("CARGO_MANIFEST_DIR"), "src", "shaderlib", "golden"]
+        .iter()
+        .collect::<PathBuf>()
+        .join(format!("{name}.png"))
+}
+
+/// Run a node's graph with the given inputs and get one of its outputs as a [Color] image.
+fn bake(graph: GraphResult, inputs: Vec<(&str, SocketValue)>, output: &str) -> Image<Color> {
+    match run_graph(graph.unwrap(), inputs, output) {
+        SocketValue::IColor(Some(image)) => image,
+        SocketValue::IValue(Some(image)) => image.into(),
+        other => panic!("Expected an image output `{output}`, got {other:?}"),
+    }
+}
+
+/// Compare a baked image with its reference, or overwrite the reference when blessing.
+fn check(name: &str, image: Image<Color>) {
+    let path = reference_path(name);
+
+    if std::env::var_os("ERAY_BLESS").is_some() {
+        image.save_as_png(&path).unwrap();
+        return;
+    }
+
+    let reference = Image::load(&path).unwrap_or_else(|err| {
+        panic!("Missing reference {path:?} ({err}), run with ERAY_BLESS=1 to create it")
+    });
+    let (_, mse) = image.diff(&reference);
+
+    assert!(
+        mse <= MAX_MSE,
+        "`{name}` differs from its reference with an MSE of {mse} (max {MAX_MSE})"
+    );
+}
+
+fn value(value: f32) -> SocketValue {
+    SocketValue::Value(Some(value))
+}
+
+fn gradient(horizontal: bool) -> Image<f32> {
+    let mut image = Image::new(SIZE, SIZE, 0.);
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            image.set(
+                x,
+                y,
+                if horizontal { x } else { y } as f32 / (SIZE - 1) as f32,
+            );
+        }
+    }
+    image
+}
+
+#[test]
+fn wave() {
+    let image = bake(
+        super::wave::graph(),
+        vec![
+            ("width", value(SIZE as f32)),
+            ("height", value(SIZE as f32)),
+            ("x_fac", value(2.)),
+            ("time", value(0.5)),
+        ],
+        "value",
+    );
+    check("wave", image);
+}
+
+#[test]
+fn rgb() {
+    let image = bake(
+        super::rgb::graph(),
+        vec![
+            ("width", value(SIZE as f32)),
+            ("height", value(SIZE as f32)),
+            ("red", SocketValue::IValue(Some(gradient(true)))),
+            ("green", SocketValue::IValue(Some(gradient(false)))),
+            (
+                "blue",
+                SocketValue::IValue(Some(Image::new(SIZE, SIZE, 0.25))),
+            ),
+        ],
+        "color",
+    );
+    check("rgb", image);
+}
+
+#[test]
+fn color_ramp() {
+    let image = bake(
+        super::color_ramp::graph(),
+        vec![
+            ("value", SocketValue::IValue(Some(gradient(true)))),
+            ("low", SocketValue::Color(Some(Color::new(0.8, 0.2, 0.1)))),
+            ("high", SocketValue::Color(Some(Color::new(0.1, 0.3, 0.9)))),
+        ],
+        "color",
+    );
+    check("color_ramp", image);
+}
+
+#[test]
+fn mix_color() {
+    let left = gradient(true).into();
+    let right = Image::new(SIZE, SIZE, Color::new(0.1, 0.8, 0.3));
+
+    let image = bake(
+        super::mix_color::graph(),
+        vec![
+            ("width", value(SIZE as f32)),
+            ("height", value(SIZE as f32)),
+            ("left", SocketValue::IColor(Some(left))),
+            ("right", SocketValue::IColor(Some(right))),
+            ("factor", value(0.25)),
+        ],
+        "color",
+    );
+    check("mix_color", image);
+}
+
+#[test]
+fn flat_color() {
+    let image = bake(
+        super::flat_color::graph(),
+        vec![
+            ("width", value(SIZE as f32)),
+            ("height", value(SIZE as f32)),
+            ("red", value(0.9)),
+            ("green", value(0.4)),
+            ("blue", value(0.1)),
+        ],
+        "color",
+    );
+    check("flat_color", image);
+}
@@ -0,0 +1,106 @@
+//! Convert a [Color image](SocketType::IColor) to its Rec. 709 luminance.
+//!
+//! Mandatory inputs:
+//! - color: IColor
+//!
+//! Outputs:
+//! - value: IValue, luminance
+//! - color: IColor, desaturated color with every channel set to the luminance
+
+use crate::handle_missing_socket_values;
+
+use super::{GraphResult, NodeResult};
+
+use eray::{
+    get_sv, node,
+    prelude::*,
+    shader::{self, graph::SocketType},
+    ssref,
+};
+
+/// Rec. 709 luminance of a linear [Color].
+pub fn luminance(color: &Color) -> f32 {
+    color.luminance()
+}
+
+/// Get a wrapping [Graph](eray::shader::graph::Graph) containing the node.
+pub fn graph() -> GraphResult {
+    Ok(shader::graph::graph! {
+        inputs:
+            // Mandatory
+            "color": SocketType::IColor.into(),
+        nodes:
+            "grayscale": {
+                let mut node = node()?;
+                node.set_input(&"color".into(), ssref!(graph "color"))?;
+                node
+            },
+        outputs:
+            "value": (ssref!(node "grayscale" "value"), SocketType::IValue.into()),
+            "color": (ssref!(node "grayscale" "color"), SocketType::IColor.into()),
+    })
+}
+
+/// Get the [node](eray::shader::graph::Node::Graph) by itself.
+pub fn node() -> NodeResult {
+    Ok(node! {
+        inputs:
+            "color": (None, SocketType::IColor),
+        outputs:
+            "value": SocketType::IValue.into(),
+            "color": SocketType::IColor.into();
+        |inputs, outputs| {
+            get_sv!( input | inputs  . "color": IColor > color);
+
+            handle_missing_socket_values![color];
+            let value = color.map(luminance);
+
+            get_sv!(output | outputs . "color": IColor > out_color);
+            out_color.replace(value.map(|&v| Color::from(v)));
+
+            get_sv!(output | outputs . "value": IValue > out_value);
+            out_value.replace(value);
+
+            Ok(())
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use eray::shader::graph::SocketValue;
+
+    use super::*;
+
+    #[test]
+    fn rec_709_luminance() {
+        let mut graph = graph().unwrap();
+        graph.inputs.insert(
+            "color".into(),
+            SocketValue::IColor(Some(Image {
+                width: 3,
+                height: 1,
+                pixels: vec![
+                    Color::new(1., 0., 0.),
+                    Color::new(0., 1., 0.),
+                    Color::new(0., 0., 1.),
+                ],
+            })),
+        );
+
+        let mut graph = graph.validate().unwrap();
+        graph.run().unwrap();
+
+        let Some((_, SocketValue::IValue(Some(value)))) = graph.outputs.remove(&"value".into())
+        else {
+            panic!("Missing grayscale value output")
+        };
+        assert_eq!(vec![0.2126, 0.7152, 0.0722], value.pixels);
+
+        let Some((_, SocketValue::IColor(Some(color)))) = graph.outputs.remove(&"color".into())
+        else {
+            panic!("Missing grayscale color output")
+        };
+        assert_eq!(Color::new(0.2126, 0.2126, 0.2126), color.pixels[0]);
+    }
+}
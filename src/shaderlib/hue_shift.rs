@@ -0,0 +1,140 @@
+//! Rotate the hue of a [Color image](SocketType::IColor) and scale its saturation and value.
+//!
+//! Mandatory inputs:
+//! - color: IColor
+//!
+//! Optional inputs:
+//! - degrees: Value, hue rotation, default is `DEFAULT_DEGREES`
+//! - saturation: Value, saturation multiplier, default is `DEFAULT_SATURATION`
+//! - value: Value, value multiplier, default is `DEFAULT_VALUE`
+//!
+//! Output:
+//! - color: IColor
+
+use crate::handle_missing_socket_values;
+
+use super::{GraphResult, NodeResult};
+
+use eray::{
+    get_sv, node,
+    prelude::*,
+    shader::{
+        self,
+        graph::{SocketType, SocketValue},
+    },
+    ssref,
+};
+
+pub const DEFAULT_DEGREES: f32 = 0.;
+pub const DEFAULT_SATURATION: f32 = 1.;
+pub const DEFAULT_VALUE: f32 = 1.;
+
+/// Get a wrapping [Graph](eray::shader::graph::Graph) containing the node.
+pub fn graph() -> GraphResult {
+    Ok(shader::graph::graph! {
+        inputs:
+            // Mandatory
+            "color": SocketType::IColor.into(),
+
+            // Optional
+            "degrees": SocketValue::Value(Some(DEFAULT_DEGREES)),
+            "saturation": SocketValue::Value(Some(DEFAULT_SATURATION)),
+            "value": SocketValue::Value(Some(DEFAULT_VALUE)),
+        nodes:
+            "hue_shift": {
+                let mut node = node()?;
+                node.set_input(&"color".into(), ssref!(graph "color"))?
+                    .set_input(&"degrees".into(), ssref!(graph "degrees"))?
+                    .set_input(&"saturation".into(), ssref!(graph "saturation"))?
+                    .set_input(&"value".into(), ssref!(graph "value"))?;
+                node
+            },
+        outputs:
+            "color": (ssref!(node "hue_shift" "color"), SocketType::IColor.into()),
+    })
+}
+
+/// Get the [node](eray::shader::graph::Node::Graph) by itself.
+pub fn node() -> NodeResult {
+    Ok(node! {
+        inputs:
+            "color": (None, SocketType::IColor),
+
+            "degrees": (None, SocketType::Value),
+            "saturation": (None, SocketType::Value),
+            "value": (None, SocketType::Value),
+        outputs:
+            "color": SocketType::IColor.into();
+        |inputs, outputs| {
+            get_sv!( input | inputs  . "color": IColor > color);
+
+            get_sv!( input | inputs  . "degrees": Value > degrees);
+            get_sv!( input | inputs  . "saturation": Value > saturation);
+            get_sv!( input | inputs  . "value": Value > value);
+
+            get_sv!(output | outputs . "color": IColor > out);
+
+            handle_missing_socket_values![color];
+            let degrees = degrees.unwrap_or(DEFAULT_DEGREES);
+            let saturation = saturation.unwrap_or(DEFAULT_SATURATION);
+            let value = value.unwrap_or(DEFAULT_VALUE);
+
+            out.replace(color.map(|pixel| {
+                let [h, s, v] = pixel.to_hsv();
+                Color::from_hsv([h + degrees, (s * saturation).clamp(0., 1.), v * value])
+            }));
+
+            Ok(())
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::shaderlib::utils::run_graph;
+
+    fn run(color: Color, degrees: f32, saturation: f32) -> Color {
+        let inputs = [
+            ("color", SocketValue::IColor(Some(Image::new(1, 1, color)))),
+            ("degrees", SocketValue::Value(Some(degrees))),
+            ("saturation", SocketValue::Value(Some(saturation))),
+        ];
+
+        let SocketValue::IColor(Some(image)) = run_graph(graph().unwrap(), inputs, "color") else {
+            panic!("Missing hue_shift output")
+        };
+
+        image.mod_get(0, 0)
+    }
+
+    #[test]
+    fn red_to_green() {
+        let red = Color::new(1., 0., 0.);
+
+        let green = run(red, 120., 1.);
+        assert!(
+            green.approx_eq(&Color::new(0., 1., 0.), 1e-6),
+            "Expected green, got {green:?}"
+        );
+
+        let blue = run(red, -120., 1.);
+        assert!(
+            blue.approx_eq(&Color::new(0., 0., 1.), 1e-6),
+            "Expected blue, got {blue:?}"
+        );
+
+        assert!(run(red, 360., 1.).approx_eq(&red, 1e-6));
+    }
+
+    #[test]
+    fn desaturate() {
+        let color = Color::new(0.8, 0.4, 0.2);
+        let grey = run(color, 0., 0.);
+
+        assert!(
+            grey.approx_eq(&Color::from(0.8), 1e-6),
+            "Expected grey, got {grey:?}"
+        );
+    }
+}
@@ -0,0 +1,57 @@
+//! Interpolation helpers shared by nodes.
+
+/// Linear interpolation between `a` and `b`.
+pub fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+/// Hermite interpolation from 0 to 1 as `x` goes from `edge0` to `edge1`, clamped outside.
+pub fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0., 1.);
+    t * t * (3. - 2. * t)
+}
+
+/// Same as [smoothstep] with zero first and second derivatives at the edges.
+pub fn smootherstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0., 1.);
+    t * t * t * (t * (t * 6. - 15.) + 10.)
+}
+
+/// Catmull-Rom spline through `p1` (at `t = 0`) and `p2` (at `t = 1`), using `p0` and `p3` as
+/// neighbouring control points.
+pub fn catmull_rom(p0: f32, p1: f32, p2: f32, p3: f32, t: f32) -> f32 {
+    let (t2, t3) = (t * t, t * t * t);
+
+    0.5 * (2. * p1
+        + (p2 - p0) * t
+        + (2. * p0 - 5. * p1 + 4. * p2 - p3) * t2
+        + (3. * p1 - p0 - 3. * p2 + p3) * t3)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn steps() {
+        for step in [smoothstep, smootherstep] {
+            assert_eq!(step(0., 1., 0.), 0.);
+            assert_eq!(step(0., 1., 1.), 1.);
+            assert_eq!(step(0., 1., 0.5), 0.5);
+            assert_eq!(step(0., 1., -1.), 0.);
+            assert_eq!(step(0., 1., 2.), 1.);
+            assert_eq!(step(2., 4., 3.), 0.5);
+        }
+
+        assert_eq!(smoothstep(0., 1., 0.25), 0.15625);
+    }
+
+    #[test]
+    fn catmull_rom_spline() {
+        assert_eq!(catmull_rom(0., 1., 2., 3., 0.), 1.);
+        assert_eq!(catmull_rom(0., 1., 2., 3., 1.), 2.);
+        // Collinear control points give a straight line
+        assert_eq!(catmull_rom(0., 1., 2., 3., 0.5), 1.5);
+        assert_eq!(catmull_rom(0., 1., 1., 0., 0.5), 1.125);
+    }
+}
@@ -80,19 +80,12 @@ pub fn node() -> NodeResult {
             handle_missing_socket_values![width, height, left, right];
             let factor = factor.unwrap_or(DEFAULT_FACTOR);
 
-            let mut res = Image::new(*width as u32, *height as u32, Color::default());
-
-            for y in 0..(res.height) {
-                for x in 0..(res.width) {
-                    let index = (y * res.width + x) as usize;
-
-                    let interp = |l, r| l * (1. - factor) + r * factor;
-                    let (lpx, rpx) = (left.mod_get(x, y), right.mod_get(x, y));
-                    let value = Color::new(interp(lpx.r, rpx.r), interp(lpx.g, rpx.g), interp(lpx.b, rpx.b));
-
-                    res.pixels[index] = value;
-                }
-            }
+            let interp = |l, r| l * (1. - factor) + r * factor;
+            let res = left
+                .tile(*width as u32, *height as u32)
+                .zip_with(right, |lpx, rpx| {
+                    Color::new(interp(lpx.r, rpx.r), interp(lpx.g, rpx.g), interp(lpx.b, rpx.b))
+                });
 
             out.replace(res);
 
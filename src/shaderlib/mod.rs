@@ -1,10 +1,30 @@
 #![allow(unused)]
 
+#[cfg(test)]
+mod golden;
+mod math;
+mod perlin;
 mod utils;
 
+pub mod bright_contrast;
+pub mod checker;
+pub mod color_ramp;
+pub mod combine_vec2;
+pub mod fbm;
 pub mod flat_color;
+pub mod grayscale;
+pub mod hue_shift;
 pub mod mix_color;
+pub mod posterize;
+pub mod presets;
 pub mod rgb;
+pub mod separate_vec2;
+pub mod switch;
+pub mod threshold;
+pub mod transform;
+pub mod uv_debug;
+pub mod uv_offset;
+pub mod warp;
 pub mod wave;
 
 use eray::{
@@ -16,12 +36,23 @@ type GraphResult = Result<Graph<Unvalidated>, Error>;
 type MaterialResult = Result<Material, Error>;
 type NodeResult = Result<Node<Unvalidated>, Error>;
 
+macro_rules! elib_graph {
+    ($lib:ident) => {
+        $lib::graph()
+    };
+
+    ($lib:ident :: $graph:ident) => {
+        $lib::$graph()
+    };
+}
+
+/// Overloads are registered as `lib::graph_function` and share the `lib` name.
 macro_rules! create_elib {
-    ($($lib:ident),+ $(,)?) => {
+    ($($lib:ident $(:: $graph:ident)?),+ $(,)?) => {
         pub fn elib() -> Vec<ImportedNode<Unvalidated>> {
             vec![
                 $(
-                    ImportedNode::from((stringify!($lib), $lib::graph().unwrap()))
+                    ImportedNode::from((stringify!($lib), elib_graph!($lib $(:: $graph)?).unwrap()))
                 ),+
             ]
         }
@@ -30,14 +61,30 @@ macro_rules! create_elib {
 
 create_elib! {
     // Generators
+    checker,
+    fbm,
     flat_color,
+    uv_debug,
     wave,
 
     // Converters
+    bright_contrast,
+    color_ramp,
+    combine_vec2,
+    grayscale,
+    hue_shift,
+    posterize,
+    posterize::value_graph,
     rgb,
+    separate_vec2,
+    threshold,
+    transform,
+    uv_offset,
 
     // Mixers
     mix_color,
+    switch,
+    warp,
 }
 
 lazy_static::lazy_static! {
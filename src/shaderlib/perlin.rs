@@ -0,0 +1,49 @@
+//! Gradient (Perlin) noise sampling shared by noise-based nodes.
+
+use super::math::{lerp, smootherstep};
+
+/// Sample 2D gradient noise at the given position, the result roughly lies in the -1..=1 range.
+///
+/// Integer coordinates are lattice points where the noise is always 0. The lattice wraps around
+/// every `period` cells along each axis, so that the noise tiles over that many cells.
+pub fn sample(x: f32, y: f32, period: (u32, u32)) -> f32 {
+    let (x0, y0) = (x.floor(), y.floor());
+    let (fx, fy) = (x - x0, y - y0);
+    let (ix, iy) = (x0 as i32, y0 as i32);
+    let (px, py) = (period.0.max(1) as i32, period.1.max(1) as i32);
+
+    let dot = |cx: i32, cy: i32, dx: f32, dy: f32| {
+        let (gx, gy) = gradient(hash((ix + cx).rem_euclid(px), (iy + cy).rem_euclid(py)));
+        gx * dx + gy * dy
+    };
+
+    let (u, v) = (smootherstep(0., 1., fx), smootherstep(0., 1., fy));
+
+    let bottom = lerp(dot(0, 0, fx, fy), dot(1, 0, fx - 1., fy), u);
+    let top = lerp(dot(0, 1, fx, fy - 1.), dot(1, 1, fx - 1., fy - 1.), u);
+
+    lerp(bottom, top, v)
+}
+
+fn hash(x: i32, y: i32) -> u32 {
+    let mut h = (x as u32).wrapping_mul(0x27d4_eb2d) ^ (y as u32).wrapping_mul(0x1656_67b1);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x85eb_ca6b);
+    h ^= h >> 13;
+    h
+}
+
+fn gradient(hash: u32) -> (f32, f32) {
+    const DIAG: f32 = std::f32::consts::FRAC_1_SQRT_2;
+
+    match hash & 7 {
+        0 => (1., 0.),
+        1 => (-1., 0.),
+        2 => (0., 1.),
+        3 => (0., -1.),
+        4 => (DIAG, DIAG),
+        5 => (-DIAG, DIAG),
+        6 => (DIAG, -DIAG),
+        _ => (-DIAG, -DIAG),
+    }
+}
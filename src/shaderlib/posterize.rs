@@ -0,0 +1,204 @@
+//! Quantize an image into a fixed number of levels per channel.
+//!
+//! Two overloads are provided, one for [Color images](SocketType::IColor) ([graph]/[node]) and one
+//! for [Value images](SocketType::IValue) ([value_graph]/[value_node]).
+//!
+//! Mandatory inputs:
+//! - color: IColor, or value: IValue
+//!
+//! Optional inputs:
+//! - levels: Value, number of distinct levels per channel (at least 2), default is
+//!   `DEFAULT_LEVELS`
+//!
+//! Output:
+//! - color: IColor, or value: IValue
+
+use crate::handle_missing_socket_values;
+
+use super::{GraphResult, NodeResult};
+
+use eray::{
+    get_sv, node,
+    prelude::*,
+    shader::{
+        self,
+        graph::{SocketType, SocketValue},
+    },
+    ssref,
+};
+
+pub const DEFAULT_LEVELS: f32 = 4.;
+
+/// Round `value` to the nearest of `levels` evenly spaced levels in the 0..=1 range.
+fn quantize(value: f32, levels: f32) -> f32 {
+    let steps = levels.round().max(2.) - 1.;
+    (value.clamp(0., 1.) * steps).round() / steps
+}
+
+/// Get a wrapping [Graph](eray::shader::graph::Graph) containing the [Color](SocketType::IColor)
+/// node.
+pub fn graph() -> GraphResult {
+    Ok(shader::graph::graph! {
+        inputs:
+            // Mandatory
+            "color": SocketType::IColor.into(),
+
+            // Optional
+            "levels": SocketValue::Value(Some(DEFAULT_LEVELS)),
+        nodes:
+            "posterize": {
+                let mut node = node()?;
+                node.set_input(&"color".into(), ssref!(graph "color"))?
+                    .set_input(&"levels".into(), ssref!(graph "levels"))?;
+                node
+            },
+        outputs:
+            "color": (ssref!(node "posterize" "color"), SocketType::IColor.into()),
+    })
+}
+
+/// Get the [Color](SocketType::IColor) [node](eray::shader::graph::Node::Graph) by itself.
+pub fn node() -> NodeResult {
+    Ok(node! {
+        inputs:
+            "color": (None, SocketType::IColor),
+
+            "levels": (None, SocketType::Value),
+        outputs:
+            "color": SocketType::IColor.into();
+        |inputs, outputs| {
+            get_sv!( input | inputs  . "color": IColor > color);
+
+            get_sv!( input | inputs  . "levels": Value > levels);
+
+            get_sv!(output | outputs . "color": IColor > out);
+
+            handle_missing_socket_values![color];
+            let levels = levels.unwrap_or(DEFAULT_LEVELS);
+
+            out.replace(Image {
+                width: color.width,
+                height: color.height,
+                pixels: color
+                    .pixels
+                    .iter()
+                    .map(|pixel| Color::new(
+                        quantize(pixel.r, levels),
+                        quantize(pixel.g, levels),
+                        quantize(pixel.b, levels),
+                    ))
+                    .collect(),
+            });
+
+            Ok(())
+        }
+    })
+}
+
+/// Get a wrapping [Graph](eray::shader::graph::Graph) containing the [Value](SocketType::IValue)
+/// node.
+pub fn value_graph() -> GraphResult {
+    Ok(shader::graph::graph! {
+        inputs:
+            // Mandatory
+            "value": SocketType::IValue.into(),
+
+            // Optional
+            "levels": SocketValue::Value(Some(DEFAULT_LEVELS)),
+        nodes:
+            "posterize": {
+                let mut node = value_node()?;
+                node.set_input(&"value".into(), ssref!(graph "value"))?
+                    .set_input(&"levels".into(), ssref!(graph "levels"))?;
+                node
+            },
+        outputs:
+            "value": (ssref!(node "posterize" "value"), SocketType::IValue.into()),
+    })
+}
+
+/// Get the [Value](SocketType::IValue) [node](eray::shader::graph::Node::Graph) by itself.
+pub fn value_node() -> NodeResult {
+    Ok(node! {
+        inputs:
+            "value": (None, SocketType::IValue),
+
+            "levels": (None, SocketType::Value),
+        outputs:
+            "value": SocketType::IValue.into();
+        |inputs, outputs| {
+            get_sv!( input | inputs  . "value": IValue > value);
+
+            get_sv!( input | inputs  . "levels": Value > levels);
+
+            get_sv!(output | outputs . "value": IValue > out);
+
+            handle_missing_socket_values![value];
+            let levels = levels.unwrap_or(DEFAULT_LEVELS);
+
+            out.replace(Image {
+                width: value.width,
+                height: value.height,
+                pixels: value.pixels.iter().map(|&pixel| quantize(pixel, levels)).collect(),
+            });
+
+            Ok(())
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::shaderlib::utils::run_graph;
+
+    fn gradient() -> Image<Color> {
+        Image {
+            width: 256,
+            height: 1,
+            pixels: (0..=255u32)
+                .map(|v| [v, 255 - v, (v * 7) % 256].map(|v| v as f32 / 255.))
+                .map(|[r, g, b]| Color::new(r, g, b))
+                .collect(),
+        }
+    }
+
+    fn run(color: Image<Color>, levels: f32) -> Image<Color> {
+        let inputs = [
+            ("color", SocketValue::IColor(Some(color))),
+            ("levels", SocketValue::Value(Some(levels))),
+        ];
+
+        let SocketValue::IColor(Some(image)) = run_graph(graph().unwrap(), inputs, "color") else {
+            panic!("Missing posterize output")
+        };
+
+        image
+    }
+
+    #[test]
+    fn two_levels() {
+        let res = run(gradient(), 2.);
+
+        for channel in [|c: &Color| c.r, |c: &Color| c.g, |c: &Color| c.b] {
+            let mut values = res.pixels.iter().map(channel).collect::<Vec<f32>>();
+            values.sort_by(f32::total_cmp);
+            values.dedup();
+
+            assert_eq!(values, vec![0., 1.]);
+        }
+    }
+
+    #[test]
+    fn identity_at_8_bits() {
+        let input = gradient();
+        let res = run(input.clone(), 256.);
+
+        for (got, expected) in res.pixels.iter().zip(input.pixels.iter()) {
+            assert!(
+                got.approx_eq(expected, 1e-5),
+                "Expected {expected:?}, got {got:?}"
+            );
+        }
+    }
+}
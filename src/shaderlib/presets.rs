@@ -0,0 +1,228 @@
+//! Ready-to-use [Material]s assembled from shaderlib nodes.
+//!
+//! Every preset's graph takes the following optional inputs on top of its own:
+//! - width: Value, width of the generated textures, default is `DEFAULT_SIZE`
+//! - height: Value, height of the generated textures, default is `DEFAULT_SIZE`
+
+use super::{checker, color_ramp, combine_vec2, fbm, warp, wave, MaterialResult};
+
+use eray::{
+    prelude::*,
+    shader::{
+        self,
+        graph::{SocketType, SocketValue},
+    },
+    ssref,
+};
+
+use map_macro::hash_map;
+
+pub const DEFAULT_SIZE: f32 = 128.;
+
+/// Function assembling a preset [Material].
+pub type Preset = fn() -> MaterialResult;
+
+/// Every preset along with its name.
+pub const PRESETS: [(&str, Preset); 3] = [("marble", marble), ("wood", wood), ("tiles", tiles)];
+
+/// White marble with grey veins, [fbm] noise mapped through a [color_ramp].
+///
+/// Optional inputs:
+/// - scale: Value, size in pixels of the veins, default is 48
+/// - octaves: Value, number of [fbm] octaves, default is 6
+/// - low, high: Color, colors of the veins and of the stone
+pub fn marble() -> MaterialResult {
+    Material::try_from((
+        shader::graph::graph! {
+            inputs:
+                "width": SocketValue::Value(Some(DEFAULT_SIZE)),
+                "height": SocketValue::Value(Some(DEFAULT_SIZE)),
+                "scale": SocketValue::Value(Some(48.)),
+                "octaves": SocketValue::Value(Some(6.)),
+                "low": SocketValue::Color(Some(Color::new(0.35, 0.35, 0.4))),
+                "high": SocketValue::Color(Some(Color::new(0.95, 0.95, 0.92))),
+            nodes:
+                "noise": {
+                    let mut node = fbm::node()?;
+                    node.set_input(&"width".into(), ssref!(graph "width"))?
+                        .set_input(&"height".into(), ssref!(graph "height"))?
+                        .set_input(&"scale".into(), ssref!(graph "scale"))?
+                        .set_input(&"octaves".into(), ssref!(graph "octaves"))?;
+                    node
+                },
+                "ramp": {
+                    let mut node = color_ramp::node()?;
+                    node.set_input(&"value".into(), ssref!(node "noise" "value"))?
+                        .set_input(&"low".into(), ssref!(graph "low"))?
+                        .set_input(&"high".into(), ssref!(graph "high"))?;
+                    node
+                },
+            outputs:
+                "color": (ssref!(node "ramp" "color"), SocketType::IColor.into()),
+        }
+        .validate()?,
+        hash_map! {
+            StandardMaterialOutput::Color => "color".into(),
+        },
+    ))
+}
+
+/// Brown wood grain, [wave] stripes mapped through a [color_ramp] and bent by [fbm] noise
+/// through a [warp].
+///
+/// Optional inputs:
+/// - rings: Value, stripe frequency multiplier across the width, default is 3
+/// - flat: Value, stripe frequency multiplier across the height, default is 0
+/// - strength: Value, how much the noise bends the stripes, default is 0.05
+/// - low, high: Color, colors of the dark and light grain
+pub fn wood() -> MaterialResult {
+    Material::try_from((
+        shader::graph::graph! {
+            inputs:
+                "width": SocketValue::Value(Some(DEFAULT_SIZE)),
+                "height": SocketValue::Value(Some(DEFAULT_SIZE)),
+                "rings": SocketValue::Value(Some(3.)),
+                "flat": SocketValue::Value(Some(0.)),
+                "strength": SocketValue::Value(Some(0.05)),
+                "low": SocketValue::Color(Some(Color::new(0.3, 0.15, 0.05))),
+                "high": SocketValue::Color(Some(Color::new(0.65, 0.4, 0.2))),
+            nodes:
+                "stripes": {
+                    let mut node = wave::node()?;
+                    node.set_input(&"width".into(), ssref!(graph "width"))?
+                        .set_input(&"height".into(), ssref!(graph "height"))?
+                        .set_input(&"x_fac".into(), ssref!(graph "rings"))?
+                        .set_input(&"y_fac".into(), ssref!(graph "flat"))?;
+                    node
+                },
+                "ramp": {
+                    let mut node = color_ramp::node()?;
+                    node.set_input(&"value".into(), ssref!(node "stripes" "value"))?
+                        .set_input(&"low".into(), ssref!(graph "low"))?
+                        .set_input(&"high".into(), ssref!(graph "high"))?;
+                    node
+                },
+                "noise": {
+                    let mut node = fbm::node()?;
+                    node.set_input(&"width".into(), ssref!(graph "width"))?
+                        .set_input(&"height".into(), ssref!(graph "height"))?;
+                    node
+                },
+                "offset": {
+                    let mut node = combine_vec2::node()?;
+                    node.set_input(&"x".into(), ssref!(node "noise" "value"))?
+                        .set_input(&"y".into(), ssref!(node "noise" "value"))?;
+                    node
+                },
+                "grain": {
+                    let mut node = warp::node()?;
+                    node.set_input(&"color".into(), ssref!(node "ramp" "color"))?
+                        .set_input(&"offset".into(), ssref!(node "offset" "vec"))?
+                        .set_input(&"strength".into(), ssref!(graph "strength"))?;
+                    node
+                },
+            outputs:
+                "color": (ssref!(node "grain" "color"), SocketType::IColor.into()),
+        }
+        .validate()?,
+        hash_map! {
+            StandardMaterialOutput::Color => "color".into(),
+        },
+    ))
+}
+
+/// Two-tone floor tiles, a [checker] mapped through a [color_ramp], the same checker making the
+/// lighter tiles shiny.
+///
+/// Materials have no normal or bump output yet, so the tiles are told apart by their shine
+/// rather than by a bump along their edges.
+///
+/// Optional inputs:
+/// - cells: Value, number of tiles along each axis, default is 8
+/// - low, high: Color, colors of the dark and light tiles
+pub fn tiles() -> MaterialResult {
+    Material::try_from((
+        shader::graph::graph! {
+            inputs:
+                "width": SocketValue::Value(Some(DEFAULT_SIZE)),
+                "height": SocketValue::Value(Some(DEFAULT_SIZE)),
+                "cells": SocketValue::Value(Some(8.)),
+                "low": SocketValue::Color(Some(Color::new(0.15, 0.2, 0.3))),
+                "high": SocketValue::Color(Some(Color::new(0.85, 0.85, 0.8))),
+            nodes:
+                "pattern": {
+                    let mut node = checker::node()?;
+                    node.set_input(&"width".into(), ssref!(graph "width"))?
+                        .set_input(&"height".into(), ssref!(graph "height"))?
+                        .set_input(&"cells".into(), ssref!(graph "cells"))?;
+                    node
+                },
+                "ramp": {
+                    let mut node = color_ramp::node()?;
+                    node.set_input(&"value".into(), ssref!(node "pattern" "value"))?
+                        .set_input(&"low".into(), ssref!(graph "low"))?
+                        .set_input(&"high".into(), ssref!(graph "high"))?;
+                    node
+                },
+            outputs:
+                "color": (ssref!(node "ramp" "color"), SocketType::IColor.into()),
+                "specular": (ssref!(node "pattern" "value"), SocketType::IValue.into()),
+        }
+        .validate()?,
+        hash_map! {
+            StandardMaterialOutput::Color => "color".into(),
+            StandardMaterialOutput::Specular => "specular".into(),
+        },
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bake() {
+        for (name, preset) in PRESETS {
+            let bake = || {
+                let mut material = preset().unwrap();
+                material
+                    .set_input(&"width".into(), SocketValue::Value(Some(32.)))
+                    .unwrap()
+                    .set_input(&"height".into(), SocketValue::Value(Some(32.)))
+                    .unwrap();
+                material.update(0.).unwrap();
+                material.bake(8, 8)
+            };
+
+            let image = bake();
+            assert!(
+                image.pixels.iter().any(|&pixel| pixel != image.pixels[0]),
+                "`{name}` should not be uniform"
+            );
+            assert_eq!(image, bake(), "`{name}` should be deterministic");
+        }
+    }
+    #[test]
+    fn wood_grain_along_y() {
+        // Without the noise bending them, the rings only vary across the width
+        let mut material = wood().unwrap();
+        material
+            .set_input(&"width".into(), SocketValue::Value(Some(32.)))
+            .unwrap()
+            .set_input(&"height".into(), SocketValue::Value(Some(32.)))
+            .unwrap()
+            .set_input(&"strength".into(), SocketValue::Value(Some(0.)))
+            .unwrap();
+        material.update(0.).unwrap();
+
+        let image = material.bake(8, 8);
+        let first = &image.pixels[..8];
+        assert!(
+            first.iter().any(|&pixel| pixel != first[0]),
+            "Expected rings along x"
+        );
+        for row in image.pixels.chunks_exact(8) {
+            assert_eq!(first, row);
+        }
+    }
+}
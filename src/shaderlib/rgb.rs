@@ -28,12 +28,12 @@ use eray::{
 use map_macro::hash_map;
 
 pub fn material() -> MaterialResult {
-    Ok(Material::from((
+    Material::try_from((
         graph()?.validate()?,
         hash_map! {
             StandardMaterialOutput::Color => "color".into()
         },
-    )))
+    ))
 }
 
 pub fn graph() -> GraphResult {
@@ -93,7 +93,6 @@ pub fn node() -> NodeResult {
                     res.pixels[index] = value;
                 }
             }
-            res.save_as_ppm(std::path::Path::new("rgb.ppm"));
             out.replace(res);
 
 
@@ -0,0 +1,67 @@
+//! Unpack a [Vec2 image](SocketType::IVec2) into two [Value images](SocketType::IValue), see
+//! [combine_vec2](super::combine_vec2) for the reverse operation.
+//!
+//! Mandatory inputs:
+//! - vec: IVec2
+//!
+//! Outputs:
+//! - x: IValue
+//! - y: IValue
+
+use crate::handle_missing_socket_values;
+
+use super::{GraphResult, NodeResult};
+
+use eray::{
+    get_sv, node,
+    prelude::*,
+    shader::{self, graph::SocketType},
+    ssref,
+};
+
+/// Get a wrapping [Graph](eray::shader::graph::Graph) containing the node.
+pub fn graph() -> GraphResult {
+    Ok(shader::graph::graph! {
+        inputs:
+            // Mandatory
+            "vec": SocketType::IVec2.into(),
+        nodes:
+            "separate": {
+                let mut node = node()?;
+                node.set_input(&"vec".into(), ssref!(graph "vec"))?;
+                node
+            },
+        outputs:
+            "x": (ssref!(node "separate" "x"), SocketType::IValue.into()),
+            "y": (ssref!(node "separate" "y"), SocketType::IValue.into()),
+    })
+}
+
+/// Get the [node](eray::shader::graph::Node::Graph) by itself.
+pub fn node() -> NodeResult {
+    Ok(node! {
+        inputs:
+            "vec": (None, SocketType::IVec2),
+        outputs:
+            "x": SocketType::IValue.into(),
+            "y": SocketType::IValue.into();
+        |inputs, outputs| {
+            get_sv!( input | inputs  . "vec": IVec2 > vec);
+
+            handle_missing_socket_values![vec];
+            let component = |index: usize| Image {
+                width: vec.width,
+                height: vec.height,
+                pixels: vec.pixels.iter().map(|v| v[index]).collect(),
+            };
+
+            get_sv!(output | outputs . "x": IValue > out_x);
+            out_x.replace(component(0));
+
+            get_sv!(output | outputs . "y": IValue > out_y);
+            out_y.replace(component(1));
+
+            Ok(())
+        }
+    })
+}
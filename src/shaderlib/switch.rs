@@ -0,0 +1,116 @@
+//! Pick each pixel from one of two colors depending on a [Bool image](SocketType::IBool).
+//!
+//! Mandatory inputs:
+//! - condition: IBool, the output takes its size, the colors being tiled to match it
+//! - left: IColor, used where the condition is true
+//! - right: IColor, used where the condition is false
+//!
+//! Output:
+//! - color: IColor
+
+use crate::handle_missing_socket_values;
+
+use super::{GraphResult, NodeResult};
+
+use eray::{
+    get_sv, node,
+    prelude::*,
+    shader::{
+        self,
+        graph::{SocketType, SocketValue},
+    },
+    ssref,
+};
+
+/// Get a wrapping [Graph](eray::shader::graph::Graph) containing the node.
+pub fn graph() -> GraphResult {
+    Ok(shader::graph::graph! {
+        inputs:
+            // Mandatory
+            "condition": SocketType::IBool.into(),
+            "left": SocketType::IColor.into(),
+            "right": SocketType::IColor.into(),
+        nodes:
+            "switch": {
+                let mut node = node()?;
+                node.set_input(&"condition".into(), ssref!(graph "condition"))?
+                    .set_input(&"left".into(), ssref!(graph "left"))?
+                    .set_input(&"right".into(), ssref!(graph "right"))?;
+                node
+            },
+        outputs:
+            "color": (ssref!(node "switch" "color"), SocketType::IColor.into()),
+    })
+}
+
+/// Get the [node](eray::shader::graph::Node::Graph) by itself.
+pub fn node() -> NodeResult {
+    Ok(node! {
+        inputs:
+            "condition": (None, SocketType::IBool),
+            "left": (None, SocketType::IColor),
+            "right": (None, SocketType::IColor),
+        outputs:
+            "color": SocketType::IColor.into();
+        |inputs, outputs| {
+            get_sv!( input | inputs  . "condition": IBool > condition);
+            get_sv!( input | inputs  . "left": IColor > left);
+            get_sv!( input | inputs  . "right": IColor > right);
+
+            get_sv!(output | outputs . "color": IColor > out);
+
+            handle_missing_socket_values![condition, left, right];
+
+            let res = condition
+                .zip_with(left, |&condition, &left| (condition, left))
+                .zip_with(right, |&(condition, left), &right| {
+                    if condition {
+                        left
+                    } else {
+                        right
+                    }
+                });
+
+            out.replace(res);
+
+            Ok(())
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::shaderlib::utils::run_graph;
+
+    #[test]
+    fn checkerboard() {
+        let (left, right) = (Color::new(1., 0., 0.), Color::new(0., 0., 1.));
+
+        // Boolean mask converted from a checker pattern
+        let checker = [("width", 4.), ("height", 4.), ("cells", 4.)]
+            .map(|(name, value)| (name, SocketValue::Value(Some(value))));
+        let mask = run_graph(
+            crate::shaderlib::checker::graph().unwrap(),
+            checker,
+            "value",
+        );
+
+        let inputs = [
+            ("condition", mask.try_convert(SocketType::IBool).unwrap()),
+            ("left", SocketValue::IColor(Some(Image::new(1, 1, left)))),
+            ("right", SocketValue::IColor(Some(Image::new(2, 2, right)))),
+        ];
+
+        let SocketValue::IColor(Some(image)) = run_graph(graph().unwrap(), inputs, "color") else {
+            panic!("Missing switch output")
+        };
+
+        assert_eq!((4, 4), (image.width, image.height));
+        for (x, y, &color) in image.enumerate_pixels() {
+            // The checker is 0, hence false, on its first cell
+            let expected = if (x + y) % 2 == 0 { right } else { left };
+            assert_eq!(expected, color, "at ({x}, {y})");
+        }
+    }
+}
@@ -0,0 +1,138 @@
+//! Step function turning a [Value image](SocketType::IValue) into a mask.
+//!
+//! Mandatory inputs:
+//! - value: IValue
+//!
+//! Optional inputs:
+//! - threshold: Value, values strictly above it map to 1, default is `DEFAULT_THRESHOLD`
+//! - edge_width: Value, width of the smoothstep transition centered on the threshold, default is
+//!   0 (hard step)
+//!
+//! Output:
+//! - value: IValue
+
+use crate::handle_missing_socket_values;
+
+use super::{math::smoothstep, GraphResult, NodeResult};
+
+use eray::{
+    get_sv, node,
+    prelude::*,
+    shader::{
+        self,
+        graph::{SocketType, SocketValue},
+    },
+    ssref,
+};
+
+pub const DEFAULT_THRESHOLD: f32 = 0.5;
+
+/// Get a wrapping [Graph](eray::shader::graph::Graph) containing the node.
+pub fn graph() -> GraphResult {
+    Ok(shader::graph::graph! {
+        inputs:
+            // Mandatory
+            "value": SocketType::IValue.into(),
+
+            // Optional
+            "threshold": SocketValue::Value(Some(DEFAULT_THRESHOLD)),
+            "edge_width": SocketValue::Value(Some(0.)),
+        nodes:
+            "threshold": {
+                let mut node = node()?;
+                node.set_input(&"value".into(), ssref!(graph "value"))?
+                    .set_input(&"threshold".into(), ssref!(graph "threshold"))?
+                    .set_input(&"edge_width".into(), ssref!(graph "edge_width"))?;
+                node
+            },
+        outputs:
+            "value": (ssref!(node "threshold" "value"), SocketType::IValue.into()),
+    })
+}
+
+/// Get the [node](eray::shader::graph::Node::Graph) by itself.
+pub fn node() -> NodeResult {
+    Ok(node! {
+        inputs:
+            "value": (None, SocketType::IValue),
+
+            "threshold": (None, SocketType::Value),
+            "edge_width": (None, SocketType::Value),
+        outputs:
+            "value": SocketType::IValue.into();
+        |inputs, outputs| {
+            get_sv!( input | inputs  . "value": IValue > value);
+
+            get_sv!( input | inputs  . "threshold": Value > threshold);
+            get_sv!( input | inputs  . "edge_width": Value > edge_width);
+
+            get_sv!(output | outputs . "value": IValue > out);
+
+            handle_missing_socket_values![value];
+            let threshold = threshold.unwrap_or(DEFAULT_THRESHOLD);
+            let edge_width = edge_width.unwrap_or(0.).max(0.);
+
+            let step = |v: f32| {
+                if edge_width == 0. {
+                    return (v > threshold) as u32 as f32;
+                }
+
+                smoothstep(threshold - edge_width / 2., threshold + edge_width / 2., v)
+            };
+
+            out.replace(value.map(|&v| step(v)));
+
+            Ok(())
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::shaderlib::utils::run_graph;
+
+    fn run(pixels: Vec<f32>, threshold: f32, edge_width: f32) -> Vec<f32> {
+        let value = Image {
+            width: pixels.len() as u32,
+            height: 1,
+            pixels,
+        };
+        let inputs = [
+            ("value", SocketValue::IValue(Some(value))),
+            ("threshold", SocketValue::Value(Some(threshold))),
+            ("edge_width", SocketValue::Value(Some(edge_width))),
+        ];
+
+        let SocketValue::IValue(Some(image)) = run_graph(graph().unwrap(), inputs, "value") else {
+            panic!("Missing threshold output")
+        };
+
+        image.pixels
+    }
+
+    #[test]
+    fn hard_threshold() {
+        assert_eq!(
+            run(vec![0., 0.3, 0.5, 0.51, 1.], 0.5, 0.),
+            vec![0., 0., 0., 1., 1.]
+        );
+    }
+
+    #[test]
+    fn smooth_transition() {
+        let res = run(vec![0.3, 0.42, 0.45, 0.5, 0.55, 0.58, 0.7], 0.5, 0.2);
+
+        assert_eq!(res[0], 0.);
+        assert_eq!(res[6], 1.);
+        assert!(
+            (res[3] - 0.5).abs() < 1e-6,
+            "Expected 0.5 at the threshold, got {}",
+            res[3]
+        );
+        assert!(
+            res[1..=5].windows(2).all(|pair| pair[0] < pair[1]),
+            "Expected a strictly increasing transition, got {res:?}"
+        );
+    }
+}
@@ -0,0 +1,107 @@
+//! Transform a [Vec3](SocketType::Vec3) position by a [Matrix](SocketType::Matrix).
+//!
+//! Mandatory inputs:
+//! - vector: Vec3
+//!
+//! Optional inputs:
+//! - matrix: Matrix, applied to the vector as a point, default is the identity
+//!
+//! Output:
+//! - vector: Vec3
+
+use crate::handle_missing_socket_values;
+
+use super::{GraphResult, NodeResult};
+
+use eray::{
+    get_sv, node,
+    prelude::*,
+    shader::{
+        self,
+        graph::{SocketType, SocketValue},
+    },
+    ssref,
+};
+
+/// Get a wrapping [Graph](eray::shader::graph::Graph) containing the node.
+pub fn graph() -> GraphResult {
+    Ok(shader::graph::graph! {
+        inputs:
+            // Mandatory
+            "vector": SocketType::Vec3.into(),
+
+            // Optional
+            "matrix": SocketValue::Matrix(Some(Mat4::identity())),
+        nodes:
+            "transform": {
+                let mut node = node()?;
+                node.set_input(&"vector".into(), ssref!(graph "vector"))?
+                    .set_input(&"matrix".into(), ssref!(graph "matrix"))?;
+                node
+            },
+        outputs:
+            "vector": (ssref!(node "transform" "vector"), SocketType::Vec3.into()),
+    })
+}
+
+/// Get the [node](eray::shader::graph::Node::Graph) by itself.
+pub fn node() -> NodeResult {
+    Ok(node! {
+        inputs:
+            "vector": (None, SocketType::Vec3),
+
+            "matrix": (None, SocketType::Matrix),
+        outputs:
+            "vector": SocketType::Vec3.into();
+        |inputs, outputs| {
+            get_sv!( input | inputs  . "vector": Vec3 > vector);
+
+            get_sv!( input | inputs  . "matrix": Matrix > matrix);
+
+            get_sv!(output | outputs . "vector": Vec3 > out);
+
+            handle_missing_socket_values![vector];
+
+            out.replace(match matrix {
+                Some(matrix) => matrix.transform_point(*vector),
+                None => *vector,
+            });
+
+            Ok(())
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::shaderlib::utils::run_graph;
+
+    fn run(matrix: Option<Mat4>) -> Vector<3, f32> {
+        let inputs = std::iter::once(("vector", SocketValue::Vec3(Some(Vector::new(1., 2., 3.)))))
+            .chain(matrix.map(|matrix| ("matrix", SocketValue::Matrix(Some(matrix)))));
+
+        let SocketValue::Vec3(Some(vector)) = run_graph(graph().unwrap(), inputs, "vector") else {
+            panic!("Missing vector output")
+        };
+
+        vector
+    }
+
+    #[test]
+    fn identity() {
+        assert_eq!(Vector::new(1., 2., 3.), run(None));
+    }
+
+    #[test]
+    fn transforms() {
+        let transform = Transform::default()
+            .apply_translation(Vector::new(1., 0., -1.))
+            .apply_scale(Vector::new(2., 2., 2.));
+
+        assert_eq!(
+            Vector::new(3., 4., 5.),
+            run(Some(transform.matrix().clone()))
+        );
+    }
+}
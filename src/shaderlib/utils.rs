@@ -1,3 +1,9 @@
+#[cfg(test)]
+use eray::{
+    prelude::{Color, Image},
+    shader::graph::{Graph, Name, SocketValue, Unvalidated},
+};
+
 #[macro_export]
 macro_rules! missing_socket_error_vec {
     ($($name:ident),+ $(,)?) => {
@@ -34,3 +40,65 @@ macro_rules! handle_missing_socket_values {
 }
 
 pub use {handle_missing_socket_values, missing_socket_error_vec};
+
+/// Check that `image` tiles without visible seams, i.e. that stepping across its edges onto the
+/// opposite ones never changes a channel by more than the largest step between neighbouring
+/// pixels inside the image, plus `tolerance`.
+#[cfg(test)]
+pub fn assert_tiles_seamlessly(image: &Image<Color>, tolerance: f32) {
+    let step = |a: Color, b: Color| {
+        (a.r - b.r)
+            .abs()
+            .max((a.g - b.g).abs())
+            .max((a.b - b.b).abs())
+    };
+    let (width, height) = (image.width, image.height);
+
+    // Largest interior steps along each axis
+    let (mut max_x, mut max_y) = (0f32, 0f32);
+    for (x, y, &pixel) in image.enumerate_pixels() {
+        if x + 1 < width {
+            max_x = max_x.max(step(pixel, image.mod_get(x + 1, y)));
+        }
+        if y + 1 < height {
+            max_y = max_y.max(step(pixel, image.mod_get(x, y + 1)));
+        }
+    }
+
+    for y in 0..height {
+        let seam = step(image.mod_get(width - 1, y), image.mod_get(0, y));
+        assert!(
+            seam <= max_x + tolerance,
+            "Horizontal seam on row {y}: step of {seam}, interior steps are at most {max_x}"
+        );
+    }
+
+    for x in 0..width {
+        let seam = step(image.mod_get(x, height - 1), image.mod_get(x, 0));
+        assert!(
+            seam <= max_y + tolerance,
+            "Vertical seam on column {x}: step of {seam}, interior steps are at most {max_y}"
+        );
+    }
+}
+
+/// Run `graph` with the given `inputs` set and take the value of its `output`.
+#[cfg(test)]
+pub fn run_graph<'a>(
+    mut graph: Graph<Unvalidated>,
+    inputs: impl IntoIterator<Item = (&'a str, SocketValue)>,
+    output: &str,
+) -> SocketValue {
+    for (name, value) in inputs {
+        graph.inputs.insert(name.into(), value);
+    }
+
+    let mut graph = graph.validate().unwrap();
+    graph.run().unwrap();
+
+    graph
+        .outputs
+        .remove(&Name::from(output))
+        .unwrap_or_else(|| panic!("Missing output `{output}`"))
+        .1
+}
@@ -0,0 +1,129 @@
+//! Debug pattern showing the UV coordinates of each texel center, u as red and v as green.
+//!
+//! Applied to a mesh through its [material], it shows how the mesh is unwrapped, e.g. to spot
+//! stretched or flipped faces.
+//!
+//! Optional inputs:
+//! - width: Value, width of the output image, default is `DEFAULT_SIZE`
+//! - height: Value, height of the output image, default is `DEFAULT_SIZE`
+//!
+//! Output:
+//! - color: IColor
+
+use crate::handle_missing_socket_values;
+
+use super::{GraphResult, MaterialResult, NodeResult};
+
+use eray::{
+    get_sv, node,
+    prelude::*,
+    shader::{
+        self,
+        graph::{SocketType, SocketValue},
+    },
+    ssref,
+};
+
+use map_macro::hash_map;
+
+pub const DEFAULT_SIZE: f32 = 64.;
+
+/// [Unlit](Material::set_unlit) material showing the pattern as is.
+pub fn material() -> MaterialResult {
+    let mut material = Material::try_from((
+        graph()?.validate()?,
+        hash_map! {
+            StandardMaterialOutput::Color => "color".into(),
+        },
+    ))?;
+    material.set_unlit(true);
+    Ok(material)
+}
+
+/// Get a wrapping [Graph](eray::shader::graph::Graph) containing the node.
+pub fn graph() -> GraphResult {
+    Ok(shader::graph::graph! {
+        inputs:
+            // Optional
+            "width": SocketValue::Value(Some(DEFAULT_SIZE)),
+            "height": SocketValue::Value(Some(DEFAULT_SIZE)),
+        nodes:
+            "uv": {
+                let mut node = node()?;
+                node.set_input(&"width".into(), ssref!(graph "width"))?
+                    .set_input(&"height".into(), ssref!(graph "height"))?;
+                node
+            },
+        outputs:
+            "color": (ssref!(node "uv" "color"), SocketType::IColor.into()),
+    })
+}
+
+/// Get the [node](eray::shader::graph::Node::Graph) by itself.
+pub fn node() -> NodeResult {
+    Ok(node! {
+        inputs:
+            "width": (None, SocketType::Value),
+            "height": (None, SocketType::Value),
+        outputs:
+            "color": SocketType::IColor.into();
+        |inputs, outputs| {
+            get_sv!( input | inputs  . "width": Value > width);
+            get_sv!( input | inputs  . "height": Value > height);
+
+            get_sv!(output | outputs . "color": IColor > out);
+
+            handle_missing_socket_values![width, height];
+
+            let (width, height) = (*width as u32, *height as u32);
+            let mut res = Image::new(width, height, Color::default());
+
+            for (x, y, pixel) in res.enumerate_pixels_mut() {
+                *pixel = Color::new(
+                    (x as f32 + 0.5) / width as f32,
+                    (y as f32 + 0.5) / height as f32,
+                    0.,
+                );
+            }
+
+            out.replace(res);
+
+            Ok(())
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::shaderlib::utils::run_graph;
+
+    #[test]
+    fn channels() {
+        let inputs = [("width", 4.), ("height", 2.)]
+            .map(|(name, value)| (name, SocketValue::Value(Some(value))));
+
+        let SocketValue::IColor(Some(image)) = run_graph(graph().unwrap(), inputs, "color") else {
+            panic!("Missing uv output")
+        };
+
+        assert_eq!(Color::new(0.125, 0.25, 0.), image.mod_get(0, 0));
+        assert_eq!(Color::new(0.875, 0.25, 0.), image.mod_get(3, 0));
+        assert_eq!(Color::new(0.375, 0.75, 0.), image.mod_get(1, 1));
+    }
+
+    #[test]
+    fn material_shows_uv() {
+        let mut material = material().unwrap();
+        material.update(0.).unwrap();
+        assert!(material.is_unlit());
+
+        // Texel centers sampled with the default nearest filter
+        let step = 1. / DEFAULT_SIZE;
+        for (u, v) in [(0.5, 0.5), (2.5, 10.5), (63.5, 31.5)] {
+            let (u, v) = (u * step, v * step);
+            let color = material.get(u, v).color.unwrap();
+            assert_eq!((color.r, color.g, color.b), (u, v, 0.));
+        }
+    }
+}
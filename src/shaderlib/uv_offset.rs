@@ -0,0 +1,102 @@
+//! Offset a [Vec2 image](SocketType::IVec2) of UV coordinates, e.g. to scroll a texture.
+//!
+//! Mandatory inputs:
+//! - uv: IVec2
+//!
+//! Optional inputs:
+//! - offset: Vec2, added to every UV coordinate, default is (0, 0)
+//!
+//! Output:
+//! - uv: IVec2
+
+use crate::handle_missing_socket_values;
+
+use super::{GraphResult, NodeResult};
+
+use eray::{
+    get_sv, node,
+    prelude::*,
+    shader::{
+        self,
+        graph::{SocketType, SocketValue},
+    },
+    ssref,
+};
+
+/// Get a wrapping [Graph](eray::shader::graph::Graph) containing the node.
+pub fn graph() -> GraphResult {
+    Ok(shader::graph::graph! {
+        inputs:
+            // Mandatory
+            "uv": SocketType::IVec2.into(),
+
+            // Optional
+            "offset": SocketValue::Vec2(Some(Vector::default())),
+        nodes:
+            "offset": {
+                let mut node = node()?;
+                node.set_input(&"uv".into(), ssref!(graph "uv"))?
+                    .set_input(&"offset".into(), ssref!(graph "offset"))?;
+                node
+            },
+        outputs:
+            "uv": (ssref!(node "offset" "uv"), SocketType::IVec2.into()),
+    })
+}
+
+/// Get the [node](eray::shader::graph::Node::Graph) by itself.
+pub fn node() -> NodeResult {
+    Ok(node! {
+        inputs:
+            "uv": (None, SocketType::IVec2),
+
+            "offset": (None, SocketType::Vec2),
+        outputs:
+            "uv": SocketType::IVec2.into();
+        |inputs, outputs| {
+            get_sv!( input | inputs  . "uv": IVec2 > uv);
+
+            get_sv!( input | inputs  . "offset": Vec2 > offset);
+
+            get_sv!(output | outputs . "uv": IVec2 > out);
+
+            handle_missing_socket_values![uv];
+            let offset = offset.unwrap_or_default();
+
+            out.replace(uv.map(|&v| v + offset));
+
+            Ok(())
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::shaderlib::utils::run_graph;
+
+    #[test]
+    fn offset() {
+        let uv = Image {
+            width: 2,
+            height: 1,
+            pixels: vec![Vector::from([0., 0.]), Vector::from([0.5, 1.])],
+        };
+        let inputs = [
+            ("uv", SocketValue::IVec2(Some(uv))),
+            (
+                "offset",
+                SocketValue::Vec2(Some(Vector::from([0.25, -0.5]))),
+            ),
+        ];
+
+        let SocketValue::IVec2(Some(image)) = run_graph(graph().unwrap(), inputs, "uv") else {
+            panic!("Missing uv output")
+        };
+
+        assert_eq!(
+            vec![Vector::from([0.25, -0.5]), Vector::from([0.75, 0.5])],
+            image.pixels
+        );
+    }
+}
@@ -0,0 +1,147 @@
+//! Distort a [Color image](SocketType::IColor) by displacing its lookups with an offset map,
+//! e.g. for heat haze.
+//!
+//! Mandatory inputs:
+//! - color: IColor
+//! - offset: IVec2, UV-space displacement of each pixel's lookup, tiled over the color image
+//!
+//! Optional inputs:
+//! - strength: Value, multiplier applied to the offsets, default is `DEFAULT_STRENGTH`
+//!
+//! Output:
+//! - color: IColor, bilinearly sampled and tiling at the edges
+
+use crate::handle_missing_socket_values;
+
+use super::{GraphResult, NodeResult};
+
+use eray::{
+    get_sv, node,
+    prelude::*,
+    shader::{
+        self,
+        graph::{SocketType, SocketValue},
+    },
+    ssref,
+};
+
+pub const DEFAULT_STRENGTH: f32 = 1.;
+
+/// Get a wrapping [Graph](eray::shader::graph::Graph) containing the node.
+pub fn graph() -> GraphResult {
+    Ok(shader::graph::graph! {
+        inputs:
+            // Mandatory
+            "color": SocketType::IColor.into(),
+            "offset": SocketType::IVec2.into(),
+
+            // Optional
+            "strength": SocketValue::Value(Some(DEFAULT_STRENGTH)),
+        nodes:
+            "warp": {
+                let mut node = node()?;
+                node.set_input(&"color".into(), ssref!(graph "color"))?
+                    .set_input(&"offset".into(), ssref!(graph "offset"))?
+                    .set_input(&"strength".into(), ssref!(graph "strength"))?;
+                node
+            },
+        outputs:
+            "color": (ssref!(node "warp" "color"), SocketType::IColor.into()),
+    })
+}
+
+/// Get the [node](eray::shader::graph::Node::Graph) by itself.
+pub fn node() -> NodeResult {
+    Ok(node! {
+        inputs:
+            "color": (None, SocketType::IColor),
+            "offset": (None, SocketType::IVec2),
+
+            "strength": (None, SocketType::Value),
+        outputs:
+            "color": SocketType::IColor.into();
+        |inputs, outputs| {
+            get_sv!( input | inputs  . "color": IColor > color);
+            get_sv!( input | inputs  . "offset": IVec2 > offset);
+
+            get_sv!( input | inputs  . "strength": Value > strength);
+
+            get_sv!(output | outputs . "color": IColor > out);
+
+            handle_missing_socket_values![color, offset];
+            let strength = strength.unwrap_or(DEFAULT_STRENGTH);
+
+            let sampler = Sampler::new(Filter::Bilinear);
+            let (width, height) = (color.width as f32, color.height as f32);
+
+            let mut warped = Image::new(color.width, color.height, Color::default());
+            for (x, y, pixel) in warped.enumerate_pixels_mut() {
+                // Sample at texel centers so that a zero offset is lossless
+                let uv = Vector::from([(x as f32 + 0.5) / width, (y as f32 + 0.5) / height]);
+                let displacement = offset.mod_get(x, y) * strength;
+
+                *pixel = sampler.sample(color, uv + displacement, &Footprint::default());
+            }
+
+            out.replace(warped);
+
+            Ok(())
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::shaderlib::utils::run_graph;
+
+    fn checker() -> Image<Color> {
+        let mut image = Image::new(4, 2, Color::default());
+        for (x, y, pixel) in image.enumerate_pixels_mut() {
+            *pixel = Color::new(x as f32 / 3., y as f32, ((x + y) % 2) as f32);
+        }
+        image
+    }
+
+    fn run(color: Image<Color>, offset: Vector<2, f32>) -> Image<Color> {
+        let inputs = [
+            ("color", SocketValue::IColor(Some(color))),
+            ("offset", SocketValue::IVec2(Some(Image::new(1, 1, offset)))),
+        ];
+
+        let SocketValue::IColor(Some(image)) = run_graph(graph().unwrap(), inputs, "color") else {
+            panic!("Missing warp output")
+        };
+
+        image
+    }
+
+    #[test]
+    fn zero_offset() {
+        let input = checker();
+        let res = run(input.clone(), Vector::default());
+
+        for (got, expected) in res.pixels.iter().zip(input.pixels.iter()) {
+            assert!(
+                got.approx_eq(expected, 1e-6),
+                "Expected {expected:?}, got {got:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn constant_offset() {
+        let input = checker();
+
+        // One texel to the right and one down, wrapping around
+        let res = run(input.clone(), Vector::from([0.25, 0.5]));
+
+        for (x, y, got) in res.enumerate_pixels() {
+            let expected = input.mod_get((x + 1) % 4, (y + 1) % 2);
+            assert!(
+                got.approx_eq(&expected, 1e-6),
+                "Expected {expected:?} at ({x}, {y}), got {got:?}"
+            );
+        }
+    }
+}
@@ -1,5 +1,7 @@
 //! Sine wave.
 //!
+//! Frequencies are rounded to fit a whole number of bumps in the image so that it tiles.
+//!
 //! Mandatory inputs:
 //! - width: Value, width of the output image
 //! - height: Value, height of the output image
@@ -7,9 +9,10 @@
 //! Optional inputs:
 //! - x_fac: Value, multiplier for x direction, default is 1.
 //! - y_fac: Value, multiplier for y direction, default is 1.
+//! - time: Value, phase shift used to scroll the wave, default is 0.
 //!
 //! Output:
-//! - value: Value
+//! - value: IValue
 
 use crate::handle_missing_socket_values;
 
@@ -28,10 +31,28 @@ use eray::{
 
 use map_macro::hash_map;
 
+use std::f32::consts::PI;
+
 pub const DEFAULT_FACTOR: f32 = 1.;
 
+/// Number of bumps along a `size` pixels long axis, each pixel advancing the phase by a tenth of
+/// `factor` radians, rounded so that the wave wraps around seamlessly.
+///
+/// A zero `factor` gives no bumps, leaving the axis constant, while others keep their sign and
+/// give at least one bump.
+fn bumps(size: u32, factor: f32) -> f32 {
+    if factor == 0. {
+        return 0.;
+    }
+
+    (size as f32 * factor.abs() / (10. * PI))
+        .round()
+        .max(1.)
+        .copysign(factor)
+}
+
 pub fn material() -> MaterialResult {
-    Ok(Material::from((
+    Material::try_from((
         shader::graph::graph! {
             inputs:
                 // Mandatory
@@ -41,6 +62,7 @@ pub fn material() -> MaterialResult {
                 // Optional
                 "x_fac": SocketValue::Value(Some(DEFAULT_FACTOR)),
                 "y_fac": SocketValue::Value(Some(DEFAULT_FACTOR)),
+                "time": SocketValue::Value(Some(0.)),
             nodes:
                 "inner": {
                     let map = hash_map!{
@@ -51,7 +73,8 @@ pub fn material() -> MaterialResult {
                     node.set_input(&"width".into(), ssref!(graph "width"))?
                         .set_input(&"height".into(), ssref!(graph "height"))?
                         .set_input(&"x_fac".into(), ssref!(graph "x_fac"))?
-                        .set_input(&"y_fac".into(), ssref!(graph "y_fac"))?;
+                        .set_input(&"y_fac".into(), ssref!(graph "y_fac"))?
+                        .set_input(&"time".into(), ssref!(graph "time"))?;
                     node
                 },
                 "viewer": {
@@ -70,7 +93,7 @@ pub fn material() -> MaterialResult {
         hash_map! {
             StandardMaterialOutput::Color => "color".into(),
         },
-    )))
+    ))
 }
 
 pub fn graph() -> GraphResult {
@@ -83,17 +106,19 @@ pub fn graph() -> GraphResult {
             // Optional
             "x_fac": SocketValue::Value(Some(DEFAULT_FACTOR)),
             "y_fac": SocketValue::Value(Some(DEFAULT_FACTOR)),
+            "time": SocketValue::Value(Some(0.)),
         nodes:
             "wave": {
                 let mut node = node()?;
                 node.set_input(&"width".into(), ssref!(graph "width"))?
                     .set_input(&"height".into(), ssref!(graph "height"))?
                     .set_input(&"x_fac".into(), ssref!(graph "x_fac"))?
-                    .set_input(&"y_fac".into(), ssref!(graph "y_fac"))?;
+                    .set_input(&"y_fac".into(), ssref!(graph "y_fac"))?
+                    .set_input(&"time".into(), ssref!(graph "time"))?;
                 node
             },
         outputs:
-            "value": (ssref!(node "wave" "value"), SocketType::Value.into()),
+            "value": (ssref!(node "wave" "value"), SocketType::IValue.into()),
     })
 }
 
@@ -105,6 +130,8 @@ pub fn node() -> NodeResult {
 
             "x_fac": (None, SocketType::Value),
             "y_fac": (None, SocketType::Value),
+
+            "time": (None, SocketType::Value),
         outputs:
             "value": SocketType::IValue.into();
         |inputs, outputs| {
@@ -114,19 +141,23 @@ pub fn node() -> NodeResult {
             get_sv!( input | inputs  . "x_fac": Value > x_fac);
             get_sv!( input | inputs  . "y_fac": Value > y_fac);
 
+            get_sv!( input | inputs  . "time": Value > time);
+
             get_sv!(output | outputs . "value": IValue > out);
 
             handle_missing_socket_values![width, height];
             let x_fac = x_fac.unwrap_or(DEFAULT_FACTOR);
             let y_fac = y_fac.unwrap_or(DEFAULT_FACTOR);
+            let time = time.unwrap_or(0.);
 
-            let mut res = Image::new(*width as u32, *height as u32, 0.);
+            let (width, height) = (*width as u32, *height as u32);
+            let (x_bumps, y_bumps) = (bumps(width, x_fac), bumps(height, y_fac));
 
-            for y in 0..(res.height) {
-                for x in 0..(res.width) {
-                    let value = ((x as f32 * x_fac + y as f32 * y_fac) / 10.).cos().abs();
-                    res.pixels[(y * res.width + x) as usize] = value;
-                }
+            let mut res = Image::new(width, height, 0.);
+
+            for (x, y, pixel) in res.enumerate_pixels_mut() {
+                let phase = x as f32 * x_bumps / width as f32 + y as f32 * y_bumps / height as f32;
+                *pixel = (phase * PI + time).cos().abs();
             }
 
             out.replace(res);
@@ -135,3 +166,76 @@ pub fn node() -> NodeResult {
         }
     })
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::shaderlib::utils::{assert_tiles_seamlessly, run_graph};
+    use eray::shader::Signature;
+
+    #[test]
+    fn tiles_seamlessly() {
+        for (x_fac, y_fac) in [(1., 1.), (2., 0.5), (3.3, 0.)] {
+            let inputs = [
+                ("width", 64.),
+                ("height", 32.),
+                ("x_fac", x_fac),
+                ("y_fac", y_fac),
+            ]
+            .map(|(name, value)| (name, SocketValue::Value(Some(value))));
+
+            let SocketValue::IValue(Some(image)) = run_graph(graph().unwrap(), inputs, "value")
+            else {
+                panic!("Missing wave output")
+            };
+
+            assert_tiles_seamlessly(&image.into(), 1e-5);
+        }
+    }
+
+    #[test]
+    fn bumps_keep_sign() {
+        assert_eq!(0., bumps(64, 0.));
+        assert_eq!(1., bumps(64, 0.1));
+        assert_eq!(-1., bumps(64, -0.1));
+        assert_eq!(-bumps(64, 3.3), bumps(64, -3.3));
+    }
+
+    #[test]
+    fn zero_factor_is_constant() {
+        let inputs = [("width", 16.), ("height", 8.), ("x_fac", 2.), ("y_fac", 0.)]
+            .map(|(name, value)| (name, SocketValue::Value(Some(value))));
+
+        let SocketValue::IValue(Some(image)) = run_graph(graph().unwrap(), inputs, "value") else {
+            panic!("Missing wave output")
+        };
+
+        let first = &image.pixels[..image.width as usize];
+        assert!(
+            first.iter().any(|&value| value != first[0]),
+            "Expected stripes along x"
+        );
+        for row in image.pixels.chunks_exact(image.width as usize) {
+            assert_eq!(first, row);
+        }
+    }
+
+    #[test]
+    fn signature() {
+        let value = |name: &str| (name.into(), SocketType::Value);
+
+        assert_eq!(
+            graph().unwrap().validate().unwrap().signature(),
+            Signature::new(
+                [
+                    value("width"),
+                    value("height"),
+                    value("x_fac"),
+                    value("y_fac"),
+                    value("time")
+                ],
+                [("value".into(), SocketType::IValue)],
+            )
+        );
+    }
+}